@@ -0,0 +1,59 @@
+use std::io::{self, BufRead, Write};
+
+use crate::error::{SaftError, SaftResult};
+use crate::provider::ToolCall;
+
+/// Gate consulted before every model-requested tool call runs. Denied calls are
+/// reported back to the model as a tool result instead of being executed.
+pub trait ToolApprover {
+    fn approve(&mut self, call: &ToolCall) -> SaftResult<bool>;
+}
+
+/// Prints each tool call (name + JSON args) and waits for a `y`/`yes` answer.
+/// Anything else, including end of input, denies the call.
+pub struct PromptingToolApprover {
+    /// `None` reads from stdin's shared buffer, one line per answer.
+    input: Option<Box<dyn BufRead>>,
+    output: Box<dyn Write>,
+}
+
+impl PromptingToolApprover {
+    pub fn new(input: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
+        Self {
+            input: Some(input),
+            output,
+        }
+    }
+
+    /// Reads answers from stdin and prints requests to stderr so stdout stays script output.
+    /// Lines after an answer stay in stdin's own buffer for `input()`.
+    pub fn stdio() -> Self {
+        Self {
+            input: None,
+            output: Box::new(io::stderr()),
+        }
+    }
+}
+
+impl ToolApprover for PromptingToolApprover {
+    fn approve(&mut self, call: &ToolCall) -> SaftResult<bool> {
+        write!(
+            self.output,
+            "tool call requested: {}({})\napprove? [y/N] ",
+            call.name, call.args
+        )
+        .and_then(|_| self.output.flush())
+        .map_err(|err| SaftError::new(format!("failed to write tool approval prompt: {err}")))?;
+
+        let mut answer = String::new();
+        match &mut self.input {
+            Some(input) => input.read_line(&mut answer),
+            None => io::stdin().lock().read_line(&mut answer),
+        }
+        .map_err(|err| SaftError::new(format!("failed to read tool approval: {err}")))?;
+        Ok(matches!(
+            answer.trim().to_ascii_lowercase().as_str(),
+            "y" | "yes"
+        ))
+    }
+}
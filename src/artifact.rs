@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Program;
+use crate::error::{SaftError, SaftResult};
+use crate::hash::sha256_hex;
+use crate::{resolver, stdlib};
+
+/// File extension of compiled programs; `run` loads these instead of parsing.
+pub const ARTIFACT_EXTENSION: &str = "saftc";
+
+const ARTIFACT_FORMAT: &str = "orangensaft-program";
+
+/// A parsed and resolved program written by `orangensaft compile`.
+///
+/// The source is kept alongside the AST so runtime errors still render with
+/// snippets, and its hash lets `compile` skip artifacts that are up to date.
+/// Artifacts are only loaded by the exact version that wrote them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artifact {
+    pub format: String,
+    pub compiler: String,
+    pub source_sha256: String,
+    /// Names the program reads without defining, bound by `run --var`.
+    pub globals: Vec<String>,
+    pub source: String,
+    pub program: Program,
+}
+
+impl Artifact {
+    pub fn compile(source: &str, globals: &[&str]) -> SaftResult<Self> {
+        let program = crate::check_source_with_globals(source, globals)?;
+        Ok(Self {
+            format: ARTIFACT_FORMAT.to_string(),
+            compiler: env!("CARGO_PKG_VERSION").to_string(),
+            source_sha256: sha256_hex(source.as_bytes()),
+            globals: globals.iter().map(|name| name.to_string()).collect(),
+            source: source.to_string(),
+            program,
+        })
+    }
+
+    pub fn load(path: &Path) -> SaftResult<Self> {
+        let raw = fs::read_to_string(path)
+            .map_err(|err| SaftError::new(format!("failed to read '{}': {err}", path.display())))?;
+        Self::parse(&raw).map_err(|err| {
+            SaftError::new(format!(
+                "invalid artifact '{}': {}",
+                path.display(),
+                err.message
+            ))
+        })
+    }
+
+    /// Decodes an artifact and re-resolves its program, so a stale or
+    /// hand-edited file fails here rather than halfway through a run.
+    pub fn parse(raw: &str) -> SaftResult<Self> {
+        let artifact: Artifact = serde_json::from_str(raw)
+            .map_err(|err| SaftError::new(format!("not a compiled program ({err})")))?;
+        if artifact.format != ARTIFACT_FORMAT {
+            return Err(SaftError::new(format!(
+                "unknown format '{}'",
+                artifact.format
+            )));
+        }
+        if artifact.compiler != env!("CARGO_PKG_VERSION") {
+            return Err(SaftError::new(format!(
+                "compiled by orangensaft {}, this is {}; recompile it",
+                artifact.compiler,
+                env!("CARGO_PKG_VERSION")
+            )));
+        }
+        let mut names = stdlib::BUILTIN_NAMES.to_vec();
+        names.extend(artifact.globals.iter().map(String::as_str));
+        resolver::resolve(&artifact.program, &names)?;
+        Ok(artifact)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("artifact serializes to JSON")
+    }
+
+    /// Whether this artifact was compiled from `source` with the same globals
+    /// by this version, so recompiling would produce the same file.
+    pub fn is_current(&self, source: &str, globals: &[&str]) -> bool {
+        self.compiler == env!("CARGO_PKG_VERSION")
+            && self.source_sha256 == sha256_hex(source.as_bytes())
+            && self
+                .globals
+                .iter()
+                .map(String::as_str)
+                .eq(globals.iter().copied())
+    }
+}
+
+pub fn is_artifact_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext == ARTIFACT_EXTENSION)
+}
+
+/// `dir/script.saft` compiles to `dir/script.saftc` unless `-o` says otherwise.
+pub fn default_output(source_path: &str) -> PathBuf {
+    Path::new(source_path).with_extension(ARTIFACT_EXTENSION)
+}
@@ -1,12 +1,14 @@
+use serde::{Deserialize, Serialize};
+
 use crate::error::Span;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Program {
     pub stmts: Vec<Stmt>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Stmt {
     FnDef(FnDef),
     Assign {
@@ -55,7 +57,7 @@ impl Stmt {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FnDef {
     pub name: String,
     pub params: Vec<FnParam>,
@@ -64,20 +66,20 @@ pub struct FnDef {
     pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FnParam {
     pub name: String,
     pub schema: Option<SchemaExpr>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Pattern {
     Name(String),
     Tuple(Vec<String>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Expr {
     Int(i64, Span),
     Float(f64, Span),
@@ -146,13 +148,13 @@ impl Expr {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UnaryOp {
     Neg,
     Not,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BinaryOp {
     Add,
     Sub,
@@ -169,19 +171,21 @@ pub enum BinaryOp {
     Or,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptExpr {
     pub parts: Vec<PromptPart>,
+    /// Per-prompt overrides from `$ ... $ with {provider: ..., model: ...}`.
+    pub options: Option<Box<Expr>>,
     pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PromptPart {
     Text(String),
     Interpolation(Expr),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SchemaExpr {
     Any,
     Int,
@@ -190,12 +194,15 @@ pub enum SchemaExpr {
     String,
     List(Box<SchemaExpr>),
     Tuple(Vec<SchemaExpr>),
-    Object(Vec<SchemaField>),
+    Object(Vec<SchemaField>, Span),
+    /// `dataframe` (no fields) or `dataframe{col: type, ...}`; listed columns must
+    /// exist with a matching dtype, other columns are allowed.
+    DataFrame(Vec<SchemaField>),
     Union(Vec<SchemaExpr>),
     Optional(Box<SchemaExpr>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchemaField {
     pub name: String,
     pub schema: SchemaExpr,
@@ -0,0 +1,94 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use tokio::runtime::{Builder, Handle, Runtime as TokioRuntime};
+
+use crate::error::{SaftError, SaftResult};
+use crate::provider::{PromptProvider, PromptRequest, PromptResponse};
+
+pub type ProviderFuture<'a> = Pin<Box<dyn Future<Output = SaftResult<PromptResponse>> + 'a>>;
+
+/// Async counterpart of `PromptProvider` for providers that do non-blocking I/O.
+pub trait AsyncPromptProvider {
+    fn complete(&mut self, request: PromptRequest) -> ProviderFuture<'_>;
+
+    fn model(&self) -> Option<&str> {
+        None
+    }
+
+    fn temperature(&self) -> Option<f32> {
+        None
+    }
+}
+
+/// Exposes an existing sync provider through `AsyncPromptProvider`; the wrapped
+/// call still runs inline on the polling thread.
+pub struct SyncProviderAdapter {
+    inner: Box<dyn PromptProvider>,
+}
+
+impl SyncProviderAdapter {
+    pub fn new(inner: Box<dyn PromptProvider>) -> Self {
+        Self { inner }
+    }
+}
+
+impl AsyncPromptProvider for SyncProviderAdapter {
+    fn complete(&mut self, request: PromptRequest) -> ProviderFuture<'_> {
+        Box::pin(async move { self.inner.complete(request) })
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.inner.model()
+    }
+
+    fn temperature(&self) -> Option<f32> {
+        self.inner.temperature()
+    }
+}
+
+/// Drives an async provider from the interpreter's prompt loop by awaiting each
+/// request on a private current-thread tokio runtime. This is a bridge, not a
+/// source of concurrency: each request finishes before the script continues.
+/// Inside another tokio runtime (where tokio forbids nested `block_on`) it
+/// returns an error instead; run the interpreter via `spawn_blocking` there.
+pub struct BlockOnProvider {
+    inner: Box<dyn AsyncPromptProvider>,
+    executor: TokioRuntime,
+}
+
+impl BlockOnProvider {
+    pub fn new(inner: Box<dyn AsyncPromptProvider>) -> SaftResult<Self> {
+        reject_nested_runtime()?;
+        let executor = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| SaftError::new(format!("failed to start tokio runtime: {err}")))?;
+        Ok(Self { inner, executor })
+    }
+}
+
+impl PromptProvider for BlockOnProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        reject_nested_runtime()?;
+        self.executor.block_on(self.inner.complete(request))
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.inner.model()
+    }
+
+    fn temperature(&self) -> Option<f32> {
+        self.inner.temperature()
+    }
+}
+
+fn reject_nested_runtime() -> SaftResult<()> {
+    if Handle::try_current().is_ok() {
+        return Err(SaftError::new(
+            "async providers cannot be awaited from inside a tokio runtime; \
+             run the interpreter with spawn_blocking or on a plain thread",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,262 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{Value as JsonValue, json};
+
+use crate::error::{SaftError, SaftResult};
+use crate::hash::sha256_hex;
+use crate::provider::{PromptProvider, PromptRequest, PromptResponse, ToolCall};
+
+/// Where `--cache` keeps responses, relative to the project root.
+pub const DEFAULT_CACHE_DIR: &str = ".orangensaft/cache";
+
+/// On-disk prompt-response cache: one JSON file per request, named by the
+/// SHA-256 of the provider, model, temperature, and full request (tool results
+/// and image bytes included), so a changed prompt, tool output, or sampling
+/// setting is a miss.
+#[derive(Debug, Clone)]
+pub struct PromptCache {
+    dir: PathBuf,
+}
+
+/// Summary of one cached response, as listed by `orangensaft cache ls`.
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub key: String,
+    pub provider: String,
+    pub model: Option<String>,
+    pub prompt: String,
+    pub created_at_ms: u64,
+    pub bytes: u64,
+}
+
+impl PromptCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn key(
+        provider: &str,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        request: &PromptRequest,
+    ) -> String {
+        let identity = json!({
+            "provider": provider,
+            "model": model,
+            "temperature": temperature,
+            "prompt": request.prompt,
+            "images": request
+                .images
+                .iter()
+                .map(|image| json!([image.media_type, sha256_hex(image.data_base64.as_bytes())]))
+                .collect::<Vec<_>>(),
+            "tools": request
+                .tools
+                .iter()
+                .map(|tool| json!([tool.name, tool.param_names]))
+                .collect::<Vec<_>>(),
+            "tool_results": request
+                .tool_results
+                .iter()
+                .map(|result| json!([result.id, result.name, result.args, result.output]))
+                .collect::<Vec<_>>(),
+            "response_format": request
+                .response_format
+                .as_ref()
+                .map(|format| json!([format.name, format.schema])),
+        });
+        sha256_hex(identity.to_string().as_bytes())
+    }
+
+    /// The cached response for `key`; unreadable or malformed entries count as misses.
+    pub fn get(&self, key: &str) -> Option<PromptResponse> {
+        let raw = fs::read_to_string(self.entry_path(key)).ok()?;
+        let entry: JsonValue = serde_json::from_str(&raw).ok()?;
+        response_from_json(entry.get("response")?)
+    }
+
+    pub fn put(
+        &self,
+        key: &str,
+        provider: &str,
+        model: Option<&str>,
+        request: &PromptRequest,
+        response: &PromptResponse,
+    ) -> SaftResult<()> {
+        fs::create_dir_all(&self.dir).map_err(|err| self.error("create", &self.dir, err))?;
+        let entry = json!({
+            "provider": provider,
+            "model": model,
+            "prompt": request.prompt,
+            "created_at_ms": now_ms(),
+            "response": response_to_json(response),
+        });
+        let path = self.entry_path(key);
+        fs::write(&path, entry.to_string()).map_err(|err| self.error("write", &path, err))
+    }
+
+    /// Every entry, oldest first; an absent directory is an empty cache.
+    pub fn entries(&self) -> SaftResult<Vec<CacheEntry>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(self.error("read", &self.dir, err)),
+        };
+        let mut entries = Vec::new();
+        for item in dir {
+            let path = item
+                .map_err(|err| self.error("read", &self.dir, err))?
+                .path();
+            let Some(key) = cache_key(&path) else {
+                continue;
+            };
+            let raw = fs::read_to_string(&path).map_err(|err| self.error("read", &path, err))?;
+            let entry: JsonValue = serde_json::from_str(&raw).unwrap_or_default();
+            let text = |field: &str| entry.get(field).and_then(JsonValue::as_str);
+            entries.push(CacheEntry {
+                key,
+                provider: text("provider").unwrap_or("?").to_string(),
+                model: text("model").map(str::to_string),
+                prompt: text("prompt").unwrap_or_default().to_string(),
+                created_at_ms: entry
+                    .get("created_at_ms")
+                    .and_then(JsonValue::as_u64)
+                    .unwrap_or(0),
+                bytes: raw.len() as u64,
+            });
+        }
+        entries.sort_by(|left, right| {
+            (left.created_at_ms, &left.key).cmp(&(right.created_at_ms, &right.key))
+        });
+        Ok(entries)
+    }
+
+    /// Deletes entries created more than `older_than_ms` ago (all of them when
+    /// `None`) and returns how many were removed.
+    pub fn clear(&self, older_than_ms: Option<u64>) -> SaftResult<usize> {
+        let cutoff = older_than_ms.map(|age| now_ms().saturating_sub(age));
+        let mut removed = 0;
+        for entry in self.entries()? {
+            if cutoff.is_some_and(|cutoff| entry.created_at_ms >= cutoff) {
+                continue;
+            }
+            let path = self.entry_path(&entry.key);
+            fs::remove_file(&path).map_err(|err| self.error("remove", &path, err))?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+
+    fn error(&self, action: &str, path: &Path, err: std::io::Error) -> SaftError {
+        SaftError::new(format!(
+            "could not {action} prompt cache '{}': {err}",
+            path.display()
+        ))
+    }
+}
+
+/// Provider wrapper that answers repeated requests from a `PromptCache` and
+/// stores every fresh successful response in it.
+pub struct CachingProvider {
+    inner: Box<dyn PromptProvider>,
+    cache: PromptCache,
+    provider: String,
+}
+
+impl CachingProvider {
+    /// `provider` names the wrapped provider so identical prompts sent to
+    /// different backends do not share entries.
+    pub fn new(inner: Box<dyn PromptProvider>, cache: PromptCache, provider: &str) -> Self {
+        Self {
+            inner,
+            cache,
+            provider: provider.to_string(),
+        }
+    }
+}
+
+impl PromptProvider for CachingProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        let model = request
+            .model
+            .clone()
+            .or_else(|| self.inner.model().map(str::to_string));
+        let key = PromptCache::key(
+            &self.provider,
+            model.as_deref(),
+            self.inner.temperature(),
+            &request,
+        );
+        if let Some(response) = self.cache.get(&key) {
+            return Ok(response);
+        }
+        let response = self.inner.complete(request.clone())?;
+        self.cache
+            .put(&key, &self.provider, model.as_deref(), &request, &response)?;
+        Ok(response)
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.inner.model()
+    }
+
+    fn temperature(&self) -> Option<f32> {
+        self.inner.temperature()
+    }
+}
+
+fn cache_key(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let is_key = path.extension().is_some_and(|ext| ext == "json")
+        && stem.len() == 64
+        && stem.bytes().all(|byte| byte.is_ascii_hexdigit());
+    is_key.then(|| stem.to_string())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn response_to_json(response: &PromptResponse) -> JsonValue {
+    match response {
+        PromptResponse::FinalText(text) => json!({ "final_text": text }),
+        PromptResponse::ToolCalls(calls) => json!({
+            "tool_calls": calls
+                .iter()
+                .map(|call| json!({ "id": call.id, "name": call.name, "args": call.args }))
+                .collect::<Vec<_>>(),
+        }),
+    }
+}
+
+fn response_from_json(value: &JsonValue) -> Option<PromptResponse> {
+    if let Some(text) = value.get("final_text").and_then(JsonValue::as_str) {
+        return Some(PromptResponse::FinalText(text.to_string()));
+    }
+    value
+        .get("tool_calls")?
+        .as_array()?
+        .iter()
+        .map(|call| {
+            Some(ToolCall {
+                id: call.get("id")?.as_str()?.to_string(),
+                name: call.get("name")?.as_str()?.to_string(),
+                args: call.get("args").cloned().unwrap_or(JsonValue::Null),
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(PromptResponse::ToolCalls)
+}
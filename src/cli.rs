@@ -1,354 +1,2138 @@
+use std::cell::Cell;
 use std::env;
 use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-use crate::error::SaftError;
-use crate::provider::{HeuristicMockProvider, NoopProvider, OpenRouterProvider, PromptProvider};
-use crate::runtime::RuntimeOptions;
+use serde_json::Value as JsonValue;
+
+use crate::approval::PromptingToolApprover;
+use crate::artifact::{self, Artifact, is_artifact_path};
+use crate::ast::Program;
+use crate::cache::{CachingProvider, DEFAULT_CACHE_DIR, PromptCache};
+use crate::config::{CONFIG_FILE, Config, ConfigValue};
+use crate::embedding::{
+    EmbeddingProvider, HashingEmbeddingProvider, NoopEmbeddingProvider, OllamaEmbeddingProvider,
+    OpenAiEmbeddingProvider,
+};
+use crate::error::{ErrorKind, SaftError, SaftResult};
+use crate::fixture::FixtureProvider;
+use crate::formatter::{self, FormatOptions};
+use crate::provider::{
+    HeuristicMockProvider, NoopProvider, OllamaProvider, OpenRouterProvider, ProbeCheck,
+    ProbeStatus, PromptProvider, PromptRequest, PromptResponse, UnavailableProvider,
+};
+use crate::rate_limit::{RateLimit, RateLimitedProvider, RateLimiter};
+use crate::runtime::{Runtime, RuntimeOptions};
+use crate::stdlib::{self, BuiltinHost};
+use crate::transcript::{TranscriptLog, TranscriptProvider};
+
+/// File argument that reads the script from stdin instead.
+const STDIN_PATH: &str = "-";
+
+/// Process exit codes, so wrappers can tell failure classes apart.
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_CHECK_ERROR: i32 = 3;
+pub const EXIT_RUNTIME_ERROR: i32 = 4;
+pub const EXIT_PROVIDER_ERROR: i32 = 5;
+pub const EXIT_ASSERTION_FAILED: i32 = 6;
+pub const EXIT_TIMEOUT: i32 = 7;
 
 pub fn run(args: Vec<String>) -> i32 {
     match parse_args(&args) {
         Ok(command) => {
             if let Err(err) = execute(command) {
-                eprintln!("{err}");
-                return 1;
+                eprintln!("{}", err.message);
+                return err.code;
             }
             0
         }
         Err(message) => {
             eprintln!("{message}");
-            1
+            EXIT_USAGE
+        }
+    }
+}
+
+/// A failed command: the message for stderr and the process exit code.
+#[derive(Debug)]
+struct CliError {
+    code: i32,
+    message: String,
+}
+
+impl CliError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
         }
     }
 }
 
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        Self::new(EXIT_FAILURE, message)
+    }
+}
+
 #[derive(Debug, Clone)]
 enum Command {
     Check {
-        file: String,
+        /// Files or directories (searched recursively for `*.saft`).
+        paths: Vec<String>,
+        /// `--autofmt`, with each file's `[fmt]` options from `orangensaft.toml`.
         autofmt: bool,
+        error_format: ErrorFormat,
+        /// `--var` names the resolver should treat as defined.
+        globals: Vec<String>,
     },
     Run {
         file: String,
-        provider: ProviderKind,
-        api_key_env: String,
-        model: Option<String>,
-        temperature: Option<f32>,
-        max_tool_rounds: usize,
-        max_tool_calls: usize,
-        autofmt: bool,
+        options: Box<RunOptions>,
+        approve_tools: bool,
+        /// `--autofmt`, with the `[fmt]` options from `orangensaft.toml`.
+        autofmt: Option<FormatOptions>,
+        error_format: ErrorFormat,
+        script_args: Vec<String>,
+        /// `--var` bindings, already converted to their declared type.
+        vars: Vec<(String, JsonValue)>,
     },
     Fmt {
-        file: String,
+        /// Files or directories (searched recursively for `*.saft`).
+        paths: Vec<String>,
         write: bool,
         check: bool,
+        overrides: FormatOverrides,
+    },
+    /// Parse and resolve one script into a `.saftc` artifact that `run` loads.
+    Compile {
+        file: String,
+        /// Defaults to the script path with the `.saftc` extension.
+        output: Option<String>,
+        /// Names from `--var` (no value needed), bound when the artifact runs.
+        globals: Vec<String>,
+        force: bool,
+        error_format: ErrorFormat,
+    },
+    Init {
+        dir: String,
+    },
+    Doc {
+        /// A single builtin to describe; every builtin when `None`.
+        name: Option<String>,
+    },
+    Bench {
+        file: String,
+        iterations: usize,
+        /// Mock, fixture, or none: bench runs must not depend on a live model.
+        provider: ProviderKind,
+        seed: u64,
+        script_args: Vec<String>,
+    },
+    Cache {
+        action: CacheAction,
+    },
+    /// `providers test`: probe setup of the default and named providers.
+    Providers {
+        options: Box<RunOptions>,
+        /// Only probe this provider (`--provider`) instead of every known one.
+        only: bool,
+    },
+    /// `--help`/`--version` output, printed to stdout.
+    Help(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CacheAction {
+    List,
+    Stats,
+    /// Removes every entry, or only those older than the given number of days.
+    Clear {
+        older_than_days: Option<u64>,
     },
 }
 
+/// How `check`/`run` print diagnostics: caret rendering or one JSON object.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum ProviderKind {
     Mock,
     OpenRouter,
+    Ollama,
+    Fixture(String),
     None,
 }
 
-fn parse_args(args: &[String]) -> Result<Command, String> {
-    if args.len() < 2 {
-        return Err(usage(
-            args.first().map(String::as_str).unwrap_or("orangensaft"),
-        ));
+impl ProviderKind {
+    /// The `--provider` spelling, which also namespaces prompt cache entries.
+    fn label(&self) -> String {
+        match self {
+            ProviderKind::Mock => "mock".to_string(),
+            ProviderKind::OpenRouter => "openrouter".to_string(),
+            ProviderKind::Ollama => "ollama".to_string(),
+            ProviderKind::Fixture(path) => format!("fixture:{path}"),
+            ProviderKind::None => "none".to_string(),
+        }
     }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EmbeddingProviderKind {
+    Mock,
+    OpenAi,
+    OpenRouter,
+    Ollama,
+    None,
+}
+
+/// A flag accepted by a subcommand. The `COMMANDS` table drives parsing,
+/// `--help` output, and suggestions for misspelled flags.
+struct FlagSpec {
+    long: &'static str,
+    short: Option<&'static str>,
+    /// Value placeholder shown in help (`N`, `PATH`); `None` for switches.
+    value: Option<&'static str>,
+    help: &'static str,
+}
 
-    match args[1].as_str() {
-        "check" => parse_check_command(args),
-        "fmt" => parse_fmt_command(args),
-        "run" => parse_run_command(args, 2, 3),
-        _ => parse_run_command(args, 1, 2),
+const fn switch(long: &'static str, help: &'static str) -> FlagSpec {
+    FlagSpec {
+        long,
+        short: None,
+        value: None,
+        help,
     }
 }
 
-fn parse_check_command(args: &[String]) -> Result<Command, String> {
-    let bin_name = args.first().map(String::as_str).unwrap_or("orangensaft");
-    if args.len() < 3 {
-        return Err(format!("missing file path\n{}", usage(bin_name)));
+const fn valued(long: &'static str, value: &'static str, help: &'static str) -> FlagSpec {
+    FlagSpec {
+        long,
+        short: None,
+        value: Some(value),
+        help,
     }
-    let file = args[2].clone();
-    let mut autofmt = false;
-    let mut i = 3;
-    while i < args.len() {
-        match args[i].as_str() {
-            "--autofmt" => {
-                autofmt = true;
-                i += 1;
+}
+
+struct CommandSpec {
+    name: &'static str,
+    /// Usage lines, without the leading binary name.
+    usage: &'static [&'static str],
+    about: &'static str,
+    flags: &'static [FlagSpec],
+    /// Flags that cannot be combined.
+    conflicts: &'static [(&'static str, &'static str)],
+    /// Whether words after `--` are passed through (as script arguments).
+    takes_rest: bool,
+}
+
+const ERROR_FORMAT_FLAG: FlagSpec = valued(
+    "--error-format",
+    "FORMAT",
+    "print diagnostics as human (default) or json",
+);
+const AUTOFMT_FLAG: FlagSpec = switch(
+    "--autofmt",
+    "format the source in memory before checking (the file is not changed)",
+);
+const VAR_FLAG: FlagSpec = valued(
+    "--var",
+    "NAME[:TYPE]=VALUE",
+    "bind a global; TYPE is string (default), int, float, bool, or json",
+);
+
+const RUN_FLAGS: &[FlagSpec] = &[
+    valued(
+        "--provider",
+        "NAME",
+        "mock, openrouter, ollama, fixture:PATH, or none",
+    ),
+    valued(
+        "--api-key-env",
+        "ENV",
+        "env var holding the provider API key",
+    ),
+    valued("--model", "NAME", "model to request from the provider"),
+    valued("--temperature", "N", "sampling temperature"),
+    valued("--max-tool-rounds", "N", "tool rounds allowed per prompt"),
+    valued("--max-tool-calls", "N", "tool calls allowed per prompt"),
+    valued(
+        "--embedding-provider",
+        "NAME",
+        "mock, openai, openrouter, ollama, or none",
+    ),
+    valued("--embedding-model", "NAME", "model used by embed()"),
+    valued(
+        "--transcript",
+        "PATH",
+        "write every provider round trip to a JSONL file (replaced each run; cache hits are not logged)",
+    ),
+    valued("--requests-per-minute", "N", "throttle provider requests"),
+    valued(
+        "--max-concurrent-requests",
+        "N",
+        "cap provider requests in flight",
+    ),
+    valued("--tool-timeout-ms", "N", "time limit for each tool call"),
+    valued("--timeout", "SECONDS", "stop the whole run after this long"),
+    valued("--seed", "N", "make the random builtins reproducible"),
+    switch("--allow-net", "let the script use the network builtins"),
+    switch("--allow-exec", "let the script run external commands"),
+    switch(
+        "--approve-tools",
+        "ask before each tool call the model makes",
+    ),
+    FlagSpec {
+        long: "--verbose",
+        short: Some("-v"),
+        value: None,
+        help: "log prompt rounds, tool calls, and provider latency to stderr",
+    },
+    switch(
+        "--cache",
+        "reuse provider responses from the project's prompt cache",
+    ),
+    switch("--no-cache", "ignore a cache = true project setting"),
+    switch(
+        "--profile",
+        "print time per statement, function, and prompt round after the run",
+    ),
+    switch(
+        "--autofmt",
+        "format the script in memory before running it (the file is not changed)",
+    ),
+    ERROR_FORMAT_FLAG,
+    VAR_FLAG,
+];
+
+const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "run",
+        usage: &[
+            "run <file.saft> [options] [-- script args...]",
+            "<file.saft> [options] [-- script args...]",
+        ],
+        about: "Run a script, sending its prompts to the configured provider.\n\
+                Use - as <file.saft> to read the script from stdin.",
+        flags: RUN_FLAGS,
+        conflicts: &[("--cache", "--no-cache")],
+        takes_rest: true,
+    },
+    CommandSpec {
+        name: "check",
+        usage: &["check <file.saft|dir>... [options]"],
+        about: "Parse and resolve scripts without running them.",
+        flags: &[AUTOFMT_FLAG, ERROR_FORMAT_FLAG, VAR_FLAG],
+        conflicts: &[],
+        takes_rest: false,
+    },
+    CommandSpec {
+        name: "fmt",
+        usage: &[
+            "fmt <file.saft|dir>... [--write|--check]",
+            "fmt --stdin [--check]",
+        ],
+        about: "Format scripts, printing the result unless --write or --check is given.",
+        flags: &[
+            switch("--write", "write formatted output back to each file"),
+            switch("--check", "report every file that is not already formatted"),
+            switch("--stdin", "format source piped on stdin and print it"),
+            valued(
+                "--max-width",
+                "N",
+                "wrap calls, lists, and objects on lines longer than this (default 100)",
+            ),
+            valued(
+                "--max-blank-lines",
+                "N",
+                "blank lines kept in a row between statements (default 1)",
+            ),
+            valued(
+                "--indent-width",
+                "N",
+                "spaces per indentation level (default 4)",
+            ),
+        ],
+        conflicts: &[("--write", "--check")],
+        takes_rest: false,
+    },
+    CommandSpec {
+        name: "compile",
+        usage: &["compile <file.saft> [options]"],
+        about: "Parse and resolve a script into a .saftc artifact that run loads without\n\
+                re-parsing. An artifact already compiled from the same source is left as is.",
+        flags: &[
+            FlagSpec {
+                long: "--output",
+                short: Some("-o"),
+                value: Some("FILE"),
+                help: "where to write the artifact (default: <file>.saftc)",
+            },
+            switch("--force", "recompile even if the artifact is up to date"),
+            ERROR_FORMAT_FLAG,
+            VAR_FLAG,
+        ],
+        conflicts: &[],
+        takes_rest: false,
+    },
+    CommandSpec {
+        name: "bench",
+        usage: &["bench <file.saft> [options] [-- script args...]"],
+        about: "Run a script repeatedly against a deterministic provider and report\n\
+                min/mean/max wall-clock time and prompt rounds (on stderr).",
+        flags: &[
+            valued("--iterations", "N", "number of timed runs (default 10)"),
+            valued(
+                "--provider",
+                "NAME",
+                "mock (default), fixture:PATH, or none",
+            ),
+            valued("--seed", "N", "seed for the random builtins (default 0)"),
+        ],
+        conflicts: &[],
+        takes_rest: true,
+    },
+    CommandSpec {
+        name: "cache",
+        usage: &["cache ls|stats|clear [--older-than DAYS]"],
+        about: "Inspect or prune the prompt cache that `run --cache` fills\n\
+                (.orangensaft/cache next to orangensaft.toml, or ORANGENSAFT_CACHE_DIR).\n\
+                Entries are keyed by provider, model, temperature, prompt, images,\n\
+                tools, tool results, and response schema.",
+        flags: &[valued(
+            "--older-than",
+            "DAYS",
+            "with clear: only remove entries older than this",
+        )],
+        conflicts: &[],
+        takes_rest: false,
+    },
+    CommandSpec {
+        name: "providers",
+        usage: &["providers test [options]"],
+        about: "Check that providers are set up (API key present, endpoint reachable,\n\
+                model available) and print a status table. Defaults come from\n\
+                orangensaft.toml and ORANGENSAFT_* like for run.",
+        flags: &[
+            valued(
+                "--provider",
+                "NAME",
+                "only test this provider (mock, openrouter, ollama, fixture:PATH, or none)",
+            ),
+            valued(
+                "--model",
+                "NAME",
+                "model to look for on the default provider",
+            ),
+            valued(
+                "--api-key-env",
+                "ENV",
+                "env var holding the OpenRouter API key",
+            ),
+        ],
+        conflicts: &[],
+        takes_rest: false,
+    },
+    CommandSpec {
+        name: "init",
+        usage: &["init [dir]"],
+        about: "Scaffold a starter project (never overwrites existing files).",
+        flags: &[],
+        conflicts: &[],
+        takes_rest: false,
+    },
+    CommandSpec {
+        name: "doc",
+        usage: &["doc [builtin]"],
+        about: "List the builtins, or describe one.",
+        flags: &[],
+        conflicts: &[],
+        takes_rest: false,
+    },
+];
+
+fn command_spec(name: &str) -> &'static CommandSpec {
+    COMMANDS
+        .iter()
+        .find(|spec| spec.name == name)
+        .expect("command spec should exist")
+}
+
+/// Command-line words split against a `CommandSpec`.
+#[derive(Debug, Default)]
+struct ParsedArgs {
+    /// `(long name, value)` pairs in the order given.
+    flags: Vec<(&'static str, Option<String>)>,
+    positionals: Vec<String>,
+    /// Words after `--`.
+    rest: Vec<String>,
+    help: bool,
+}
+
+impl ParsedArgs {
+    fn has(&self, long: &str) -> bool {
+        self.flags.iter().any(|(name, _)| *name == long)
+    }
+}
+
+fn split_args(spec: &CommandSpec, args: &[String], bin_name: &str) -> Result<ParsedArgs, String> {
+    let mut parsed = ParsedArgs::default();
+    let mut words = args.iter();
+    while let Some(word) = words.next() {
+        if word == "--" {
+            if !spec.takes_rest {
+                return Err(format!("{} does not take arguments after --", spec.name));
+            }
+            parsed.rest = words.by_ref().cloned().collect();
+            break;
+        }
+        if word == "-h" || word == "--help" {
+            parsed.help = true;
+            continue;
+        }
+        if word == STDIN_PATH || !word.starts_with('-') {
+            parsed.positionals.push(word.clone());
+            continue;
+        }
+
+        let (name, inline) = match word.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (word.as_str(), None),
+        };
+        let Some(flag) = spec
+            .flags
+            .iter()
+            .find(|flag| flag.long == name || flag.short == Some(name))
+        else {
+            let hint = suggest(name, spec.flags.iter().map(|flag| flag.long))
+                .map(|flag| format!(" (did you mean '{flag}'?)"))
+                .unwrap_or_default();
+            return Err(format!(
+                "unknown option '{name}' for {}{hint}\nRun '{bin_name} {} --help' for usage.",
+                spec.name, spec.name
+            ));
+        };
+        let value = match (flag.value, inline) {
+            (None, None) => None,
+            (None, Some(_)) => {
+                return Err(format!("option '{}' does not take a value", flag.long));
             }
-            other => return Err(format!("unknown option '{other}'\n{}", usage(bin_name))),
+            (Some(_), Some(value)) => Some(value),
+            (Some(_), None) => Some(
+                words
+                    .next()
+                    .cloned()
+                    .ok_or_else(|| format!("missing value for option '{}'", flag.long))?,
+            ),
+        };
+        parsed.flags.push((flag.long, value));
+    }
+
+    for (left, right) in spec.conflicts {
+        if parsed.has(left) && parsed.has(right) {
+            return Err(format!(
+                "{} options {left} and {right} cannot be used together",
+                spec.name
+            ));
+        }
+    }
+    Ok(parsed)
+}
+
+/// The closest candidate to a mistyped word, if any is close enough to be a typo.
+fn suggest<'a>(word: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let word = word.trim_start_matches('-');
+    candidates
+        .map(|candidate| {
+            (
+                edit_distance(word, candidate.trim_start_matches('-')),
+                candidate,
+            )
+        })
+        .filter(|(distance, _)| *distance <= (word.len() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right = right.chars().collect::<Vec<_>>();
+    let mut previous = (0..=right.len()).collect::<Vec<_>>();
+    for (i, left_ch) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, right_ch) in right.iter().enumerate() {
+            let substitute = previous[j] + usize::from(left_ch != *right_ch);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let bin_name = args
+        .first()
+        .and_then(|arg0| Path::new(arg0).file_name())
+        .map_or_else(
+            || "orangensaft".to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+    let Some(first) = args.get(1) else {
+        return Err(usage(&bin_name));
+    };
+
+    let (spec, rest) = match first.as_str() {
+        "-h" | "--help" => return Ok(Command::Help(usage(&bin_name))),
+        "-V" | "--version" => {
+            return Ok(Command::Help(format!(
+                "{bin_name} {}",
+                env!("CARGO_PKG_VERSION")
+            )));
         }
+        "help" => {
+            return match args.get(2) {
+                None => Ok(Command::Help(usage(&bin_name))),
+                Some(name) => match COMMANDS.iter().find(|spec| spec.name == name) {
+                    Some(spec) => Ok(Command::Help(command_help(spec, &bin_name))),
+                    None => Err(unknown_command(name, &bin_name)),
+                },
+            };
+        }
+        name => match COMMANDS.iter().find(|spec| spec.name == name) {
+            Some(spec) => (spec, &args[2..]),
+            None => {
+                if !name.starts_with('-')
+                    && !name.ends_with(".saft")
+                    && !Path::new(name).exists()
+                    && suggest(name, COMMANDS.iter().map(|spec| spec.name)).is_some()
+                {
+                    return Err(unknown_command(name, &bin_name));
+                }
+                (command_spec("run"), &args[1..])
+            }
+        },
+    };
+
+    let parsed = split_args(spec, rest, &bin_name)?;
+    if parsed.help {
+        return Ok(Command::Help(command_help(spec, &bin_name)));
     }
+    match spec.name {
+        "check" => parse_check_command(parsed, &bin_name),
+        "fmt" => parse_fmt_command(parsed, &bin_name),
+        "bench" => parse_bench_command(parsed, &bin_name),
+        "compile" => parse_compile_command(parsed, &bin_name),
+        "cache" => parse_cache_command(parsed, &bin_name),
+        "providers" => parse_providers_command(parsed, &bin_name),
+        "init" => parse_init_command(parsed),
+        "doc" => parse_doc_command(parsed),
+        _ => parse_run_command(parsed, &bin_name),
+    }
+}
 
-    Ok(Command::Check { file, autofmt })
+fn unknown_command(name: &str, bin_name: &str) -> String {
+    let hint = suggest(name, COMMANDS.iter().map(|spec| spec.name))
+        .map(|command| format!(" (did you mean '{command}'?)"))
+        .unwrap_or_default();
+    format!("unknown command '{name}'{hint}\nRun '{bin_name} --help' for usage.")
 }
 
-fn parse_fmt_command(args: &[String]) -> Result<Command, String> {
-    let bin_name = args.first().map(String::as_str).unwrap_or("orangensaft");
-    if args.len() < 3 {
-        return Err(format!("missing file path\n{}", usage(bin_name)));
+fn missing_path(command: &str, bin_name: &str) -> String {
+    format!("missing file path\nRun '{bin_name} {command} --help' for usage.")
+}
+
+fn parse_check_command(parsed: ParsedArgs, bin_name: &str) -> Result<Command, String> {
+    let mut autofmt = false;
+    let mut error_format = ErrorFormat::Human;
+    let mut vars = Vec::new();
+    for (flag, value) in &parsed.flags {
+        match (*flag, value.as_deref()) {
+            ("--autofmt", _) => autofmt = true,
+            ("--error-format", Some(value)) => error_format = parse_error_format(value)?,
+            ("--var", Some(value)) => push_var(&mut vars, value, false)?,
+            _ => unreachable!("flag {flag} is not declared for check"),
+        }
     }
 
-    let file = args[2].clone();
-    let mut write = false;
-    let mut check = false;
-    let mut i = 3;
-    while i < args.len() {
-        match args[i].as_str() {
-            "--write" => {
-                write = true;
-                i += 1;
+    let paths = parsed.positionals;
+    if paths.is_empty() {
+        return Err(missing_path("check", bin_name));
+    }
+    if paths.len() > 1 && paths.iter().any(|path| path == STDIN_PATH) {
+        return Err("check cannot mix stdin (-) with other paths".to_string());
+    }
+
+    Ok(Command::Check {
+        paths,
+        autofmt,
+        error_format,
+        globals: vars.into_iter().map(|(name, _)| name).collect(),
+    })
+}
+
+fn parse_fmt_command(parsed: ParsedArgs, bin_name: &str) -> Result<Command, String> {
+    let write = parsed.has("--write");
+    let check = parsed.has("--check");
+    let stdin = parsed.has("--stdin");
+    let mut overrides = FormatOverrides::default();
+    for (flag, value) in &parsed.flags {
+        let raw = value.as_deref().unwrap_or_default();
+        match *flag {
+            "--max-width" => overrides.max_width = Some(parse_positive_usize_option(flag, raw)?),
+            "--max-blank-lines" => {
+                overrides.max_blank_lines = Some(parse_usize_option(flag, raw)?);
+            }
+            "--indent-width" => {
+                overrides.indent_width = Some(parse_positive_usize_option(flag, raw)?);
             }
-            "--check" => {
-                check = true;
-                i += 1;
+            _ => {}
+        }
+    }
+    let mut paths = parsed.positionals;
+    // Editor integrations pipe the buffer in; same as passing `-`.
+    if stdin {
+        paths.push(STDIN_PATH.to_string());
+    }
+
+    if paths.is_empty() {
+        return Err(missing_path("fmt", bin_name));
+    }
+    if paths.iter().any(|path| path == STDIN_PATH) {
+        if write {
+            return Err("fmt --write cannot be used when reading from stdin".to_string());
+        }
+        if paths.len() > 1 {
+            return Err("fmt cannot mix stdin (-) with other paths".to_string());
+        }
+    }
+
+    Ok(Command::Fmt {
+        paths,
+        write,
+        check,
+        overrides,
+    })
+}
+
+fn parse_compile_command(parsed: ParsedArgs, bin_name: &str) -> Result<Command, String> {
+    let mut output = None;
+    let mut force = false;
+    let mut error_format = ErrorFormat::Human;
+    let mut vars = Vec::new();
+    for (flag, value) in &parsed.flags {
+        match (*flag, value.as_deref()) {
+            ("--output", Some(value)) => output = Some(value.to_string()),
+            ("--force", _) => force = true,
+            ("--error-format", Some(value)) => error_format = parse_error_format(value)?,
+            ("--var", Some(value)) => push_var(&mut vars, value, false)?,
+            _ => unreachable!("flag {flag} is not declared for compile"),
+        }
+    }
+
+    let file = match <[String; 1]>::try_from(parsed.positionals) {
+        Ok([file]) => file,
+        Err(files) if files.is_empty() => return Err(missing_path("compile", bin_name)),
+        Err(_) => return Err("compile takes a single script".to_string()),
+    };
+    if file == STDIN_PATH && output.is_none() {
+        return Err("compile needs --output when reading the script from stdin".to_string());
+    }
+
+    Ok(Command::Compile {
+        file,
+        output,
+        globals: vars.into_iter().map(|(name, _)| name).collect(),
+        force,
+        error_format,
+    })
+}
+
+fn parse_bench_command(parsed: ParsedArgs, bin_name: &str) -> Result<Command, String> {
+    let mut iterations = 10;
+    let mut provider = ProviderKind::Mock;
+    let mut seed = 0;
+    for (flag, value) in &parsed.flags {
+        let raw = value.as_deref().unwrap_or_default();
+        match *flag {
+            "--iterations" => iterations = parse_positive_usize_option(flag, raw)?,
+            "--provider" => provider = parse_provider_kind(raw)?,
+            "--seed" => seed = parse_u64_option(flag, raw)?,
+            _ => unreachable!("flag {flag} is not declared for bench"),
+        }
+    }
+    if matches!(provider, ProviderKind::OpenRouter | ProviderKind::Ollama) {
+        return Err(
+            "bench needs a deterministic provider: mock, fixture:PATH, or none".to_string(),
+        );
+    }
+
+    let file = match <[String; 1]>::try_from(parsed.positionals) {
+        Ok([file]) => file,
+        Err(files) if files.is_empty() => return Err(missing_path("bench", bin_name)),
+        Err(_) => return Err("bench takes a single script".to_string()),
+    };
+    if file == STDIN_PATH {
+        return Err("bench cannot read the script from stdin".to_string());
+    }
+
+    Ok(Command::Bench {
+        file,
+        iterations,
+        provider,
+        seed,
+        script_args: parsed.rest,
+    })
+}
+
+fn parse_cache_command(parsed: ParsedArgs, bin_name: &str) -> Result<Command, String> {
+    let older_than_days = match parsed.flags.first() {
+        Some((flag, value)) => {
+            Some(parse_positive_usize_option(flag, value.as_deref().unwrap_or_default())? as u64)
+        }
+        None => None,
+    };
+    let action = match parsed.positionals.as_slice() {
+        [action] if action == "clear" => CacheAction::Clear { older_than_days },
+        [action] if older_than_days.is_some() => {
+            return Err(format!(
+                "--older-than only applies to cache clear, not {action}"
+            ));
+        }
+        [action] if action == "ls" => CacheAction::List,
+        [action] if action == "stats" => CacheAction::Stats,
+        [action] => {
+            let hint = suggest(action, ["ls", "stats", "clear"].into_iter())
+                .map(|known| format!(" (did you mean '{known}'?)"))
+                .unwrap_or_default();
+            return Err(format!("unknown cache action '{action}'{hint}"));
+        }
+        _ => {
+            return Err(format!(
+                "cache needs one action: ls, stats, or clear\nRun '{bin_name} cache --help' for usage."
+            ));
+        }
+    };
+    Ok(Command::Cache { action })
+}
+
+fn parse_providers_command(parsed: ParsedArgs, bin_name: &str) -> Result<Command, String> {
+    if parsed.positionals != ["test"] {
+        return Err(format!(
+            "providers needs the 'test' action\nRun '{bin_name} providers --help' for usage."
+        ));
+    }
+    let config = discover_config(None)?;
+    let mut options = run_defaults(&config)?;
+    let mut only = false;
+    for (flag, value) in parsed.flags {
+        let raw = value.unwrap_or_default();
+        match flag {
+            "--provider" => {
+                options.provider = parse_provider_kind(&raw)?;
+                only = true;
             }
-            other => return Err(format!("unknown option '{other}'\n{}", usage(bin_name))),
+            "--model" => options.model = Some(raw),
+            "--api-key-env" => options.api_key_env = raw,
+            _ => unreachable!("flag {flag} is not declared for providers"),
         }
     }
+    Ok(Command::Providers {
+        options: Box::new(options),
+        only,
+    })
+}
 
-    if write && check {
-        return Err("fmt options --write and --check are mutually exclusive".to_string());
+fn parse_init_command(parsed: ParsedArgs) -> Result<Command, String> {
+    match <[String; 1]>::try_from(parsed.positionals) {
+        Ok([dir]) => Ok(Command::Init { dir }),
+        Err(dirs) if dirs.is_empty() => Ok(Command::Init {
+            dir: ".".to_string(),
+        }),
+        Err(_) => Err("init takes at most one directory".to_string()),
     }
+}
 
-    Ok(Command::Fmt { file, write, check })
+fn parse_doc_command(parsed: ParsedArgs) -> Result<Command, String> {
+    match <[String; 1]>::try_from(parsed.positionals) {
+        Ok([name]) => Ok(Command::Doc { name: Some(name) }),
+        Err(names) if names.is_empty() => Ok(Command::Doc { name: None }),
+        Err(_) => Err("doc takes at most one builtin name".to_string()),
+    }
 }
 
 #[derive(Debug, Clone)]
-struct RunDefaults {
+/// Provider/runtime settings for `run`, seeded from `ORANGENSAFT_*` env vars and
+/// then overridden by CLI flags.
+struct RunOptions {
     provider: ProviderKind,
     api_key_env: String,
     model: Option<String>,
     temperature: Option<f32>,
     max_tool_rounds: usize,
     max_tool_calls: usize,
+    embedding_provider: EmbeddingProviderKind,
+    embedding_model: Option<String>,
+    transcript: Option<String>,
+    requests_per_minute: Option<usize>,
+    max_concurrent_requests: Option<usize>,
+    tool_timeout_ms: Option<usize>,
+    timeout_secs: Option<usize>,
+    seed: Option<u64>,
+    /// Capability flags are CLI-only so an env var cannot silently grant them.
+    allow_net: bool,
+    allow_exec: bool,
+    verbose: bool,
+    profile: bool,
+    /// Prompt cache directory when caching is on.
+    cache_dir: Option<PathBuf>,
 }
 
-fn parse_run_command(
-    args: &[String],
-    file_index: usize,
-    option_start: usize,
-) -> Result<Command, String> {
-    let bin_name = args.first().map(String::as_str).unwrap_or("orangensaft");
-    if file_index >= args.len() {
-        return Err(format!("missing file path\n{}", usage(bin_name)));
-    }
-
-    let file = args[file_index].clone();
-    let defaults = run_defaults()?;
-    let mut provider = defaults.provider;
-    let mut api_key_env = defaults.api_key_env;
-    let mut model = defaults.model;
-    let mut temperature = defaults.temperature;
-    let mut max_tool_rounds = defaults.max_tool_rounds;
-    let mut max_tool_calls = defaults.max_tool_calls;
-    let mut autofmt = false;
-    let mut i = option_start;
+fn parse_run_command(parsed: ParsedArgs, bin_name: &str) -> Result<Command, String> {
+    let mut positionals = parsed.positionals.into_iter();
+    let file = positionals
+        .next()
+        .ok_or_else(|| missing_path("run", bin_name))?;
+    if let Some(extra) = positionals.next() {
+        return Err(format!(
+            "unexpected argument '{extra}' (pass script arguments after --)"
+        ));
+    }
 
-    while i < args.len() {
-        match args[i].as_str() {
-            "--api-key-env" => {
-                if i + 1 >= args.len() {
-                    return Err(format!("missing value for option '{}'", args[i]));
-                }
-                api_key_env = args[i + 1].clone();
-                i += 2;
-            }
-            "--model" => {
-                if i + 1 >= args.len() {
-                    return Err(format!("missing value for option '{}'", args[i]));
-                }
-                model = Some(args[i + 1].clone());
-                i += 2;
-            }
-            "--temperature" => {
-                if i + 1 >= args.len() {
-                    return Err(format!("missing value for option '{}'", args[i]));
-                }
-                temperature = Some(parse_f32_option("--temperature", &args[i + 1])?);
-                i += 2;
-            }
-            "--max-tool-rounds" => {
-                if i + 1 >= args.len() {
-                    return Err("missing value for option '--max-tool-rounds'".to_string());
-                }
-                max_tool_rounds = parse_usize_option("--max-tool-rounds", &args[i + 1])?;
-                i += 2;
-            }
-            "--max-tool-calls" => {
-                if i + 1 >= args.len() {
-                    return Err("missing value for option '--max-tool-calls'".to_string());
-                }
-                max_tool_calls = parse_usize_option("--max-tool-calls", &args[i + 1])?;
-                i += 2;
+    let config = discover_config(Some(&file))?;
+    let mut options = run_defaults(&config)?;
+    let mut approve_tools = false;
+    let mut autofmt = None;
+    let mut error_format = ErrorFormat::Human;
+    let mut vars = Vec::new();
+
+    for (flag, value) in parsed.flags {
+        let raw = value.unwrap_or_default();
+        match flag {
+            "--provider" => options.provider = parse_provider_kind(&raw)?,
+            "--api-key-env" => options.api_key_env = raw,
+            "--model" => options.model = Some(raw),
+            "--temperature" => options.temperature = Some(parse_f32_option(flag, &raw)?),
+            "--max-tool-rounds" => options.max_tool_rounds = parse_usize_option(flag, &raw)?,
+            "--max-tool-calls" => options.max_tool_calls = parse_usize_option(flag, &raw)?,
+            "--embedding-provider" => {
+                options.embedding_provider = parse_embedding_provider_kind(&raw)?;
             }
-            "--provider" => {
-                if i + 1 >= args.len() {
-                    return Err("missing value for option '--provider'".to_string());
-                }
-                provider = parse_provider_kind(&args[i + 1])?;
-                i += 2;
+            "--embedding-model" => options.embedding_model = Some(raw),
+            "--transcript" => options.transcript = Some(raw),
+            "--requests-per-minute" => {
+                options.requests_per_minute = Some(parse_positive_usize_option(flag, &raw)?);
             }
-            "--autofmt" => {
-                autofmt = true;
-                i += 1;
+            "--max-concurrent-requests" => {
+                options.max_concurrent_requests = Some(parse_positive_usize_option(flag, &raw)?);
             }
-            other => {
-                return Err(format!("unknown option '{other}'\n{}", usage(bin_name)));
+            "--tool-timeout-ms" => {
+                options.tool_timeout_ms = Some(parse_positive_usize_option(flag, &raw)?);
             }
+            "--timeout" => options.timeout_secs = Some(parse_positive_usize_option(flag, &raw)?),
+            "--seed" => options.seed = Some(parse_u64_option(flag, &raw)?),
+            "--allow-net" => options.allow_net = true,
+            "--allow-exec" => options.allow_exec = true,
+            "--verbose" => options.verbose = true,
+            "--profile" => options.profile = true,
+            "--cache" => options.cache_dir = Some(cache_dir(&config)),
+            "--no-cache" => options.cache_dir = None,
+            "--approve-tools" => approve_tools = true,
+            "--autofmt" => autofmt = Some(fmt_defaults(&config)?),
+            "--error-format" => error_format = parse_error_format(&raw)?,
+            "--var" => push_var(&mut vars, &raw, true)?,
+            _ => unreachable!("flag {flag} is not declared for run"),
         }
     }
 
+    if approve_tools && file == STDIN_PATH {
+        return Err(
+            "--approve-tools cannot be used when the script is read from stdin".to_string(),
+        );
+    }
+    if autofmt.is_some() && is_artifact_path(&file) {
+        return Err("--autofmt cannot be used with a compiled program".to_string());
+    }
+
     Ok(Command::Run {
         file,
-        provider,
-        api_key_env,
-        model,
-        temperature,
-        max_tool_rounds,
-        max_tool_calls,
+        options: Box::new(options),
+        approve_tools,
         autofmt,
+        error_format,
+        script_args: parsed.rest,
+        vars,
     })
 }
 
+/// Parses `--var NAME[:TYPE]=VALUE` into a typed binding. `check` also accepts
+/// a bare `NAME`, since it only needs to know the name exists.
+fn push_var(
+    vars: &mut Vec<(String, JsonValue)>,
+    raw: &str,
+    needs_value: bool,
+) -> Result<(), String> {
+    let (target, value) = match raw.split_once('=') {
+        Some((target, value)) => (target, Some(value)),
+        None => (raw, None),
+    };
+    let (name, kind) = match target.split_once(':') {
+        Some((name, kind)) => (name, kind),
+        None => (target, "string"),
+    };
+    let is_identifier = name
+        .chars()
+        .next()
+        .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+    if !is_identifier {
+        return Err(format!(
+            "invalid --var '{raw}': expected NAME[:TYPE]=VALUE with NAME an identifier"
+        ));
+    }
+    if stdlib::BUILTIN_NAMES.contains(&name) {
+        return Err(format!("--var {name} would shadow the builtin '{name}'"));
+    }
+    if vars.iter().any(|(existing, _)| existing == name) {
+        return Err(format!("--var {name} is given more than once"));
+    }
+
+    if needs_value && value.is_none() {
+        return Err(format!("--var {name} needs a value: {target}=VALUE"));
+    }
+
+    let invalid = |raw: &str| format!("invalid {kind} value for --var {name}: '{raw}'");
+    let value = match (kind, value) {
+        (_, None) => JsonValue::Null,
+        ("string", Some(raw)) => JsonValue::String(raw.to_string()),
+        ("int", Some(raw)) => JsonValue::from(raw.parse::<i64>().map_err(|_| invalid(raw))?),
+        ("float", Some(raw)) => raw
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(JsonValue::Number)
+            .ok_or_else(|| invalid(raw))?,
+        ("bool", Some(raw)) => JsonValue::Bool(raw.parse::<bool>().map_err(|_| invalid(raw))?),
+        ("json", Some(raw)) => serde_json::from_str(raw).map_err(|_| invalid(raw))?,
+        (other, Some(_)) => {
+            return Err(format!(
+                "unknown --var type '{other}' (expected string, int, float, bool, or json)"
+            ));
+        }
+    };
+    vars.push((name.to_string(), value));
+    Ok(())
+}
+
+/// `[run]` keys of `orangensaft.toml`, each overridden by its env var (and both
+/// by CLI flags).
+const RUN_SETTINGS: &[(&str, &str)] = &[
+    ("provider", "ORANGENSAFT_PROVIDER"),
+    ("api_key_env", "ORANGENSAFT_API_KEY_ENV"),
+    ("model", "ORANGENSAFT_MODEL"),
+    ("temperature", "ORANGENSAFT_TEMPERATURE"),
+    ("max_tool_rounds", "ORANGENSAFT_MAX_TOOL_ROUNDS"),
+    ("max_tool_calls", "ORANGENSAFT_MAX_TOOL_CALLS"),
+    ("embedding_provider", "ORANGENSAFT_EMBEDDING_PROVIDER"),
+    ("embedding_model", "ORANGENSAFT_EMBEDDING_MODEL"),
+    ("transcript", "ORANGENSAFT_TRANSCRIPT"),
+    ("requests_per_minute", "ORANGENSAFT_REQUESTS_PER_MINUTE"),
+    (
+        "max_concurrent_requests",
+        "ORANGENSAFT_MAX_CONCURRENT_REQUESTS",
+    ),
+    ("tool_timeout_ms", "ORANGENSAFT_TOOL_TIMEOUT_MS"),
+    ("timeout", "ORANGENSAFT_TIMEOUT"),
+    ("seed", "ORANGENSAFT_SEED"),
+    ("verbose", "ORANGENSAFT_LOG"),
+    ("cache", "ORANGENSAFT_CACHE"),
+];
+
+/// `[fmt]` keys of `orangensaft.toml`, each overridden by the `fmt` flag of
+/// the same name.
+const FMT_SETTINGS: &[&str] = &["indent_width", "max_blank_lines", "max_width"];
+
+/// Rejects sections and keys `orangensaft.toml` does not support, so typos
+/// do not silently fall back to defaults.
+fn check_config(config: &Config) -> Result<(), String> {
+    let Some(path) = &config.path else {
+        return Ok(());
+    };
+    let path = path.display();
+    if let Some(section) = config
+        .section_names()
+        .find(|name| !matches!(*name, "run" | "fmt"))
+    {
+        return Err(format!(
+            "{path}: unknown section [{section}] (expected [run] or [fmt])"
+        ));
+    }
+    if let Some((key, _)) = config
+        .section("fmt")
+        .find(|(key, _)| !FMT_SETTINGS.contains(key))
+    {
+        return Err(format!("{path}: unknown key '{key}' in [fmt]"));
+    }
+    for (key, _) in config.section("run") {
+        if matches!(key, "allow_net" | "allow_exec") {
+            return Err(format!(
+                "{path}: {key} cannot be set in a config file; pass --{} on the command line",
+                key.replace('_', "-")
+            ));
+        }
+        if !RUN_SETTINGS.iter().any(|(known, _)| *known == key) {
+            return Err(format!("{path}: unknown key '{key}' in [run]"));
+        }
+    }
+    Ok(())
+}
+
+/// Loads and validates the `orangensaft.toml` that applies to `script`: the
+/// nearest one at or above the script's directory, or above the working
+/// directory for stdin and commands that take no script.
+fn discover_config(script: Option<&str>) -> Result<Config, String> {
+    let start = match script.filter(|script| *script != STDIN_PATH) {
+        Some(script) => Path::new(script)
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new(".")),
+        None => Path::new("."),
+    };
+    let config = Config::discover(start).map_err(|err| err.message)?;
+    check_config(&config)?;
+    Ok(config)
+}
+
+/// `fmt` flags, applied on top of each file's `[fmt]` config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct FormatOverrides {
+    max_width: Option<usize>,
+    max_blank_lines: Option<usize>,
+    indent_width: Option<usize>,
+}
+
+/// Formatter options for `file`: its project's `[fmt]` table, then `overrides`.
+fn format_options_for(file: &str, overrides: &FormatOverrides) -> Result<FormatOptions, CliError> {
+    let mut options = discover_config(Some(file))
+        .and_then(|config| fmt_defaults(&config))
+        .map_err(|message| CliError::new(EXIT_USAGE, message))?;
+    if let Some(max_width) = overrides.max_width {
+        options.max_width = max_width;
+    }
+    if let Some(max_blank_lines) = overrides.max_blank_lines {
+        options.max_blank_lines = max_blank_lines;
+    }
+    if let Some(indent_width) = overrides.indent_width {
+        options.indent_width = indent_width;
+    }
+    Ok(options)
+}
+
+/// Formatter options from the `[fmt]` table of `orangensaft.toml`.
+fn fmt_defaults(config: &Config) -> Result<FormatOptions, String> {
+    let mut options = FormatOptions::default();
+    let path = config.path.as_deref().unwrap_or(Path::new(CONFIG_FILE));
+    for (key, value) in config.section("fmt") {
+        let name = format!("{key} in {}", path.display());
+        let ConfigValue::Int(number) = value else {
+            return Err(format!(
+                "invalid {name} value: expected an integer, got a {}",
+                value.type_name()
+            ));
+        };
+        let raw = number.to_string();
+        match key {
+            "indent_width" => options.indent_width = parse_positive_usize_option(&name, &raw)?,
+            "max_blank_lines" => options.max_blank_lines = parse_usize_option(&name, &raw)?,
+            "max_width" => options.max_width = parse_positive_usize_option(&name, &raw)?,
+            _ => unreachable!("check_config rejects unknown [fmt] key {key}"),
+        }
+    }
+    Ok(options)
+}
+
+/// Raw value of a run default and the name to blame in errors: the env var
+/// when it is set, otherwise the matching `[run]` key of `orangensaft.toml`.
+fn run_setting(config: &Config, env_name: &str) -> Option<(String, String)> {
+    if let Ok(raw) = env::var(env_name) {
+        return Some((env_name.to_string(), raw));
+    }
+    let (key, _) = RUN_SETTINGS.iter().find(|(_, env)| *env == env_name)?;
+    let (_, value) = config.section("run").find(|(name, _)| name == key)?;
+    let raw = match value {
+        ConfigValue::String(text) => text.clone(),
+        ConfigValue::Int(number) => number.to_string(),
+        ConfigValue::Float(number) => number.to_string(),
+        ConfigValue::Bool(flag) => flag.to_string(),
+    };
+    let path = config.path.as_deref().unwrap_or(Path::new(CONFIG_FILE));
+    Some((format!("{key} in {}", path.display()), raw))
+}
+
+/// `ORANGENSAFT_CACHE_DIR`, or `.orangensaft/cache` beside the project's
+/// `orangensaft.toml` (the current directory when there is none).
+fn cache_dir(config: &Config) -> PathBuf {
+    if let Some(dir) = env::var_os("ORANGENSAFT_CACHE_DIR") {
+        return PathBuf::from(dir);
+    }
+    config
+        .path
+        .as_deref()
+        .and_then(Path::parent)
+        .unwrap_or(Path::new(""))
+        .join(DEFAULT_CACHE_DIR)
+}
+
 fn parse_provider_kind(raw: &str) -> Result<ProviderKind, String> {
     match raw {
         "mock" => Ok(ProviderKind::Mock),
         "openrouter" => Ok(ProviderKind::OpenRouter),
+        "ollama" => Ok(ProviderKind::Ollama),
         "none" => Ok(ProviderKind::None),
+        other => match other.strip_prefix("fixture:") {
+            Some(path) if !path.is_empty() => Ok(ProviderKind::Fixture(path.to_string())),
+            _ => Err(format!(
+                "invalid provider '{other}' (expected 'mock', 'openrouter', 'ollama', 'fixture:PATH', or 'none')"
+            )),
+        },
+    }
+}
+
+fn parse_error_format(raw: &str) -> Result<ErrorFormat, String> {
+    match raw {
+        "human" => Ok(ErrorFormat::Human),
+        "json" => Ok(ErrorFormat::Json),
+        other => Err(format!(
+            "invalid error format '{other}' (expected 'human' or 'json')"
+        )),
+    }
+}
+
+fn parse_embedding_provider_kind(raw: &str) -> Result<EmbeddingProviderKind, String> {
+    match raw {
+        "mock" => Ok(EmbeddingProviderKind::Mock),
+        "openai" => Ok(EmbeddingProviderKind::OpenAi),
+        "openrouter" => Ok(EmbeddingProviderKind::OpenRouter),
+        "ollama" => Ok(EmbeddingProviderKind::Ollama),
+        "none" => Ok(EmbeddingProviderKind::None),
         other => Err(format!(
-            "invalid provider '{other}' (expected 'mock', 'openrouter', or 'none')"
+            "invalid embedding provider '{other}' (expected 'mock', 'openai', 'openrouter', 'ollama', or 'none')"
         )),
     }
 }
 
-fn run_defaults() -> Result<RunDefaults, String> {
+fn run_defaults(config: &Config) -> Result<RunOptions, String> {
     let runtime_defaults = RuntimeOptions::default();
-    let provider = match env::var("ORANGENSAFT_PROVIDER") {
-        Ok(value) => parse_provider_kind(&value)
-            .map_err(|err| format!("invalid ORANGENSAFT_PROVIDER value: {err}"))?,
-        Err(_) => ProviderKind::Mock,
+    let provider = match run_setting(config, "ORANGENSAFT_PROVIDER") {
+        Some((name, value)) => {
+            parse_provider_kind(&value).map_err(|err| format!("invalid {name} value: {err}"))?
+        }
+        None => ProviderKind::Mock,
     };
 
-    let api_key_env =
-        env::var("ORANGENSAFT_API_KEY_ENV").unwrap_or_else(|_| "OPENROUTER_API_KEY".to_string());
-    let model = env::var("ORANGENSAFT_MODEL").ok();
-    let temperature = match env::var("ORANGENSAFT_TEMPERATURE") {
-        Ok(raw) => Some(parse_f32_option("ORANGENSAFT_TEMPERATURE", &raw)?),
-        Err(_) => None,
+    let api_key_env = run_setting(config, "ORANGENSAFT_API_KEY_ENV")
+        .map_or_else(|| "OPENROUTER_API_KEY".to_string(), |(_, raw)| raw);
+    let model = run_setting(config, "ORANGENSAFT_MODEL").map(|(_, raw)| raw);
+    let temperature = match run_setting(config, "ORANGENSAFT_TEMPERATURE") {
+        Some((name, raw)) => Some(parse_f32_option(&name, &raw)?),
+        None => None,
+    };
+    let max_tool_rounds = match run_setting(config, "ORANGENSAFT_MAX_TOOL_ROUNDS") {
+        Some((name, raw)) => parse_usize_option(&name, &raw)?,
+        None => runtime_defaults.max_tool_rounds,
+    };
+    let max_tool_calls = match run_setting(config, "ORANGENSAFT_MAX_TOOL_CALLS") {
+        Some((name, raw)) => parse_usize_option(&name, &raw)?,
+        None => runtime_defaults.max_tool_calls,
+    };
+    let embedding_provider = match run_setting(config, "ORANGENSAFT_EMBEDDING_PROVIDER") {
+        Some((name, value)) => parse_embedding_provider_kind(&value)
+            .map_err(|err| format!("invalid {name} value: {err}"))?,
+        None => EmbeddingProviderKind::Mock,
     };
-    let max_tool_rounds = match env::var("ORANGENSAFT_MAX_TOOL_ROUNDS") {
-        Ok(raw) => parse_usize_option("ORANGENSAFT_MAX_TOOL_ROUNDS", &raw)?,
-        Err(_) => runtime_defaults.max_tool_rounds,
+    let embedding_model = run_setting(config, "ORANGENSAFT_EMBEDDING_MODEL").map(|(_, raw)| raw);
+    let transcript = run_setting(config, "ORANGENSAFT_TRANSCRIPT").map(|(_, raw)| raw);
+    let requests_per_minute = match run_setting(config, "ORANGENSAFT_REQUESTS_PER_MINUTE") {
+        Some((name, raw)) => Some(parse_positive_usize_option(&name, &raw)?),
+        None => None,
     };
-    let max_tool_calls = match env::var("ORANGENSAFT_MAX_TOOL_CALLS") {
-        Ok(raw) => parse_usize_option("ORANGENSAFT_MAX_TOOL_CALLS", &raw)?,
-        Err(_) => runtime_defaults.max_tool_calls,
+    let max_concurrent_requests = match run_setting(config, "ORANGENSAFT_MAX_CONCURRENT_REQUESTS") {
+        Some((name, raw)) => Some(parse_positive_usize_option(&name, &raw)?),
+        None => None,
+    };
+    let tool_timeout_ms = match run_setting(config, "ORANGENSAFT_TOOL_TIMEOUT_MS") {
+        Some((name, raw)) => Some(parse_positive_usize_option(&name, &raw)?),
+        None => None,
+    };
+    let timeout_secs = match run_setting(config, "ORANGENSAFT_TIMEOUT") {
+        Some((name, raw)) => Some(parse_positive_usize_option(&name, &raw)?),
+        None => None,
+    };
+    let seed = match run_setting(config, "ORANGENSAFT_SEED") {
+        Some((name, raw)) => Some(parse_u64_option(&name, &raw)?),
+        None => None,
+    };
+    let verbose = match run_setting(config, "ORANGENSAFT_LOG") {
+        Some((name, raw)) => parse_switch_option(&name, &raw)?,
+        None => false,
+    };
+    let cache = match run_setting(config, "ORANGENSAFT_CACHE") {
+        Some((name, raw)) => parse_switch_option(&name, &raw)?,
+        None => false,
     };
 
-    Ok(RunDefaults {
+    Ok(RunOptions {
         provider,
         api_key_env,
         model,
         temperature,
         max_tool_rounds,
         max_tool_calls,
+        embedding_provider,
+        embedding_model,
+        transcript,
+        requests_per_minute,
+        max_concurrent_requests,
+        tool_timeout_ms,
+        timeout_secs,
+        seed,
+        allow_net: false,
+        allow_exec: false,
+        verbose,
+        profile: false,
+        cache_dir: cache.then(|| cache_dir(config)),
     })
 }
 
-fn execute(command: Command) -> Result<(), String> {
+fn execute(command: Command) -> Result<(), CliError> {
     match command {
-        Command::Check { file, autofmt } => {
-            let source = read_file(&file)?;
-            let source_to_check = if autofmt {
-                crate::format_source(&source).map_err(|err| render_error(err, &file, &source))?
-            } else {
-                source.clone()
-            };
-
-            match crate::check_source(&source_to_check) {
-                Ok(_) => {
-                    println!("OK: {file}");
-                    Ok(())
-                }
-                Err(err) => Err(render_error(err, &file, &source_to_check)),
-            }
+        Command::Check {
+            paths,
+            autofmt,
+            error_format,
+            globals,
+        } => {
+            let files = expand_paths(&paths)?;
+            // A summary line would break JSON-lines consumers of `--error-format json`.
+            let summarize = error_format == ErrorFormat::Human;
+            for_each_file("check", &files, summarize, |file| {
+                check_file(file, autofmt, error_format, &globals)
+            })
         }
         Command::Run {
             file,
-            provider,
-            api_key_env,
-            model,
-            temperature,
-            max_tool_rounds,
-            max_tool_calls,
+            options,
+            approve_tools,
             autofmt,
+            error_format,
+            script_args,
+            vars,
         } => {
-            let source = read_file(&file)?;
-            let source_to_run = if autofmt {
-                crate::format_source(&source).map_err(|err| render_error(err, &file, &source))?
+            let RunOptions {
+                provider,
+                api_key_env,
+                model,
+                temperature,
+                max_tool_rounds,
+                max_tool_calls,
+                embedding_provider,
+                embedding_model,
+                transcript,
+                requests_per_minute,
+                max_concurrent_requests,
+                tool_timeout_ms,
+                timeout_secs,
+                seed,
+                allow_net,
+                allow_exec,
+                verbose,
+                profile,
+                cache_dir,
+            } = *options;
+            let (source_to_run, program) = if is_artifact_path(&file) {
+                load_artifact(&file, &vars)?
             } else {
-                source.clone()
+                let source = read_file(&file)?;
+                let source_to_run = if let Some(fmt_options) = &autofmt {
+                    formatter::format_source_with_options(&source, fmt_options).map_err(|err| {
+                        check_error(render_error(err, &file, &source, error_format))
+                    })?
+                } else {
+                    source
+                };
+                let globals = vars
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>();
+                let program =
+                    crate::check_source_with_globals(&source_to_run, &globals).map_err(|err| {
+                        check_error(render_error(err, &file, &source_to_run, error_format))
+                    })?;
+                (source_to_run, program)
             };
-            let provider: Box<dyn PromptProvider> = match provider {
+            let default_provider: Box<dyn PromptProvider> = match &provider {
                 ProviderKind::Mock => Box::new(HeuristicMockProvider::new()),
                 ProviderKind::OpenRouter => {
-                    let provider = OpenRouterProvider::from_env(&api_key_env, model, temperature)
-                        .map_err(|err| err.message)?;
+                    let provider =
+                        OpenRouterProvider::from_env(&api_key_env, model.clone(), temperature)
+                            .map_err(provider_error)?;
                     Box::new(provider)
                 }
+                ProviderKind::Ollama => Box::new(OllamaProvider::new(
+                    env::var("OLLAMA_HOST").ok(),
+                    model.clone(),
+                )),
+                ProviderKind::Fixture(path) => {
+                    Box::new(FixtureProvider::from_file(path).map_err(provider_error)?)
+                }
                 ProviderKind::None => Box::new(NoopProvider),
             };
+            let transcript_log = match transcript {
+                Some(path) => Some(TranscriptLog::create(&path).map_err(|err| err.message)?),
+                None => None,
+            };
+            let rate_limit = RateLimit {
+                max_requests: requests_per_minute,
+                max_concurrent: max_concurrent_requests,
+                ..RateLimit::default()
+            };
+            let rate_limiter = (!rate_limit.is_unlimited()).then(|| RateLimiter::new(rate_limit));
+            // Cache hits skip the rate limiter and the transcript, which only
+            // record requests that reach the provider.
+            let wrap = |name: &str, provider: Box<dyn PromptProvider>| -> Box<dyn PromptProvider> {
+                let provider: Box<dyn PromptProvider> = match &transcript_log {
+                    Some(log) => Box::new(TranscriptProvider::new(provider, log.clone())),
+                    None => provider,
+                };
+                let provider: Box<dyn PromptProvider> = match &rate_limiter {
+                    Some(limiter) => {
+                        Box::new(RateLimitedProvider::with_limiter(provider, limiter.clone()))
+                    }
+                    None => provider,
+                };
+                match &cache_dir {
+                    Some(dir) => {
+                        Box::new(CachingProvider::new(provider, PromptCache::new(dir), name))
+                    }
+                    None => provider,
+                }
+            };
+            let embedder: Box<dyn EmbeddingProvider> = match embedding_provider {
+                EmbeddingProviderKind::Mock => Box::new(HashingEmbeddingProvider::new()),
+                EmbeddingProviderKind::OpenAi => Box::new(
+                    OpenAiEmbeddingProvider::openai_from_env("OPENAI_API_KEY", embedding_model)
+                        .map_err(provider_error)?,
+                ),
+                EmbeddingProviderKind::OpenRouter => Box::new(
+                    OpenAiEmbeddingProvider::openrouter_from_env(&api_key_env, embedding_model)
+                        .map_err(provider_error)?,
+                ),
+                EmbeddingProviderKind::Ollama => Box::new(OllamaEmbeddingProvider::new(
+                    env::var("OLLAMA_HOST").ok(),
+                    embedding_model,
+                )),
+                EmbeddingProviderKind::None => Box::new(NoopEmbeddingProvider),
+            };
 
             let options = RuntimeOptions {
                 max_tool_rounds,
                 max_tool_calls,
+                tool_timeout: tool_timeout_ms.map(|ms| Duration::from_millis(ms as u64)),
+                timeout: timeout_secs.map(|secs| Duration::from_secs(secs as u64)),
+                seed,
+                allow_net,
+                allow_exec,
+                verbose,
+                profile,
+            };
+
+            let mut runtime = Runtime::with_provider_and_options(
+                wrap(&provider.label(), default_provider),
+                options,
+            );
+            runtime.set_embedding_provider(embedder);
+            runtime.set_script_args(script_args);
+            for (name, value) in vars {
+                let value = runtime.decode_json(value).map_err(|err| err.message)?;
+                runtime.set_global(&name, value);
+            }
+            if approve_tools {
+                runtime.set_tool_approver(Box::new(PromptingToolApprover::stdio()));
+            }
+
+            // Named providers for `$ ... $ with {provider: ...}`; the CLI `--model` only
+            // applies to the entry matching `--provider`.
+            let model_for =
+                |kind: ProviderKind| (provider == kind).then(|| model.clone()).flatten();
+            runtime.register_provider("mock", wrap("mock", Box::new(HeuristicMockProvider::new())));
+            runtime.register_provider(
+                "ollama",
+                wrap(
+                    "ollama",
+                    Box::new(OllamaProvider::new(
+                        env::var("OLLAMA_HOST").ok(),
+                        model_for(ProviderKind::Ollama),
+                    )),
+                ),
+            );
+            let openrouter: Box<dyn PromptProvider> = match OpenRouterProvider::from_env(
+                &api_key_env,
+                model_for(ProviderKind::OpenRouter),
+                temperature,
+            ) {
+                Ok(openrouter) => wrap("openrouter", Box::new(openrouter)),
+                Err(err) => Box::new(UnavailableProvider::new("openrouter", err.message)),
             };
+            runtime.register_provider("openrouter", openrouter);
 
-            match crate::run_source_with_provider_and_options(&source_to_run, provider, options) {
-                Ok(_) => Ok(()),
-                Err(err) => Err(render_error(err, &file, &source_to_run)),
+            let result = runtime.run_program(&program);
+            if profile {
+                eprintln!("{}", runtime.profile().report());
             }
+            result.map_err(|err| run_error(err, &file, &source_to_run, error_format))
         }
-        Command::Fmt { file, write, check } => {
+        Command::Bench {
+            file,
+            iterations,
+            provider,
+            seed,
+            script_args,
+        } => {
             let source = read_file(&file)?;
-            let formatted =
-                crate::format_source(&source).map_err(|err| render_error(err, &file, &source))?;
+            let program = crate::check_source(&source).map_err(|err| {
+                check_error(render_error(err, &file, &source, ErrorFormat::Human))
+            })?;
+            // Profiling stays off so its bookkeeping does not skew the timings;
+            // `RoundCounter` tallies prompt rounds instead.
+            let options = RuntimeOptions {
+                seed: Some(seed),
+                ..RuntimeOptions::default()
+            };
 
-            if check {
-                if source == formatted {
-                    println!("OK: {file}");
-                    Ok(())
-                } else {
-                    Err(format!("not formatted: {file}"))
-                }
-            } else if write {
-                fs::write(&file, formatted)
-                    .map_err(|err| format!("failed to write '{file}': {err}"))
+            let mut times = Vec::with_capacity(iterations);
+            let mut rounds = Vec::with_capacity(iterations);
+            for _ in 0..iterations {
+                let bench_provider: Box<dyn PromptProvider> = match &provider {
+                    ProviderKind::Fixture(path) => {
+                        Box::new(FixtureProvider::from_file(path).map_err(provider_error)?)
+                    }
+                    ProviderKind::None => Box::new(NoopProvider),
+                    _ => Box::new(HeuristicMockProvider::new()),
+                };
+                let calls = Rc::new(Cell::new(0));
+                let bench_provider = Box::new(RoundCounter {
+                    inner: bench_provider,
+                    calls: calls.clone(),
+                });
+                let mut runtime = Runtime::with_provider_and_options(bench_provider, options);
+                runtime.set_script_args(script_args.clone());
+                let started = Instant::now();
+                runtime
+                    .run_program(&program)
+                    .map_err(|err| run_error(err, &file, &source, ErrorFormat::Human))?;
+                times.push(started.elapsed());
+                rounds.push(calls.get());
+            }
+
+            let min = times.iter().min().copied().unwrap_or_default();
+            let max = times.iter().max().copied().unwrap_or_default();
+            let mean = times.iter().sum::<Duration>() / iterations as u32;
+            let (fewest, most) = (
+                rounds.iter().min().copied().unwrap_or_default(),
+                rounds.iter().max().copied().unwrap_or_default(),
+            );
+            let rounds = if fewest == most {
+                fewest.to_string()
             } else {
-                print!("{formatted}");
-                Ok(())
+                format!("{fewest}-{most}")
+            };
+            eprintln!(
+                "bench: {} ({iterations} iterations)\n  min    {min:.2?}\n  mean   {mean:.2?}\n  max    {max:.2?}\n  prompt rounds per run: {rounds}",
+                display_name(&file)
+            );
+            Ok(())
+        }
+        Command::Fmt {
+            paths,
+            write,
+            check,
+            overrides,
+        } => {
+            let files = expand_paths(&paths)?;
+            if files.len() > 1 && !write && !check {
+                return Err(CliError::new(
+                    EXIT_USAGE,
+                    "fmt prints a single file to stdout; use --write or --check for several files",
+                ));
+            }
+
+            for_each_file("fmt", &files, true, |file| {
+                format_file(file, write, check, &format_options_for(file, &overrides)?)
+            })
+        }
+        Command::Init { dir } => init_project(Path::new(&dir)),
+        Command::Doc { name } => {
+            let specs = match name {
+                Some(name) => {
+                    let spec = stdlib::BUILTINS
+                        .iter()
+                        .find(|spec| spec.name == name)
+                        .ok_or_else(|| {
+                            format!(
+                                "unknown builtin '{name}'; run `orangensaft doc` to list every builtin"
+                            )
+                        })?;
+                    vec![spec]
+                }
+                None => stdlib::BUILTINS.iter().collect(),
+            };
+            let blocks = specs
+                .into_iter()
+                .map(|spec| {
+                    format!(
+                        "{}\n  {}\n  arguments: {}",
+                        spec.signature, spec.doc, spec.arity
+                    )
+                })
+                .collect::<Vec<_>>();
+            // The full listing is often piped into `head`/`less`; a closed pipe is fine.
+            match writeln!(io::stdout(), "{}", blocks.join("\n\n")) {
+                Err(err) if err.kind() != io::ErrorKind::BrokenPipe => {
+                    Err(format!("failed to write to stdout: {err}").into())
+                }
+                _ => Ok(()),
+            }
+        }
+        Command::Cache { action } => {
+            let config =
+                discover_config(None).map_err(|message| CliError::new(EXIT_USAGE, message))?;
+            let cache = PromptCache::new(cache_dir(&config));
+            let dir = cache.dir().display();
+            match action {
+                CacheAction::List => {
+                    for entry in cache.entries().map_err(|err| err.message)? {
+                        let prompt = entry
+                            .prompt
+                            .split_whitespace()
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        let preview = match prompt.char_indices().nth(60) {
+                            Some((end, _)) => format!("{}...", &prompt[..end]),
+                            None => prompt,
+                        };
+                        println!(
+                            "{}  {}  {}  {preview}",
+                            &entry.key[..12],
+                            format_timestamp(entry.created_at_ms),
+                            cache_source(&entry.provider, entry.model.as_deref())
+                        );
+                    }
+                }
+                CacheAction::Stats => {
+                    let entries = cache.entries().map_err(|err| err.message)?;
+                    let bytes = entries.iter().map(|entry| entry.bytes).sum::<u64>();
+                    println!("{dir}: {} entries, {bytes} bytes", entries.len());
+                    if let (Some(oldest), Some(newest)) = (entries.first(), entries.last()) {
+                        println!(
+                            "  oldest {}, newest {}",
+                            format_timestamp(oldest.created_at_ms),
+                            format_timestamp(newest.created_at_ms)
+                        );
+                    }
+                    let mut per_source = std::collections::BTreeMap::<String, usize>::new();
+                    for entry in &entries {
+                        *per_source
+                            .entry(cache_source(&entry.provider, entry.model.as_deref()))
+                            .or_default() += 1;
+                    }
+                    for (source, count) in per_source {
+                        println!("  {count:>6}  {source}");
+                    }
+                }
+                CacheAction::Clear { older_than_days } => {
+                    let removed = cache
+                        .clear(older_than_days.map(|days| days * 86_400_000))
+                        .map_err(|err| err.message)?;
+                    println!("removed {removed} entries from {dir}");
+                }
+            }
+            Ok(())
+        }
+        Command::Compile {
+            file,
+            output,
+            globals,
+            force,
+            error_format,
+        } => compile_file(&file, output.as_deref(), &globals, force, error_format),
+        Command::Providers { options, only } => {
+            let default = options.provider.clone();
+            let mut kinds = vec![default.clone()];
+            if !only {
+                for kind in [
+                    ProviderKind::Mock,
+                    ProviderKind::OpenRouter,
+                    ProviderKind::Ollama,
+                ] {
+                    if !kinds.contains(&kind) {
+                        kinds.push(kind);
+                    }
+                }
+            }
+
+            let mut rows = Vec::new();
+            for kind in kinds {
+                let model = (kind == default).then(|| options.model.clone()).flatten();
+                let checks = match &kind {
+                    ProviderKind::Mock => {
+                        vec![ProbeCheck::new("setup", ProbeStatus::Ok, "built in")]
+                    }
+                    ProviderKind::None => vec![ProbeCheck::new(
+                        "setup",
+                        ProbeStatus::Ok,
+                        "every prompt fails by design",
+                    )],
+                    ProviderKind::Fixture(path) => vec![match FixtureProvider::from_file(path) {
+                        Ok(_) => ProbeCheck::new("fixture", ProbeStatus::Ok, path.clone()),
+                        Err(err) => ProbeCheck::new("fixture", ProbeStatus::Fail, err.message),
+                    }],
+                    ProviderKind::OpenRouter => {
+                        OpenRouterProvider::probe(&options.api_key_env, model.as_deref())
+                    }
+                    ProviderKind::Ollama => {
+                        OllamaProvider::new(env::var("OLLAMA_HOST").ok(), model).probe()
+                    }
+                };
+                let mut name = kind.label();
+                if kind == default {
+                    name.push('*');
+                }
+                rows.extend(checks.into_iter().map(|check| (name.clone(), check)));
+            }
+
+            let width = rows
+                .iter()
+                .map(|(name, _)| name.len())
+                .max()
+                .unwrap_or(0)
+                .max(8);
+            println!(
+                "{:<width$}  {:<8}  {:<7}  DETAIL",
+                "PROVIDER", "CHECK", "STATUS"
+            );
+            for (name, check) in &rows {
+                let status = match check.status {
+                    ProbeStatus::Ok => "ok",
+                    ProbeStatus::Fail => "FAIL",
+                    ProbeStatus::Skipped => "skipped",
+                };
+                println!(
+                    "{name:<width$}  {:<8}  {status:<7}  {}",
+                    check.check, check.detail
+                );
+            }
+            println!("* default provider");
+
+            let failed = rows
+                .iter()
+                .filter(|(name, check)| name.ends_with('*') && check.status == ProbeStatus::Fail)
+                .count();
+            if failed > 0 {
+                return Err(CliError::new(
+                    EXIT_PROVIDER_ERROR,
+                    format!(
+                        "default provider '{}' failed {failed} check(s)",
+                        default.label()
+                    ),
+                ));
+            }
+            Ok(())
+        }
+        Command::Help(text) => {
+            println!("{text}");
+            Ok(())
+        }
+    }
+}
+
+fn cache_source(provider: &str, model: Option<&str>) -> String {
+    match model {
+        Some(model) => format!("{provider}/{model}"),
+        None => provider.to_string(),
+    }
+}
+
+fn format_timestamp(ms: u64) -> String {
+    chrono::DateTime::from_timestamp_millis(ms as i64)
+        .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "?".to_string())
+}
+
+/// Files written by `init`, relative to the project directory.
+const INIT_FILES: &[(&str, &str)] = &[
+    (
+        "orangensaft.toml",
+        "# Project defaults for `orangensaft run`; CLI flags override them.\n\
+         [run]\n\
+         provider = \"mock\"\n\
+         # model = \"openai/gpt-4o-mini\"\n\
+         # api_key_env = \"OPENROUTER_API_KEY\"\n\
+         max_tool_rounds = 8\n\
+         max_tool_calls = 32\n",
+    ),
+    (
+        "examples/main.saft",
+        "// Entry script. Run it with: orangensaft run examples/main.saft\n\
+         x = 2\n\
+         y = 3\n\
+         \n\
+         total: int = $\n    what is {x} + {y}?\n$\n\
+         \n\
+         print(\"total:\", total)\n",
+    ),
+    (
+        "tests/smoke.saft",
+        "// Deterministic checks; run with: orangensaft run tests/smoke.saft --provider none\n\
+         assert upper(\"saft\") == \"SAFT\"\n\
+         assert len([1, 2, 3]) == 3\n",
+    ),
+    (
+        ".gitignore",
+        "# orangensaft caches and prompt transcripts\n.orangensaft/\ntranscript*.jsonl\n",
+    ),
+];
+
+/// Scaffolds a starter project in `dir`, refusing to overwrite existing files.
+fn init_project(dir: &Path) -> Result<(), CliError> {
+    let existing = INIT_FILES
+        .iter()
+        .map(|(name, _)| dir.join(name))
+        .filter(|path| path.exists())
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>();
+    if !existing.is_empty() {
+        return Err(format!(
+            "init would overwrite existing files: {}",
+            existing.join(", ")
+        )
+        .into());
+    }
+
+    for (name, contents) in INIT_FILES {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| {
+                format!("failed to create directory '{}': {err}", parent.display())
+            })?;
+        }
+        fs::write(&path, contents)
+            .map_err(|err| format!("failed to write '{}': {err}", path.display()))?;
+        println!("created {}", path.display());
+    }
+    Ok(())
+}
+
+/// Runs `action` on every file, continuing past failures so each one gets
+/// reported. Failures are printed as they happen, followed by a
+/// "failed for N of M files" summary when `summarize` is set. The exit code is
+/// the failures' shared code, or `EXIT_FAILURE` when they differ.
+fn for_each_file(
+    command: &str,
+    files: &[String],
+    summarize: bool,
+    mut action: impl FnMut(&str) -> Result<(), CliError>,
+) -> Result<(), CliError> {
+    if let [file] = files {
+        return action(file);
+    }
+    let mut failures = 0;
+    let mut code = None;
+    let mut last_failure: Option<CliError> = None;
+    for file in files {
+        if let Err(err) = action(file) {
+            failures += 1;
+            code = match code {
+                None => Some(err.code),
+                Some(code) if code == err.code => Some(code),
+                Some(_) => Some(EXIT_FAILURE),
+            };
+            if let Some(previous) = last_failure.replace(err) {
+                eprintln!("{}", previous.message);
+            }
+        }
+    }
+    let (Some(code), Some(last)) = (code, last_failure) else {
+        return Ok(());
+    };
+    if !summarize {
+        return Err(CliError::new(code, last.message));
+    }
+    eprintln!("{}", last.message);
+    Err(CliError::new(
+        code,
+        format!("{command} failed for {failures} of {} files", files.len()),
+    ))
+}
+
+fn check_file(
+    file: &str,
+    autofmt: bool,
+    error_format: ErrorFormat,
+    globals: &[String],
+) -> Result<(), CliError> {
+    let source = read_file(file)?;
+    let source_to_check = if autofmt {
+        let fmt_options = format_options_for(file, &FormatOverrides::default())?;
+        formatter::format_source_with_options(&source, &fmt_options)
+            .map_err(|err| check_error(render_error(err, file, &source, error_format)))?
+    } else {
+        source
+    };
+
+    let globals = globals.iter().map(String::as_str).collect::<Vec<_>>();
+    match crate::check_source_with_globals(&source_to_check, &globals) {
+        Ok(_) => {
+            println!("OK: {}", display_name(file));
+            Ok(())
+        }
+        Err(err) => Err(check_error(render_error(
+            err,
+            file,
+            &source_to_check,
+            error_format,
+        ))),
+    }
+}
+
+fn compile_file(
+    file: &str,
+    output: Option<&str>,
+    globals: &[String],
+    force: bool,
+    error_format: ErrorFormat,
+) -> Result<(), CliError> {
+    let source = read_file(file)?;
+    let output = output.map_or_else(|| artifact::default_output(file), PathBuf::from);
+    let globals = globals.iter().map(String::as_str).collect::<Vec<_>>();
+    let current = !force
+        && Artifact::load(&output).is_ok_and(|existing| existing.is_current(&source, &globals));
+    if current {
+        println!("up to date: {}", output.display());
+        return Ok(());
+    }
+
+    let compiled = Artifact::compile(&source, &globals)
+        .map_err(|err| check_error(render_error(err, file, &source, error_format)))?;
+    fs::write(&output, compiled.to_json())
+        .map_err(|err| format!("failed to write '{}': {err}", output.display()))?;
+    println!("compiled: {} -> {}", display_name(file), output.display());
+    Ok(())
+}
+
+/// Counts the prompt rounds of a `bench` iteration, one per provider call.
+struct RoundCounter {
+    inner: Box<dyn PromptProvider>,
+    calls: Rc<Cell<usize>>,
+}
+
+impl PromptProvider for RoundCounter {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        self.calls.set(self.calls.get() + 1);
+        self.inner.complete(request)
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.inner.model()
+    }
+
+    fn temperature(&self) -> Option<f32> {
+        self.inner.temperature()
+    }
+}
+
+/// Source (for diagnostics) and program of a `.saftc` file passed to `run`;
+/// every global it was compiled with must be bound by `--var`.
+fn load_artifact(file: &str, vars: &[(String, JsonValue)]) -> Result<(String, Program), CliError> {
+    let artifact = Artifact::load(Path::new(file)).map_err(|err| check_error(err.message))?;
+    if let Some(missing) = artifact
+        .globals
+        .iter()
+        .find(|name| !vars.iter().any(|(var, _)| var == *name))
+    {
+        return Err(check_error(format!(
+            "{file} was compiled with --var {missing}; bind it with --var {missing}=VALUE"
+        )));
+    }
+    Ok((artifact.source, artifact.program))
+}
+
+fn format_file(
+    file: &str,
+    write: bool,
+    check: bool,
+    options: &FormatOptions,
+) -> Result<(), CliError> {
+    let source = read_file(file)?;
+    let formatted = formatter::format_source_with_options(&source, options)
+        .map_err(|err| check_error(render_error(err, file, &source, ErrorFormat::Human)))?;
+
+    if check {
+        if source == formatted {
+            println!("OK: {}", display_name(file));
+            Ok(())
+        } else {
+            Err(format!("not formatted: {}", display_name(file)).into())
+        }
+    } else if write {
+        if source == formatted {
+            return Ok(());
+        }
+        fs::write(file, formatted).map_err(|err| format!("failed to write '{file}': {err}").into())
+    } else {
+        print!("{formatted}");
+        Ok(())
+    }
+}
+
+/// Replaces each directory in `paths` with the `*.saft` files below it, sorted.
+fn expand_paths(paths: &[String]) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        if Path::new(path).is_dir() {
+            let start = files.len();
+            collect_saft_files(Path::new(path), &mut files)?;
+            if files.len() == start {
+                return Err(format!("no .saft files found in '{path}'"));
             }
+            files[start..].sort();
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+fn collect_saft_files(dir: &Path, files: &mut Vec<String>) -> Result<(), String> {
+    let entries = fs::read_dir(dir)
+        .map_err(|err| format!("failed to read directory '{}': {err}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|err| format!("failed to read directory '{}': {err}", dir.display()))?
+            .path();
+        if path.is_dir() {
+            collect_saft_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "saft") {
+            files.push(path.to_string_lossy().into_owned());
         }
     }
+    Ok(())
 }
 
 fn read_file(path: &str) -> Result<String, String> {
+    if path == STDIN_PATH {
+        let mut source = String::new();
+        io::stdin()
+            .read_to_string(&mut source)
+            .map_err(|err| format!("failed to read stdin: {err}"))?;
+        return Ok(source);
+    }
     fs::read_to_string(path).map_err(|err| format!("failed to read '{path}': {err}"))
 }
 
-fn render_error(err: SaftError, file: &str, source: &str) -> String {
-    err.render(file, source)
+/// Name used for `file` in diagnostics and status lines.
+fn display_name(file: &str) -> &str {
+    if file == STDIN_PATH { "<stdin>" } else { file }
+}
+
+/// Maps a failed run to the exit code for its `ErrorKind`.
+fn run_error(err: SaftError, file: &str, source: &str, format: ErrorFormat) -> CliError {
+    let code = match err.kind {
+        ErrorKind::Runtime => EXIT_RUNTIME_ERROR,
+        ErrorKind::Provider => EXIT_PROVIDER_ERROR,
+        ErrorKind::Assertion => EXIT_ASSERTION_FAILED,
+        ErrorKind::Timeout => EXIT_TIMEOUT,
+    };
+    CliError::new(code, render_error(err, file, source, format))
+}
+
+/// A parse/resolve failure, reported with `EXIT_CHECK_ERROR`.
+fn check_error(message: String) -> CliError {
+    CliError::new(EXIT_CHECK_ERROR, message)
+}
+
+/// A provider that could not be set up, e.g. a missing API key.
+fn provider_error(err: SaftError) -> CliError {
+    CliError::new(EXIT_PROVIDER_ERROR, err.message)
+}
+
+fn render_error(err: SaftError, file: &str, source: &str, format: ErrorFormat) -> String {
+    match format {
+        ErrorFormat::Human => err.render_styled(display_name(file), source, use_color()),
+        ErrorFormat::Json => err.render_json(display_name(file)),
+    }
+}
+
+/// Colors diagnostics only for an interactive stderr, honoring `NO_COLOR`.
+fn use_color() -> bool {
+    io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
 }
 
+/// Top-level help: every command with its usage, plus exit codes and env vars.
 fn usage(bin_name: &str) -> String {
-    format!(
-        "Usage:\n  {bin_name} check <file.saft> [--autofmt]\n  {bin_name} run <file.saft> [options]\n  {bin_name} fmt <file.saft> [--write|--check]\n  {bin_name} <file.saft> [options]\n\nOptions (run/check):\n  --provider mock|openrouter|none\n  --api-key-env ENV\n  --model NAME\n  --temperature N\n  --max-tool-rounds N\n  --max-tool-calls N\n  --autofmt\n\nOptions (fmt):\n  --write   write formatted output back to file\n  --check   fail if file is not already formatted\n\nDefault values can be set once with env vars:\n  ORANGENSAFT_PROVIDER=mock|openrouter|none\n  ORANGENSAFT_API_KEY_ENV=OPENROUTER_API_KEY\n  ORANGENSAFT_MODEL=openai/gpt-4o-mini\n  ORANGENSAFT_TEMPERATURE=0\n  ORANGENSAFT_MAX_TOOL_ROUNDS=8\n  ORANGENSAFT_MAX_TOOL_CALLS=32"
-    )
+    let mut out = format!("Usage:\n  {bin_name} <command> [options]\n\nCommands:\n");
+    for spec in COMMANDS {
+        for line in spec.usage {
+            out.push_str(&format!("  {bin_name} {line}\n"));
+        }
+    }
+    out.push_str(&format!(
+        "\nRun '{bin_name} <command> --help' for a command's options, or '{bin_name} --version'.\n\n\
+         Exit codes:\n  1 other failure (I/O, unformatted file)\n  2 usage error\n  3 parse/resolve error\n  \
+         4 runtime error\n  5 provider error\n  6 assertion failed\n  7 --timeout exceeded\n\n\
+         Default values for run can be set in orangensaft.toml or with env vars:\n  ORANGENSAFT_PROVIDER=mock|openrouter|ollama|fixture:PATH|none\n  ORANGENSAFT_API_KEY_ENV=OPENROUTER_API_KEY\n  ORANGENSAFT_MODEL=openai/gpt-4o-mini\n  ORANGENSAFT_TEMPERATURE=0\n  ORANGENSAFT_MAX_TOOL_ROUNDS=8\n  ORANGENSAFT_MAX_TOOL_CALLS=32\n  ORANGENSAFT_EMBEDDING_PROVIDER=mock|openai|openrouter|ollama|none\n  ORANGENSAFT_EMBEDDING_MODEL=text-embedding-3-small\n  ORANGENSAFT_TRANSCRIPT=transcript.jsonl\n  ORANGENSAFT_REQUESTS_PER_MINUTE=60\n  ORANGENSAFT_MAX_CONCURRENT_REQUESTS=4\n  ORANGENSAFT_TOOL_TIMEOUT_MS=5000\n  ORANGENSAFT_TIMEOUT=120\n  ORANGENSAFT_SEED=42\n  ORANGENSAFT_LOG=1\n  ORANGENSAFT_CACHE=1"
+    ));
+    out
+}
+
+/// `<command> --help`: usage lines, description, and an aligned flag table.
+fn command_help(spec: &CommandSpec, bin_name: &str) -> String {
+    let mut out = String::from("Usage:\n");
+    for line in spec.usage {
+        out.push_str(&format!("  {bin_name} {line}\n"));
+    }
+    out.push_str(&format!("\n{}\n\nOptions:\n", spec.about));
+
+    let label = |flag: &FlagSpec| {
+        let names = match flag.short {
+            Some(short) => format!("{short}, {}", flag.long),
+            None => flag.long.to_string(),
+        };
+        match flag.value {
+            Some(value) => format!("{names} {value}"),
+            None => names,
+        }
+    };
+    let help_flag = ("-h, --help".to_string(), "print this help");
+    let rows = spec
+        .flags
+        .iter()
+        .map(|flag| (label(flag), flag.help))
+        .chain(std::iter::once(help_flag))
+        .collect::<Vec<_>>();
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    for (label, help) in rows {
+        out.push_str(&format!("  {label:<width$}  {help}\n"));
+    }
+    out.truncate(out.trim_end().len());
+    out
 }
 
 fn parse_usize_option(name: &str, raw: &str) -> Result<usize, String> {
@@ -356,6 +2140,32 @@ fn parse_usize_option(name: &str, raw: &str) -> Result<usize, String> {
         .map_err(|_| format!("invalid value for {name}: '{raw}'"))
 }
 
+fn parse_positive_usize_option(name: &str, raw: &str) -> Result<usize, String> {
+    match parse_usize_option(name, raw)? {
+        0 => Err(format!(
+            "invalid value for {name}: '{raw}' (must be at least 1)"
+        )),
+        value => Ok(value),
+    }
+}
+
+fn parse_u64_option(name: &str, raw: &str) -> Result<u64, String> {
+    raw.parse::<u64>()
+        .map_err(|_| format!("invalid value for {name}: '{raw}'"))
+}
+
+/// On/off settings such as `ORANGENSAFT_LOG` (the same trace output as
+/// `--verbose`) and `ORANGENSAFT_CACHE`.
+fn parse_switch_option(name: &str, raw: &str) -> Result<bool, String> {
+    match raw {
+        "" | "0" | "off" | "false" => Ok(false),
+        "1" | "on" | "verbose" | "true" => Ok(true),
+        other => Err(format!(
+            "invalid value for {name}: '{other}' (expected 1, on, true, 0, off, or false)"
+        )),
+    }
+}
+
 fn parse_f32_option(name: &str, raw: &str) -> Result<f32, String> {
     raw.parse::<f32>()
         .map_err(|_| format!("invalid value for {name}: '{raw}'"))
@@ -379,13 +2189,13 @@ mod tests {
         match command {
             Command::Run {
                 file,
-                provider,
+                options,
                 autofmt,
                 ..
             } => {
                 assert_eq!(file, "examples/11_simple_array_op_2.saft");
-                assert_eq!(provider, ProviderKind::None);
-                assert!(!autofmt);
+                assert_eq!(options.provider, ProviderKind::None);
+                assert!(autofmt.is_none());
             }
             other => panic!("expected run command, got {other:?}"),
         }
@@ -404,13 +2214,13 @@ mod tests {
         match command {
             Command::Run {
                 file,
-                provider,
+                options,
                 autofmt,
                 ..
             } => {
                 assert_eq!(file, "examples/11_simple_array_op_2.saft");
-                assert_eq!(provider, ProviderKind::None);
-                assert!(!autofmt);
+                assert_eq!(options.provider, ProviderKind::None);
+                assert!(autofmt.is_none());
             }
             other => panic!("expected run command, got {other:?}"),
         }
@@ -427,10 +2237,16 @@ mod tests {
 
         let command = parse_args(&args).expect("expected fmt command to parse");
         match command {
-            Command::Fmt { file, check, write } => {
-                assert_eq!(file, "examples/11_simple_array_op_2.saft");
+            Command::Fmt {
+                paths,
+                check,
+                write,
+                overrides,
+            } => {
+                assert_eq!(paths, vec!["examples/11_simple_array_op_2.saft"]);
                 assert!(check);
                 assert!(!write);
+                assert_eq!(overrides, FormatOverrides::default());
             }
             other => panic!("expected fmt command, got {other:?}"),
         }
@@ -447,7 +2263,93 @@ mod tests {
 
         let command = parse_args(&args).expect("expected run command to parse");
         match command {
-            Command::Run { autofmt, .. } => assert!(autofmt),
+            Command::Run { autofmt, .. } => assert_eq!(autofmt, Some(FormatOptions::default())),
+            other => panic!("expected run command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_fixture_provider_path() {
+        let args = vec![
+            "orangensaft".to_string(),
+            "examples/06_function_map.saft".to_string(),
+            "--provider".to_string(),
+            "fixture:examples/fixtures/06_function_map.json".to_string(),
+        ];
+
+        let command = parse_args(&args).expect("expected run command to parse");
+        match command {
+            Command::Run { options, .. } => assert_eq!(
+                options.provider,
+                ProviderKind::Fixture("examples/fixtures/06_function_map.json".to_string())
+            ),
+            other => panic!("expected run command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn capabilities_are_opt_in() {
+        let base = vec![
+            "orangensaft".to_string(),
+            "run".to_string(),
+            "examples/11_simple_array_op_2.saft".to_string(),
+        ];
+        let mut with_flags = base.clone();
+        with_flags.push("--allow-net".to_string());
+        with_flags.push("--allow-exec".to_string());
+
+        for (args, expected) in [(base, false), (with_flags, true)] {
+            match parse_args(&args).expect("expected run command to parse") {
+                Command::Run { options, .. } => {
+                    assert_eq!(options.allow_net, expected);
+                    assert_eq!(options.allow_exec, expected);
+                }
+                other => panic!("expected run command, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_seed_option() {
+        let args = vec![
+            "orangensaft".to_string(),
+            "run".to_string(),
+            "examples/11_simple_array_op_2.saft".to_string(),
+            "--seed".to_string(),
+            "42".to_string(),
+        ];
+
+        let command = parse_args(&args).expect("expected run command to parse");
+        match command {
+            Command::Run { options, .. } => assert_eq!(options.seed, Some(42)),
+            other => panic!("expected run command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn collects_script_args_after_double_dash() {
+        let args = vec![
+            "orangensaft".to_string(),
+            "run".to_string(),
+            "report.saft".to_string(),
+            "--provider".to_string(),
+            "none".to_string(),
+            "--".to_string(),
+            "data.csv".to_string(),
+            "--seed".to_string(),
+        ];
+
+        let command = parse_args(&args).expect("expected run command to parse");
+        match command {
+            Command::Run {
+                options,
+                script_args,
+                ..
+            } => {
+                assert_eq!(options.provider, ProviderKind::None);
+                assert_eq!(options.seed, None);
+                assert_eq!(script_args, vec!["data.csv", "--seed"]);
+            }
             other => panic!("expected run command, got {other:?}"),
         }
     }
@@ -463,11 +2365,152 @@ mod tests {
 
         let command = parse_args(&args).expect("expected check command to parse");
         match command {
-            Command::Check { file, autofmt } => {
-                assert_eq!(file, "examples/11_simple_array_op_2.saft");
+            Command::Check { paths, autofmt, .. } => {
+                assert_eq!(paths, vec!["examples/11_simple_array_op_2.saft"]);
                 assert!(autofmt);
             }
             other => panic!("expected check command, got {other:?}"),
         }
     }
+
+    #[test]
+    fn stdin_scripts_reject_write_back_and_tool_prompts() {
+        let fmt = vec![
+            "orangensaft".to_string(),
+            "fmt".to_string(),
+            "-".to_string(),
+            "--write".to_string(),
+        ];
+        let err = parse_args(&fmt).expect_err("fmt --write needs a file");
+        assert!(
+            err.contains("cannot be used when reading from stdin"),
+            "{err}"
+        );
+
+        let run = vec![
+            "orangensaft".to_string(),
+            "-".to_string(),
+            "--approve-tools".to_string(),
+        ];
+        let err = parse_args(&run).expect_err("tool prompts need stdin");
+        assert!(err.contains("--approve-tools cannot be used"), "{err}");
+    }
+
+    #[test]
+    fn parses_error_format_option() {
+        let args = vec![
+            "orangensaft".to_string(),
+            "check".to_string(),
+            "report.saft".to_string(),
+            "--error-format".to_string(),
+            "json".to_string(),
+        ];
+        match parse_args(&args).expect("expected check command to parse") {
+            Command::Check { error_format, .. } => assert_eq!(error_format, ErrorFormat::Json),
+            other => panic!("expected check command, got {other:?}"),
+        }
+
+        let mut invalid = args.clone();
+        invalid[4] = "xml".to_string();
+        let err = parse_args(&invalid).expect_err("unknown formats should fail");
+        assert!(err.contains("invalid error format 'xml'"), "{err}");
+    }
+
+    #[test]
+    fn fmt_stdin_flag_reads_piped_source() {
+        let args = vec![
+            "orangensaft".to_string(),
+            "fmt".to_string(),
+            "--stdin".to_string(),
+            "--check".to_string(),
+        ];
+        match parse_args(&args).expect("expected fmt command to parse") {
+            Command::Fmt { paths, check, .. } => {
+                assert_eq!(paths, vec![STDIN_PATH]);
+                assert!(check);
+            }
+            other => panic!("expected fmt command, got {other:?}"),
+        }
+
+        let mut mixed = args.clone();
+        mixed.push("report.saft".to_string());
+        let err = parse_args(&mixed).expect_err("stdin and files cannot mix");
+        assert!(err.contains("cannot mix stdin"), "{err}");
+    }
+
+    #[test]
+    fn flags_accept_inline_values_and_reject_misplaced_ones() {
+        let args = ["orangensaft", "run", "-", "--seed=7", "-v"].map(String::from);
+        match parse_args(&args).expect("expected run command to parse") {
+            Command::Run { options, .. } => {
+                assert_eq!(options.seed, Some(7));
+                assert!(options.verbose);
+            }
+            other => panic!("expected run command, got {other:?}"),
+        }
+
+        let switch_value = ["orangensaft", "fmt", "-", "--check=yes"].map(String::from);
+        let err = parse_args(&switch_value).expect_err("switches take no value");
+        assert!(err.contains("'--check' does not take a value"), "{err}");
+
+        let rest = ["orangensaft", "check", "a.saft", "--", "b"].map(String::from);
+        let err = parse_args(&rest).expect_err("check has no script args");
+        assert!(err.contains("does not take arguments after --"), "{err}");
+
+        let extra = ["orangensaft", "run", "a.saft", "b.saft"].map(String::from);
+        let err = parse_args(&extra).expect_err("run takes one script");
+        assert!(err.contains("pass script arguments after --"), "{err}");
+    }
+
+    #[test]
+    fn suggests_only_close_matches() {
+        let flags = ["--provider", "--model", "--max-tool-rounds"];
+        assert_eq!(suggest("--modle", flags.into_iter()), Some("--model"));
+        assert_eq!(
+            suggest("--max-tool-round", flags.into_iter()),
+            Some("--max-tool-rounds")
+        );
+        assert_eq!(suggest("--bogus", flags.into_iter()), None);
+    }
+
+    #[test]
+    fn parses_typed_vars() {
+        let args = [
+            "orangensaft",
+            "run",
+            "report.saft",
+            "--var",
+            "city=berlin",
+            "--var",
+            "limit:int=10",
+            "--var=tags:json=[1, 2]",
+        ]
+        .map(String::from);
+        match parse_args(&args).expect("expected run command to parse") {
+            Command::Run { vars, .. } => assert_eq!(
+                vars,
+                vec![
+                    ("city".to_string(), JsonValue::from("berlin")),
+                    ("limit".to_string(), JsonValue::from(10)),
+                    ("tags".to_string(), serde_json::json!([1, 2])),
+                ]
+            ),
+            other => panic!("expected run command, got {other:?}"),
+        }
+
+        let mut vars = Vec::new();
+        for (raw, expected) in [
+            ("2city=x", "expected NAME[:TYPE]=VALUE"),
+            ("len=3", "would shadow the builtin 'len'"),
+            ("n:int=ten", "invalid int value for --var n: 'ten'"),
+            ("when:date=today", "unknown --var type 'date'"),
+            ("city", "--var city needs a value"),
+        ] {
+            let err = push_var(&mut vars, raw, true).expect_err(raw);
+            assert!(err.contains(expected), "{raw}: {err}");
+        }
+        push_var(&mut vars, "city", false).expect("check accepts bare names");
+        let err = push_var(&mut vars, "city=x", true).expect_err("duplicate");
+        assert!(err.contains("more than once"), "{err}");
+    }
 }
@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{SaftError, SaftResult};
+
+/// Per-project settings file, looked up from the script's directory upwards.
+pub const CONFIG_FILE: &str = "orangensaft.toml";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl ConfigValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ConfigValue::String(_) => "string",
+            ConfigValue::Int(_) => "int",
+            ConfigValue::Float(_) => "float",
+            ConfigValue::Bool(_) => "bool",
+        }
+    }
+}
+
+/// A parsed `orangensaft.toml`. Only the TOML subset the settings need is
+/// supported: `[section]` headers and `key = value` lines holding strings,
+/// integers, floats, or booleans, with `#` comments.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub path: Option<PathBuf>,
+    sections: BTreeMap<String, BTreeMap<String, ConfigValue>>,
+}
+
+impl Config {
+    /// Finds the nearest `orangensaft.toml` in `start` or one of its parents;
+    /// an empty config when there is none.
+    pub fn discover(start: &Path) -> SaftResult<Self> {
+        let start = start.canonicalize().unwrap_or_else(|_| start.to_path_buf());
+        for dir in start.ancestors() {
+            let path = dir.join(CONFIG_FILE);
+            if path.is_file() {
+                return Self::load(&path);
+            }
+        }
+        Ok(Self::default())
+    }
+
+    pub fn load(path: &Path) -> SaftResult<Self> {
+        let source = fs::read_to_string(path)
+            .map_err(|err| SaftError::new(format!("failed to read '{}': {err}", path.display())))?;
+        Self::parse(&source, path)
+    }
+
+    pub fn parse(source: &str, path: &Path) -> SaftResult<Self> {
+        let mut sections: BTreeMap<String, BTreeMap<String, ConfigValue>> = BTreeMap::new();
+        let mut section = String::new();
+        for (index, raw) in source.lines().enumerate() {
+            let fail = |message: String| {
+                SaftError::new(format!("{}:{}: {message}", path.display(), index + 1))
+            };
+            let line = strip_comment(raw).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .map(str::trim)
+                    .filter(|name| is_bare_key(name))
+                    .ok_or_else(|| fail(format!("invalid section header '{line}'")))?;
+                section = name.to_string();
+                sections.entry(section.clone()).or_default();
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| fail(format!("expected 'key = value', got '{line}'")))?;
+            let key = key.trim();
+            if !is_bare_key(key) {
+                return Err(fail(format!("invalid key '{key}'")));
+            }
+            let value = parse_value(value.trim()).map_err(fail)?;
+            if sections
+                .entry(section.clone())
+                .or_default()
+                .insert(key.to_string(), value)
+                .is_some()
+            {
+                return Err(fail(format!("duplicate key '{key}'")));
+            }
+        }
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            sections,
+        })
+    }
+
+    /// Key/value pairs of `[name]`, in key order.
+    pub fn section(&self, name: &str) -> impl Iterator<Item = (&str, &ConfigValue)> {
+        self.sections
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), value))
+    }
+
+    pub fn section_names(&self) -> impl Iterator<Item = &str> {
+        self.sections.keys().map(String::as_str)
+    }
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+/// Drops a trailing `# comment`, leaving `#` inside strings alone.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, ch) in line.char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(raw: &str) -> Result<ConfigValue, String> {
+    if let Some(body) = raw.strip_prefix('"') {
+        let body = body
+            .strip_suffix('"')
+            .ok_or_else(|| format!("unterminated string {raw}"))?;
+        let mut out = String::with_capacity(body.len());
+        let mut chars = body.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '\\' {
+                out.push(ch);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                other => {
+                    return Err(format!(
+                        "unsupported escape '\\{}' in string",
+                        other.map(String::from).unwrap_or_default()
+                    ));
+                }
+            }
+        }
+        return Ok(ConfigValue::String(out));
+    }
+    match raw {
+        "true" => return Ok(ConfigValue::Bool(true)),
+        "false" => return Ok(ConfigValue::Bool(false)),
+        _ => {}
+    }
+    let digits = raw.replace('_', "");
+    if let Ok(value) = digits.parse::<i64>() {
+        return Ok(ConfigValue::Int(value));
+    }
+    match digits.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(ConfigValue::Float(value)),
+        _ => Err(format!(
+            "invalid value '{raw}' (expected a quoted string, number, true, or false)"
+        )),
+    }
+}
@@ -0,0 +1,197 @@
+use std::env;
+
+use serde_json::{Value as JsonValue, json};
+
+use crate::error::{SaftError, SaftResult};
+use crate::provider::curl_post_json;
+
+const OPENAI_EMBEDDINGS_URL: &str = "https://api.openai.com/v1/embeddings";
+const OPENROUTER_EMBEDDINGS_URL: &str = "https://openrouter.ai/api/v1/embeddings";
+const DEFAULT_OLLAMA_HOST: &str = "http://localhost:11434";
+const DEFAULT_OPENAI_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+const DEFAULT_OPENROUTER_EMBEDDING_MODEL: &str = "openai/text-embedding-3-small";
+const DEFAULT_OLLAMA_EMBEDDING_MODEL: &str = "nomic-embed-text";
+const HASHING_DIMENSIONS: usize = 64;
+
+pub trait EmbeddingProvider {
+    fn embed(&mut self, text: &str) -> SaftResult<Vec<f64>>;
+}
+
+#[derive(Default)]
+pub struct NoopEmbeddingProvider;
+
+impl EmbeddingProvider for NoopEmbeddingProvider {
+    fn embed(&mut self, _text: &str) -> SaftResult<Vec<f64>> {
+        Err(SaftError::new(
+            "no embedding provider configured; use --embedding-provider to select one",
+        ))
+    }
+}
+
+/// Deterministic bag-of-words embedding for local runs and tests: each lowercase
+/// word is hashed into a fixed number of buckets and the counts are L2-normalized,
+/// so texts sharing vocabulary have a high cosine similarity.
+#[derive(Default)]
+pub struct HashingEmbeddingProvider;
+
+impl HashingEmbeddingProvider {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl EmbeddingProvider for HashingEmbeddingProvider {
+    fn embed(&mut self, text: &str) -> SaftResult<Vec<f64>> {
+        let mut vector = vec![0.0; HASHING_DIMENSIONS];
+        let lower = text.to_lowercase();
+        for word in lower
+            .split(|ch: char| !ch.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            let bucket = (fnv1a(word.as_bytes()) % HASHING_DIMENSIONS as u64) as usize;
+            vector[bucket] += 1.0;
+        }
+
+        let norm = vector.iter().map(|v| v * v).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            for value in &mut vector {
+                *value /= norm;
+            }
+        }
+        Ok(vector)
+    }
+}
+
+/// OpenAI-compatible `/embeddings` endpoint (OpenAI itself and OpenRouter).
+pub struct OpenAiEmbeddingProvider {
+    url: String,
+    api_key: String,
+    model: String,
+    service: &'static str,
+}
+
+impl OpenAiEmbeddingProvider {
+    pub fn openai_from_env(api_key_env: &str, model: Option<String>) -> SaftResult<Self> {
+        Self::from_env(
+            OPENAI_EMBEDDINGS_URL,
+            api_key_env,
+            model.unwrap_or_else(|| DEFAULT_OPENAI_EMBEDDING_MODEL.to_string()),
+            "OpenAI",
+        )
+    }
+
+    pub fn openrouter_from_env(api_key_env: &str, model: Option<String>) -> SaftResult<Self> {
+        Self::from_env(
+            OPENROUTER_EMBEDDINGS_URL,
+            api_key_env,
+            model.unwrap_or_else(|| DEFAULT_OPENROUTER_EMBEDDING_MODEL.to_string()),
+            "OpenRouter",
+        )
+    }
+
+    fn from_env(
+        url: &str,
+        api_key_env: &str,
+        model: String,
+        service: &'static str,
+    ) -> SaftResult<Self> {
+        let api_key = env::var(api_key_env).map_err(|_| {
+            SaftError::new(format!(
+                "missing API key in env var '{api_key_env}' for {service} embedding provider"
+            ))
+        })?;
+        if api_key.trim().is_empty() {
+            return Err(SaftError::new(format!("{service} API key is empty")));
+        }
+
+        Ok(Self {
+            url: url.to_string(),
+            api_key,
+            model,
+            service,
+        })
+    }
+}
+
+impl EmbeddingProvider for OpenAiEmbeddingProvider {
+    fn embed(&mut self, text: &str) -> SaftResult<Vec<f64>> {
+        let payload = json!({
+            "model": self.model,
+            "input": text,
+        });
+        let headers = vec![format!("Authorization: Bearer {}", self.api_key)];
+        let response = curl_post_json(&self.url, &headers, &payload, self.service, None)?;
+
+        let embedding = response
+            .get("data")
+            .and_then(JsonValue::as_array)
+            .and_then(|items| items.first())
+            .and_then(|item| item.get("embedding"))
+            .ok_or_else(|| {
+                SaftError::new(format!(
+                    "{} embedding response had no 'data[0].embedding'",
+                    self.service
+                ))
+            })?;
+        json_to_vector(embedding, self.service)
+    }
+}
+
+pub struct OllamaEmbeddingProvider {
+    host: String,
+    model: String,
+}
+
+impl OllamaEmbeddingProvider {
+    pub fn new(host: Option<String>, model: Option<String>) -> Self {
+        Self {
+            host: host
+                .unwrap_or_else(|| DEFAULT_OLLAMA_HOST.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            model: model.unwrap_or_else(|| DEFAULT_OLLAMA_EMBEDDING_MODEL.to_string()),
+        }
+    }
+}
+
+impl EmbeddingProvider for OllamaEmbeddingProvider {
+    fn embed(&mut self, text: &str) -> SaftResult<Vec<f64>> {
+        let payload = json!({
+            "model": self.model,
+            "input": text,
+        });
+        let url = format!("{}/api/embed", self.host);
+        let response = curl_post_json(&url, &[], &payload, "Ollama", None)?;
+
+        let embedding = response
+            .get("embeddings")
+            .and_then(JsonValue::as_array)
+            .and_then(|items| items.first())
+            .ok_or_else(|| SaftError::new("Ollama embedding response had no 'embeddings[0]'"))?;
+        json_to_vector(embedding, "Ollama")
+    }
+}
+
+fn json_to_vector(value: &JsonValue, service: &str) -> SaftResult<Vec<f64>> {
+    let items = value
+        .as_array()
+        .ok_or_else(|| SaftError::new(format!("{service} embedding is not an array")))?;
+
+    items
+        .iter()
+        .map(|item| {
+            item.as_f64().ok_or_else(|| {
+                SaftError::new(format!("{service} embedding contains a non-numeric value"))
+            })
+        })
+        .collect()
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
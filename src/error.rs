@@ -1,6 +1,8 @@
 use std::fmt;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -35,10 +37,22 @@ impl Span {
     }
 }
 
+/// Broad class of a failure, used by the CLI to pick an exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorKind {
+    #[default]
+    Runtime,
+    Provider,
+    Assertion,
+    /// The script ran past `RuntimeOptions::timeout`.
+    Timeout,
+}
+
 #[derive(Debug, Clone)]
 pub struct SaftError {
     pub message: String,
     pub span: Option<Span>,
+    pub kind: ErrorKind,
 }
 
 impl SaftError {
@@ -46,6 +60,7 @@ impl SaftError {
         Self {
             message: message.into(),
             span: None,
+            kind: ErrorKind::default(),
         }
     }
 
@@ -53,10 +68,37 @@ impl SaftError {
         Self {
             message: message.into(),
             span: Some(span),
+            kind: ErrorKind::default(),
         }
     }
 
+    pub fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Points a span-less error (e.g. from a builtin) at `span`; keeps an existing span.
+    pub fn or_span(mut self, span: Span) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+
     pub fn render(&self, file_path: &str, source: &str) -> String {
+        self.render_styled(file_path, source, false)
+    }
+
+    /// Like `render`, but with ANSI colors (red error and carets, blue path and
+    /// gutter) when `color` is set.
+    pub fn render_styled(&self, file_path: &str, source: &str, color: bool) -> String {
+        let paint = |code: &str, text: &str| {
+            if color {
+                format!("\x1b[{code}m{text}\x1b[0m")
+            } else {
+                text.to_string()
+            }
+        };
+        let label = format!("{}: {}", paint("1;31", "error"), paint("1", &self.message));
+
         match self.span {
             Some(span) => {
                 let line_text = source
@@ -69,20 +111,40 @@ impl SaftError {
                 let carets = "^".repeat(width.min(120));
 
                 format!(
-                    "error: {}\n  --> {}:{}:{}\n   |\n{:>3} | {}\n   | {}{}",
-                    self.message,
-                    file_path,
-                    span.line,
-                    span.col,
-                    span.line,
+                    "{label}\n  {} {}\n   {}\n{} {}\n   {} {}{}",
+                    paint("1;34", "-->"),
+                    paint("34", &format!("{file_path}:{}:{}", span.line, span.col)),
+                    paint("1;34", "|"),
+                    paint("1;34", &format!("{:>3} |", span.line)),
                     line_text,
+                    paint("1;34", "|"),
                     caret_pad,
-                    carets
+                    paint("1;31", &carets)
                 )
             }
-            None => format!("error: {} ({file_path})", self.message),
+            None => format!("{label} ({})", paint("34", file_path)),
         }
     }
+
+    /// Machine-readable form of `render`: one JSON object per diagnostic, with
+    /// `span` set to null when the error has no source location.
+    pub fn render_json(&self, file_path: &str) -> String {
+        let span = self.span.map(|span| {
+            serde_json::json!({
+                "start": span.start,
+                "end": span.end,
+                "line": span.line,
+                "col": span.col,
+            })
+        });
+        serde_json::json!({
+            "severity": "error",
+            "message": self.message,
+            "file": file_path,
+            "span": span,
+        })
+        .to_string()
+    }
 }
 
 impl fmt::Display for SaftError {
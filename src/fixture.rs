@@ -0,0 +1,198 @@
+use std::fs;
+
+use regex::Regex;
+use serde_json::Value as JsonValue;
+
+use crate::error::{SaftError, SaftResult};
+use crate::provider::{PromptProvider, PromptRequest, PromptResponse, ToolCall};
+
+/// Scripted provider driven by a JSON fixture file:
+///
+/// ```json
+/// {"rules": [
+///   {"contains": "2 + 3", "text": "5"},
+///   {"regex": "each verb", "steps": [
+///     {"tool_calls": [{"name": "my_func", "args": {"verb": "build"}}]},
+///     {"text": "[\"BUILD\"]"}
+///   ]}
+/// ]}
+/// ```
+///
+/// The first rule whose matcher hits the prompt answers it. Multi-step rules
+/// advance one step per tool round and restart when a fresh prompt arrives.
+pub struct FixtureProvider {
+    rules: Vec<FixtureRule>,
+    active: Option<(usize, usize)>,
+    next_call_id: usize,
+}
+
+struct FixtureRule {
+    matcher: FixtureMatcher,
+    steps: Vec<FixtureStep>,
+}
+
+enum FixtureMatcher {
+    Contains(String),
+    Regex(Regex),
+}
+
+enum FixtureStep {
+    Text(String),
+    ToolCalls(Vec<(String, JsonValue)>),
+}
+
+impl FixtureProvider {
+    pub fn from_file(path: &str) -> SaftResult<Self> {
+        let raw = fs::read_to_string(path).map_err(|err| {
+            SaftError::new(format!("could not read fixture file '{path}': {err}"))
+        })?;
+        Self::from_json_str(&raw).map_err(|err| {
+            SaftError::new(format!("invalid fixture file '{path}': {}", err.message))
+        })
+    }
+
+    pub fn from_json_str(raw: &str) -> SaftResult<Self> {
+        let parsed: JsonValue = serde_json::from_str(raw)
+            .map_err(|err| SaftError::new(format!("fixture is not valid JSON: {err}")))?;
+        let rules = parsed
+            .get("rules")
+            .and_then(JsonValue::as_array)
+            .ok_or_else(|| SaftError::new("fixture must be an object with a 'rules' array"))?
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| parse_rule(rule, index))
+            .collect::<SaftResult<Vec<_>>>()?;
+
+        Ok(Self {
+            rules,
+            active: None,
+            next_call_id: 1,
+        })
+    }
+}
+
+impl PromptProvider for FixtureProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        let (rule_index, step_index) = match self.active {
+            Some((rule_index, step_index)) if !request.tool_results.is_empty() => {
+                let last = self.rules[rule_index].steps.len() - 1;
+                (rule_index, (step_index + 1).min(last))
+            }
+            _ => {
+                let rule_index = self
+                    .rules
+                    .iter()
+                    .position(|rule| rule.matcher.matches(&request.prompt))
+                    .ok_or_else(|| {
+                        SaftError::new(format!(
+                            "fixture has no rule matching prompt: {}",
+                            truncate(&request.prompt, 200)
+                        ))
+                    })?;
+                (rule_index, 0)
+            }
+        };
+        self.active = Some((rule_index, step_index));
+
+        match &self.rules[rule_index].steps[step_index] {
+            FixtureStep::Text(text) => Ok(PromptResponse::FinalText(text.clone())),
+            FixtureStep::ToolCalls(calls) => {
+                let mut out = Vec::with_capacity(calls.len());
+                for (name, args) in calls {
+                    out.push(ToolCall {
+                        id: format!("fixture_call_{}", self.next_call_id),
+                        name: name.clone(),
+                        args: args.clone(),
+                    });
+                    self.next_call_id += 1;
+                }
+                Ok(PromptResponse::ToolCalls(out))
+            }
+        }
+    }
+}
+
+impl FixtureMatcher {
+    fn matches(&self, prompt: &str) -> bool {
+        match self {
+            FixtureMatcher::Contains(needle) => prompt.contains(needle.as_str()),
+            FixtureMatcher::Regex(regex) => regex.is_match(prompt),
+        }
+    }
+}
+
+fn parse_rule(rule: &JsonValue, index: usize) -> SaftResult<FixtureRule> {
+    let matcher = match (
+        rule.get("contains").and_then(JsonValue::as_str),
+        rule.get("regex").and_then(JsonValue::as_str),
+    ) {
+        (Some(needle), None) => FixtureMatcher::Contains(needle.to_string()),
+        (None, Some(pattern)) => FixtureMatcher::Regex(
+            Regex::new(pattern)
+                .map_err(|err| SaftError::new(format!("rule {index} has invalid regex: {err}")))?,
+        ),
+        _ => {
+            return Err(SaftError::new(format!(
+                "rule {index} needs exactly one of 'contains' or 'regex'"
+            )));
+        }
+    };
+
+    let steps = match (rule.get("text"), rule.get("steps")) {
+        (Some(_), None) => vec![parse_step(rule, index)?],
+        (None, Some(JsonValue::Array(steps))) if !steps.is_empty() => steps
+            .iter()
+            .map(|step| parse_step(step, index))
+            .collect::<SaftResult<Vec<_>>>()?,
+        _ => {
+            return Err(SaftError::new(format!(
+                "rule {index} needs either 'text' or a non-empty 'steps' array"
+            )));
+        }
+    };
+
+    Ok(FixtureRule { matcher, steps })
+}
+
+fn parse_step(step: &JsonValue, index: usize) -> SaftResult<FixtureStep> {
+    if let Some(text) = step.get("text") {
+        return match text {
+            JsonValue::String(text) => Ok(FixtureStep::Text(text.clone())),
+            other => Ok(FixtureStep::Text(other.to_string())),
+        };
+    }
+
+    let calls = step
+        .get("tool_calls")
+        .and_then(JsonValue::as_array)
+        .ok_or_else(|| {
+            SaftError::new(format!(
+                "rule {index} step needs either 'text' or 'tool_calls'"
+            ))
+        })?;
+    calls
+        .iter()
+        .map(|call| {
+            let name = call
+                .get("name")
+                .and_then(JsonValue::as_str)
+                .ok_or_else(|| {
+                    SaftError::new(format!("rule {index} tool call is missing 'name'"))
+                })?;
+            let args = call
+                .get("args")
+                .cloned()
+                .unwrap_or_else(|| JsonValue::Object(Default::default()));
+            Ok((name.to_string(), args))
+        })
+        .collect::<SaftResult<Vec<_>>>()
+        .map(FixtureStep::ToolCalls)
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let mut out: String = text.chars().take(max_chars).collect();
+    if text.chars().count() > max_chars {
+        out.push_str("...");
+    }
+    out
+}
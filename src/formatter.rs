@@ -1,10 +1,11 @@
+use std::collections::VecDeque;
+
 use crate::ast::{
-    BinaryOp, Expr, FnDef, FnParam, Pattern, Program, PromptExpr, PromptPart, SchemaExpr, Stmt,
-    UnaryOp,
+    BinaryOp, Expr, FnDef, Pattern, Program, PromptExpr, PromptPart, SchemaExpr, Stmt, UnaryOp,
 };
-use crate::error::SaftResult;
+use crate::error::{SaftResult, Span};
+use crate::token::{Comment, TokenKind};
 
-const INDENT: &str = "    ";
 const PREC_OR: u8 = 1;
 const PREC_AND: u8 = 2;
 const PREC_COMPARE: u8 = 3;
@@ -13,125 +14,658 @@ const PREC_MUL: u8 = 5;
 const PREC_UNARY: u8 = 6;
 const PREC_POSTFIX: u8 = 7;
 
+/// Lines longer than this get their call arguments, list items, or object
+/// fields wrapped one per line.
+pub const DEFAULT_MAX_WIDTH: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub max_width: usize,
+    /// Longest run of blank lines kept between statements; 0 drops them all.
+    pub max_blank_lines: usize,
+    /// Spaces per block level, also used for wrapped items.
+    pub indent_width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            max_width: DEFAULT_MAX_WIDTH,
+            max_blank_lines: 1,
+            indent_width: 4,
+        }
+    }
+}
+
 pub fn format_source(source: &str) -> SaftResult<String> {
-    let tokens = crate::lexer::lex(source)?;
+    format_source_with_options(source, &FormatOptions::default())
+}
+
+pub fn format_source_with_options(source: &str, options: &FormatOptions) -> SaftResult<String> {
+    let (tokens, trivia) = crate::lexer::lex_with_trivia(source)?;
+    let else_offsets = tokens
+        .iter()
+        .filter(|token| token.kind == TokenKind::Else)
+        .map(|token| token.span.start)
+        .collect();
     let program = crate::parser::parse(tokens)?;
-    Ok(format_program(&program))
+    let (comments, object_comments) = claim_object_comments(&program, source, trivia.comments);
+    let mut formatter = Formatter {
+        source,
+        object_comments: &object_comments,
+        out: String::new(),
+        comments: comments.into(),
+        blank_lines: trivia.blank_lines.into(),
+        at_block_start: true,
+        else_offsets,
+        options: options.clone(),
+    };
+    formatter.write_program(&program);
+    Ok(formatter.out)
 }
 
+/// Formats a program without its comments and blank lines; `format_source`
+/// keeps them.
 pub fn format_program(program: &Program) -> String {
-    let mut out = String::new();
-    for stmt in &program.stmts {
-        write_stmt(&mut out, stmt, 0);
+    let mut formatter = Formatter {
+        source: "",
+        object_comments: &[],
+        out: String::new(),
+        comments: VecDeque::new(),
+        blank_lines: VecDeque::new(),
+        at_block_start: true,
+        else_offsets: Vec::new(),
+        options: FormatOptions::default(),
+    };
+    formatter.write_program(program);
+    formatter.out
+}
+
+/// Writes statements while threading the source comments through them.
+///
+/// A comment goes before the first statement that starts after it, at that
+/// statement's indentation, unless it trails code on the same line (kept at
+/// the end of that line) or sits below the last statement of a block at least
+/// as far indented as the block (kept at the end of the block).
+///
+/// Comments inside an object written over several lines stay inside it,
+/// beside its fields (see `expand_object`).
+///
+/// Blank lines between statements and comments are kept, up to
+/// `FormatOptions::max_blank_lines` in a row, except at the start of a block
+/// or the file, inside a statement, and at the end of the file.
+struct Formatter<'a> {
+    /// Text the program was parsed from; string literals are copied from it.
+    source: &'a str,
+    /// Comments written inside expanded objects, by object start offset.
+    object_comments: &'a [(usize, Comment)],
+    out: String,
+    comments: VecDeque<Comment>,
+    blank_lines: VecDeque<usize>,
+    /// Nothing written yet in the current block (or file).
+    at_block_start: bool,
+    /// Where each `else` keyword starts; comments above it stay above it.
+    else_offsets: Vec<usize>,
+    options: FormatOptions,
+}
+
+/// Indentation of the line an expression starts on, which multi-line prompt
+/// blocks and objects need, the source that string literals are copied from
+/// and objects are checked against for line breaks, and the comments kept
+/// inside expanded objects.
+#[derive(Debug, Clone, Copy)]
+struct Layout<'a> {
+    indent: usize,
+    indent_width: usize,
+    source: &'a str,
+    object_comments: &'a [(usize, Comment)],
+    /// Inside a prompt interpolation, whose spans are relative to the
+    /// interpolation rather than `source`.
+    in_prompt: bool,
+}
+
+impl Layout<'_> {
+    fn nested(self) -> Self {
+        Self {
+            indent: self.indent + 1,
+            ..self
+        }
+    }
+
+    /// Whether the `{ ... }` at `span` was written over several lines, and so
+    /// stays expanded.
+    fn spans_lines(self, span: Span) -> bool {
+        !self.in_prompt
+            && self
+                .source
+                .get(span.start..span.end)
+                .is_some_and(|text| text.starts_with('{') && text.contains('\n'))
     }
-    out
 }
 
-fn write_stmt(out: &mut String, stmt: &Stmt, indent: usize) {
-    match stmt {
-        Stmt::FnDef(def) => write_fn_def(out, def, indent),
-        Stmt::Assign {
-            name,
-            annotation,
-            value,
-            ..
-        } => {
-            write_indent(out, indent);
-            out.push_str(name);
-            if let Some(schema) = annotation {
-                out.push_str(": ");
-                out.push_str(&format_schema(schema));
+impl<'a> Formatter<'a> {
+    fn layout(&self, indent: usize) -> Layout<'a> {
+        Layout {
+            source: self.source,
+            object_comments: self.object_comments,
+            in_prompt: false,
+            indent,
+            indent_width: self.options.indent_width,
+        }
+    }
+
+    fn write_program(&mut self, program: &Program) {
+        self.write_block(&program.stmts, 0, usize::MAX);
+        while let Some(comment) = self.comments.pop_front() {
+            self.write_comment(&comment, 0);
+        }
+    }
+
+    /// `end` is where the source region of the block stops: the start of
+    /// whatever follows it.
+    fn write_block(&mut self, block: &[Stmt], indent: usize, end: usize) {
+        for (index, stmt) in block.iter().enumerate() {
+            self.write_comments_before(stmt.span().start, indent);
+            self.write_blank_lines(stmt.span().start);
+            let next = block.get(index + 1).map_or(end, |next| next.span().start);
+            self.write_stmt(stmt, indent, next);
+        }
+
+        let column = block.first().map_or(1, |stmt| stmt.span().col);
+        while let Some(comment) = self.comments.front() {
+            if !comment.own_line || comment.span.start >= end || comment.span.col < column {
+                break;
             }
-            out.push_str(" = ");
-            out.push_str(&format_expr(value, 0));
-            out.push('\n');
+            let comment = self.comments.pop_front().expect("front comment exists");
+            self.write_comment(&comment, indent);
         }
-        Stmt::If {
-            cond,
-            then_block,
-            else_block,
-            ..
-        } => {
-            write_indent(out, indent);
-            out.push_str("if ");
-            out.push_str(&format_expr(cond, 0));
-            out.push_str(":\n");
-            write_block(out, then_block, indent + 1);
-            if let Some(block) = else_block {
-                write_indent(out, indent);
-                out.push_str("else:\n");
-                write_block(out, block, indent + 1);
+    }
+
+    fn write_stmt(&mut self, stmt: &Stmt, indent: usize, end: usize) {
+        match stmt {
+            Stmt::FnDef(def) => self.write_fn_def(def, indent, end),
+            Stmt::Assign {
+                name,
+                annotation,
+                value,
+                span,
+            } => {
+                let mut line = name.clone();
+                if let Some(schema) = annotation {
+                    line.push_str(": ");
+                    line.push_str(&self.format_wrapped_schema(schema, indent, line.len(), 3));
+                }
+                line.push_str(" = ");
+                let used = self.column(&line, indent);
+                line.push_str(&self.format_wrapped(value, indent, used));
+                self.write_simple(&line, indent, span.end);
+            }
+            Stmt::If {
+                cond,
+                then_block,
+                else_block,
+                ..
+            } => {
+                self.write_header(
+                    &format!("if {}:", format_expr(cond, 0, self.layout(indent))),
+                    indent,
+                    then_block,
+                );
+                let Some(block) = else_block else {
+                    self.write_block(then_block, indent + 1, end);
+                    return;
+                };
+                let then_end = then_block.last().map_or(0, |stmt| stmt.span().end);
+                let else_start = self
+                    .else_offsets
+                    .iter()
+                    .copied()
+                    .find(|offset| *offset >= then_end)
+                    .unwrap_or(then_end);
+                self.write_block(then_block, indent + 1, else_start);
+                self.write_comments_before(else_start, indent);
+                self.write_blank_lines(else_start);
+                self.write_header("else:", indent, block);
+                self.write_block(block, indent + 1, end);
+            }
+            Stmt::For {
+                pattern,
+                iter,
+                body,
+                ..
+            } => {
+                let header = format!(
+                    "for {} in {}:",
+                    format_pattern(pattern),
+                    format_expr(iter, 0, self.layout(indent))
+                );
+                self.write_header(&header, indent, body);
+                self.write_block(body, indent + 1, end);
+            }
+            Stmt::Return { value, span } => {
+                let line = match value {
+                    Some(expr) => format!("ret {}", self.format_wrapped(expr, indent, 4)),
+                    None => "ret".to_string(),
+                };
+                self.write_simple(&line, indent, span.end);
+            }
+            Stmt::Assert { expr, span } => {
+                let line = format!("assert {}", self.format_wrapped(expr, indent, 7));
+                self.write_simple(&line, indent, span.end);
+            }
+            Stmt::Expr { expr, span } => {
+                self.write_simple(&self.format_wrapped(expr, indent, 0), indent, span.end);
             }
         }
-        Stmt::For {
-            pattern,
-            iter,
-            body,
-            ..
-        } => {
-            write_indent(out, indent);
-            out.push_str("for ");
-            out.push_str(&format_pattern(pattern));
-            out.push_str(" in ");
-            out.push_str(&format_expr(iter, 0));
-            out.push_str(":\n");
-            write_block(out, body, indent + 1);
-        }
-        Stmt::Return { value, .. } => {
-            write_indent(out, indent);
-            out.push_str("ret");
-            if let Some(expr) = value {
-                out.push(' ');
-                out.push_str(&format_expr(expr, 0));
+    }
+
+    fn write_fn_def(&mut self, def: &FnDef, indent: usize, end: usize) {
+        let mut header = format!("f {}(", def.name);
+        for (index, param) in def.params.iter().enumerate() {
+            if index > 0 {
+                header.push_str(", ");
+            }
+            header.push_str(&param.name);
+            if let Some(schema) = &param.schema {
+                header.push_str(": ");
+                let used = self.column(&header, indent);
+                header.push_str(&self.format_wrapped_schema(schema, indent, used, 1));
+            }
+        }
+        header.push(')');
+        if let Some(schema) = &def.return_schema {
+            header.push_str(" -> ");
+            let used = self.column(&header, indent);
+            header.push_str(&self.format_wrapped_schema(schema, indent, used, 1));
+        }
+        header.push(':');
+        self.write_header(&header, indent, &def.body);
+        self.write_block(&def.body, indent + 1, end);
+    }
+
+    /// A one-line statement ending at `end`. Comments on its own lines inside
+    /// a multi-line expression move above it; trailing ones follow it.
+    fn write_simple(&mut self, line: &str, indent: usize, end: usize) {
+        self.skip_blank_lines(end);
+        let mut trailing = Vec::new();
+        while self
+            .comments
+            .front()
+            .is_some_and(|comment| comment.span.start < end)
+        {
+            let comment = self.comments.pop_front().expect("front comment exists");
+            if comment.own_line {
+                self.write_line(&comment.text, indent, &[]);
+            } else {
+                trailing.push(comment.text);
             }
-            out.push('\n');
         }
-        Stmt::Assert { expr, .. } => {
-            write_indent(out, indent);
-            out.push_str("assert ");
-            out.push_str(&format_expr(expr, 0));
-            out.push('\n');
+        self.write_line(line, indent, &trailing);
+    }
+
+    /// The `...:` line of a compound statement, keeping comments that trail
+    /// it; comments on the lines below it belong to the body.
+    fn write_header(&mut self, line: &str, indent: usize, body: &[Stmt]) {
+        let body_start = body.first().map_or(usize::MAX, |stmt| stmt.span().start);
+        let mut trailing = Vec::new();
+        while self
+            .comments
+            .front()
+            .is_some_and(|comment| !comment.own_line && comment.span.start < body_start)
+        {
+            trailing.push(
+                self.comments
+                    .pop_front()
+                    .expect("front comment exists")
+                    .text,
+            );
+        }
+        self.write_line(line, indent, &trailing);
+        self.at_block_start = true;
+    }
+
+    /// `expr` placed after `used` columns of a line at `indent`. When its
+    /// one-line form is too wide, an outermost call, list, or object is
+    /// broken into one argument/item/field per line with trailing commas,
+    /// recursively for items that are still too wide. Objects written over
+    /// several lines stay that way at any width.
+    fn format_wrapped(&self, expr: &Expr, indent: usize, used: usize) -> String {
+        self.format_wrapped_item(expr, indent, used, 0)
+    }
+
+    /// Like `format_wrapped`, with `trail` columns (e.g. a `,`) following.
+    fn format_wrapped_item(&self, expr: &Expr, indent: usize, used: usize, trail: usize) -> String {
+        let flat = format_expr(expr, 0, self.layout(indent));
+        let first_line = flat.lines().next().unwrap_or_default();
+        let width = indent * self.options.indent_width + used + first_line.chars().count() + trail;
+        let written_expanded = matches!(
+            expr,
+            Expr::Object(fields, span) if !fields.is_empty() && self.layout(indent).spans_lines(*span)
+        );
+        if width <= self.options.max_width && !written_expanded {
+            return flat;
         }
-        Stmt::Expr { expr, .. } => {
-            write_indent(out, indent);
-            out.push_str(&format_expr(expr, 0));
-            out.push('\n');
+
+        let (open, items, close) = match expr {
+            Expr::Call { callee, args, .. } if !args.is_empty() => (
+                format!(
+                    "{}(",
+                    format_expr(callee, PREC_POSTFIX, self.layout(indent))
+                ),
+                args.iter()
+                    .map(|arg| self.format_wrapped_item(arg, indent + 1, 0, 1))
+                    .collect::<Vec<_>>(),
+                ')',
+            ),
+            Expr::List(items, _) if !items.is_empty() => (
+                "[".to_string(),
+                items
+                    .iter()
+                    .map(|item| self.format_wrapped_item(item, indent + 1, 0, 1))
+                    .collect(),
+                ']',
+            ),
+            Expr::Object(fields, span) if !fields.is_empty() => {
+                let items = fields
+                    .iter()
+                    .map(|(name, value)| {
+                        let value = self.format_wrapped_item(value, indent + 1, name.len() + 2, 1);
+                        format!("{name}: {value}")
+                    })
+                    .collect();
+                return expand_object(fields, items, *span, self.layout(indent));
+            }
+            _ => return flat,
+        };
+        expand(open, items, close, self.layout(indent))
+    }
+
+    /// Like `format_wrapped_item` for a schema: an outermost object schema
+    /// that is too wide, or was written over several lines, gets one field
+    /// per line.
+    fn format_wrapped_schema(
+        &self,
+        schema: &SchemaExpr,
+        indent: usize,
+        used: usize,
+        trail: usize,
+    ) -> String {
+        let layout = self.layout(indent);
+        let flat = format_schema(schema, layout);
+        let first_line = flat.lines().next().unwrap_or_default();
+        let width = indent * self.options.indent_width + used + first_line.chars().count() + trail;
+        match schema {
+            SchemaExpr::Object(fields, span)
+                if width > self.options.max_width || layout.spans_lines(*span) =>
+            {
+                let items = fields
+                    .iter()
+                    .map(|field| {
+                        let schema = self.format_wrapped_schema(
+                            &field.schema,
+                            indent + 1,
+                            field.name.len() + 2,
+                            1,
+                        );
+                        format!("{}: {schema}", field.name)
+                    })
+                    .collect();
+                expand("{".to_string(), items, '}', layout)
+            }
+            _ => flat,
+        }
+    }
+
+    /// Columns used on the last line of `text`, which starts a line at
+    /// `indent`, not counting that indentation.
+    fn column(&self, text: &str, indent: usize) -> usize {
+        match text.rsplit_once('\n') {
+            Some((_, last)) => last
+                .chars()
+                .count()
+                .saturating_sub(indent * self.options.indent_width),
+            None => text.chars().count(),
+        }
+    }
+
+    fn write_comments_before(&mut self, start: usize, indent: usize) {
+        while self
+            .comments
+            .front()
+            .is_some_and(|comment| comment.span.start < start)
+        {
+            let comment = self.comments.pop_front().expect("front comment exists");
+            self.write_comment(&comment, indent);
+        }
+    }
+
+    fn write_comment(&mut self, comment: &Comment, indent: usize) {
+        self.write_blank_lines(comment.span.start);
+        self.write_line(&comment.text, indent, &[]);
+    }
+
+    /// Re-emits the blank lines before `start`, capped at `max_blank_lines`.
+    fn write_blank_lines(&mut self, start: usize) {
+        let blanks = self.skip_blank_lines(start);
+        if !self.at_block_start {
+            for _ in 0..blanks.min(self.options.max_blank_lines) {
+                self.out.push('\n');
+            }
         }
     }
+
+    fn skip_blank_lines(&mut self, end: usize) -> usize {
+        let mut skipped = 0;
+        while self.blank_lines.front().is_some_and(|offset| *offset < end) {
+            self.blank_lines.pop_front();
+            skipped += 1;
+        }
+        skipped
+    }
+
+    fn write_line(&mut self, line: &str, indent: usize, trailing: &[String]) {
+        self.at_block_start = false;
+        write_indent(&mut self.out, indent, self.options.indent_width);
+        self.out.push_str(line);
+        for comment in trailing {
+            self.out.push(' ');
+            self.out.push_str(comment);
+        }
+        self.out.push('\n');
+    }
 }
 
-fn write_fn_def(out: &mut String, def: &FnDef, indent: usize) {
-    write_indent(out, indent);
-    out.push_str("f ");
-    out.push_str(&def.name);
-    out.push('(');
-    out.push_str(
-        &def.params
+/// `open`, then each item on its own line one level in with a trailing
+/// comma, then `close` on a line of its own.
+fn expand(open: String, items: Vec<String>, close: char, layout: Layout<'_>) -> String {
+    let mut text = open;
+    for item in items {
+        text.push('\n');
+        write_indent(&mut text, layout.indent + 1, layout.indent_width);
+        text.push_str(&item);
+        text.push(',');
+    }
+    text.push('\n');
+    write_indent(&mut text, layout.indent, layout.indent_width);
+    text.push(close);
+    text
+}
+
+/// Like `expand` for an object's `name: value` items, putting the comments
+/// written inside it back beside its fields: comments on their own line go
+/// above the field they precede (or the `}`), trailing ones after the comma of
+/// the field they follow (or the `{`).
+fn expand_object(
+    fields: &[(String, Expr)],
+    items: Vec<String>,
+    span: Span,
+    layout: Layout<'_>,
+) -> String {
+    let mut open = "{".to_string();
+    let mut above = vec![Vec::new(); fields.len() + 1];
+    let mut trailing = vec![Vec::new(); fields.len()];
+    for (_, comment) in layout
+        .object_comments
+        .iter()
+        .filter(|(owner, _)| *owner == span.start)
+    {
+        let index = fields
             .iter()
-            .map(format_param)
-            .collect::<Vec<_>>()
-            .join(", "),
-    );
-    out.push(')');
-    if let Some(schema) = &def.return_schema {
-        out.push_str(" -> ");
-        out.push_str(&format_schema(schema));
-    }
-    out.push_str(":\n");
-    write_block(out, &def.body, indent + 1);
+            .position(|(_, value)| comment.span.start < value.span().end)
+            .unwrap_or(fields.len());
+        if comment.own_line {
+            above[index].push(comment.text.as_str());
+        } else if index < fields.len() && comment.span.start > fields[index].1.span().start {
+            trailing[index].push(comment.text.as_str());
+        } else if index == 0 {
+            open.push(' ');
+            open.push_str(&comment.text);
+        } else {
+            trailing[index - 1].push(comment.text.as_str());
+        }
+    }
+
+    let mut text = open;
+    let push_line = |text: &mut String, line: &str| {
+        text.push('\n');
+        write_indent(text, layout.indent + 1, layout.indent_width);
+        text.push_str(line);
+    };
+    for (index, item) in items.iter().enumerate() {
+        for comment in &above[index] {
+            push_line(&mut text, comment);
+        }
+        push_line(&mut text, item);
+        text.push(',');
+        for comment in &trailing[index] {
+            text.push(' ');
+            text.push_str(comment);
+        }
+    }
+    for comment in &above[fields.len()] {
+        push_line(&mut text, comment);
+    }
+    text.push('\n');
+    write_indent(&mut text, layout.indent, layout.indent_width);
+    text.push('}');
+    text
 }
 
-fn write_block(out: &mut String, block: &[Stmt], indent: usize) {
-    for stmt in block {
-        write_stmt(out, stmt, indent);
+/// Splits off the comments inside objects that stay expanded, each keyed by
+/// the start of the innermost such object, from those written around
+/// statements.
+fn claim_object_comments(
+    program: &Program,
+    source: &str,
+    comments: Vec<Comment>,
+) -> (Vec<Comment>, Vec<(usize, Comment)>) {
+    let layout = Layout {
+        indent: 0,
+        indent_width: 0,
+        source,
+        object_comments: &[],
+        in_prompt: false,
+    };
+    let mut objects = Vec::new();
+    for stmt in &program.stmts {
+        collect_stmt_objects(stmt, layout, &mut objects);
     }
+
+    let mut rest = Vec::new();
+    let mut claimed = Vec::new();
+    for comment in comments {
+        let owner = objects
+            .iter()
+            .filter(|span| span.start < comment.span.start && comment.span.start < span.end)
+            .map(|span| span.start)
+            .max();
+        match owner {
+            Some(owner) => claimed.push((owner, comment)),
+            None => rest.push(comment),
+        }
+    }
+    (rest, claimed)
 }
 
-fn format_param(param: &FnParam) -> String {
-    if let Some(schema) = &param.schema {
-        format!("{}: {}", param.name, format_schema(schema))
-    } else {
-        param.name.clone()
+/// Spans of the objects in `stmt` written over several lines.
+fn collect_stmt_objects(stmt: &Stmt, layout: Layout<'_>, out: &mut Vec<Span>) {
+    match stmt {
+        Stmt::FnDef(def) => {
+            for stmt in &def.body {
+                collect_stmt_objects(stmt, layout, out);
+            }
+        }
+        Stmt::Assign { value: expr, .. }
+        | Stmt::Assert { expr, .. }
+        | Stmt::Expr { expr, .. }
+        | Stmt::Return {
+            value: Some(expr), ..
+        } => collect_expr_objects(expr, layout, out),
+        Stmt::Return { value: None, .. } => {}
+        Stmt::If {
+            cond,
+            then_block,
+            else_block,
+            ..
+        } => {
+            collect_expr_objects(cond, layout, out);
+            for stmt in then_block.iter().chain(else_block.iter().flatten()) {
+                collect_stmt_objects(stmt, layout, out);
+            }
+        }
+        Stmt::For { iter, body, .. } => {
+            collect_expr_objects(iter, layout, out);
+            for stmt in body {
+                collect_stmt_objects(stmt, layout, out);
+            }
+        }
+    }
+}
+
+fn collect_expr_objects(expr: &Expr, layout: Layout<'_>, out: &mut Vec<Span>) {
+    match expr {
+        Expr::Object(fields, span) => {
+            if !fields.is_empty() && layout.spans_lines(*span) {
+                out.push(*span);
+            }
+            for (_, value) in fields {
+                collect_expr_objects(value, layout, out);
+            }
+        }
+        Expr::List(items, _) | Expr::Tuple(items, _) => {
+            for item in items {
+                collect_expr_objects(item, layout, out);
+            }
+        }
+        Expr::Call { callee, args, .. } => {
+            collect_expr_objects(callee, layout, out);
+            for arg in args {
+                collect_expr_objects(arg, layout, out);
+            }
+        }
+        Expr::Binary { left, right, .. } => {
+            collect_expr_objects(left, layout, out);
+            collect_expr_objects(right, layout, out);
+        }
+        Expr::Index { target, index, .. } => {
+            collect_expr_objects(target, layout, out);
+            collect_expr_objects(index, layout, out);
+        }
+        Expr::Unary { expr: target, .. }
+        | Expr::Member { target, .. }
+        | Expr::TupleIndex { target, .. } => collect_expr_objects(target, layout, out),
+        // Interpolation spans are relative to the interpolation, not `source`.
+        Expr::Prompt(prompt) => {
+            if let Some(options) = &prompt.options {
+                collect_expr_objects(options, layout, out);
+            }
+        }
+        Expr::Int(..)
+        | Expr::Float(..)
+        | Expr::Bool(..)
+        | Expr::Str(..)
+        | Expr::Nil(_)
+        | Expr::Var(..) => {}
     }
 }
 
@@ -142,46 +676,65 @@ fn format_pattern(pattern: &Pattern) -> String {
     }
 }
 
-fn format_schema(schema: &SchemaExpr) -> String {
+fn format_schema(schema: &SchemaExpr, layout: Layout<'_>) -> String {
     match schema {
         SchemaExpr::Any => "any".to_string(),
         SchemaExpr::Int => "int".to_string(),
         SchemaExpr::Float => "float".to_string(),
         SchemaExpr::Bool => "bool".to_string(),
         SchemaExpr::String => "string".to_string(),
-        SchemaExpr::List(inner) => format!("[{}]", format_schema(inner)),
+        SchemaExpr::List(inner) => format!("[{}]", format_schema(inner, layout)),
         SchemaExpr::Tuple(items) => format!(
             "({})",
             items
                 .iter()
-                .map(format_schema)
+                .map(|item| format_schema(item, layout))
                 .collect::<Vec<_>>()
                 .join(", ")
         ),
-        SchemaExpr::Object(fields) => format!(
+        SchemaExpr::Object(fields, span) if layout.spans_lines(*span) => {
+            let items = fields
+                .iter()
+                .map(|field| {
+                    let schema = format_schema(&field.schema, layout.nested());
+                    format!("{}: {schema}", field.name)
+                })
+                .collect();
+            expand("{".to_string(), items, '}', layout)
+        }
+        SchemaExpr::Object(fields, _) => format!(
             "{{{}}}",
             fields
                 .iter()
-                .map(|field| format!("{}: {}", field.name, format_schema(&field.schema)))
+                .map(|field| format!("{}: {}", field.name, format_schema(&field.schema, layout)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        SchemaExpr::DataFrame(columns) if columns.is_empty() => "dataframe".to_string(),
+        SchemaExpr::DataFrame(columns) => format!(
+            "dataframe{{{}}}",
+            columns
+                .iter()
+                .map(|column| format!("{}: {}", column.name, format_schema(&column.schema, layout)))
                 .collect::<Vec<_>>()
                 .join(", ")
         ),
         SchemaExpr::Union(variants) => variants
             .iter()
-            .map(format_schema)
+            .map(|item| format_schema(item, layout))
             .collect::<Vec<_>>()
             .join(" | "),
         SchemaExpr::Optional(inner) => {
             let inner_text = match inner.as_ref() {
-                SchemaExpr::Union(_) => format!("({})", format_schema(inner)),
-                _ => format_schema(inner),
+                SchemaExpr::Union(_) => format!("({})", format_schema(inner, layout)),
+                _ => format_schema(inner, layout),
             };
             format!("{inner_text}?")
         }
     }
 }
 
-fn format_expr(expr: &Expr, parent_prec: u8) -> String {
+fn format_expr(expr: &Expr, parent_prec: u8, layout: Layout<'_>) -> String {
     match expr {
         Expr::Int(v, _) => v.to_string(),
         Expr::Float(v, _) => format_float(*v),
@@ -192,14 +745,14 @@ fn format_expr(expr: &Expr, parent_prec: u8) -> String {
                 "false".to_string()
             }
         }
-        Expr::Str(v, _) => serde_json::to_string(v).unwrap_or_else(|_| format!("\"{v}\"")),
+        Expr::Str(v, span) => format_string(v, *span, layout.source),
         Expr::Nil(_) => "nil".to_string(),
         Expr::Var(name, _) => name.clone(),
         Expr::List(items, _) => format!(
             "[{}]",
             items
                 .iter()
-                .map(|item| format_expr(item, 0))
+                .map(|item| format_expr(item, 0, layout))
                 .collect::<Vec<_>>()
                 .join(", ")
         ),
@@ -207,20 +760,27 @@ fn format_expr(expr: &Expr, parent_prec: u8) -> String {
             "({})",
             items
                 .iter()
-                .map(|item| format_expr(item, 0))
+                .map(|item| format_expr(item, 0, layout))
                 .collect::<Vec<_>>()
                 .join(", ")
         ),
+        Expr::Object(fields, span) if !fields.is_empty() && layout.spans_lines(*span) => {
+            let items = fields
+                .iter()
+                .map(|(name, value)| format!("{name}: {}", format_expr(value, 0, layout.nested())))
+                .collect();
+            expand_object(fields, items, *span, layout)
+        }
         Expr::Object(fields, _) => format!(
             "{{{}}}",
             fields
                 .iter()
-                .map(|(name, value)| format!("{name}: {}", format_expr(value, 0)))
+                .map(|(name, value)| format!("{name}: {}", format_expr(value, 0, layout)))
                 .collect::<Vec<_>>()
                 .join(", ")
         ),
         Expr::Unary { op, expr, .. } => {
-            let inner = format_expr(expr, PREC_UNARY);
+            let inner = format_expr(expr, PREC_UNARY, layout);
             let body = match op {
                 UnaryOp::Neg => format!("-{inner}"),
                 UnaryOp::Not => format!("not {inner}"),
@@ -231,54 +791,131 @@ fn format_expr(expr: &Expr, parent_prec: u8) -> String {
             left, op, right, ..
         } => {
             let (prec, op_text) = binary_style(op);
-            let left_text = format_expr(left, prec);
-            let right_text = format_expr(right, prec + 1);
+            let left_text = format_expr(left, prec, layout);
+            let right_text = format_expr(right, prec + 1, layout);
             let body = format!("{left_text} {op_text} {right_text}");
             maybe_parenthesize(body, prec, parent_prec)
         }
         Expr::Call { callee, args, .. } => {
-            let callee_text = format_expr(callee, PREC_POSTFIX);
+            let callee_text = format_expr(callee, PREC_POSTFIX, layout);
             let args_text = args
                 .iter()
-                .map(|arg| format_expr(arg, 0))
+                .map(|arg| format_expr(arg, 0, layout))
                 .collect::<Vec<_>>()
                 .join(", ");
             let body = format!("{callee_text}({args_text})");
             maybe_parenthesize(body, PREC_POSTFIX, parent_prec)
         }
         Expr::Index { target, index, .. } => {
-            let target_text = format_expr(target, PREC_POSTFIX);
-            let index_text = format_expr(index, 0);
+            let target_text = format_expr(target, PREC_POSTFIX, layout);
+            let index_text = format_expr(index, 0, layout);
             let body = format!("{target_text}[{index_text}]");
             maybe_parenthesize(body, PREC_POSTFIX, parent_prec)
         }
         Expr::Member { target, name, .. } => {
-            let target_text = format_expr(target, PREC_POSTFIX);
+            let target_text = format_expr(target, PREC_POSTFIX, layout);
             let body = format!("{target_text}.{name}");
             maybe_parenthesize(body, PREC_POSTFIX, parent_prec)
         }
         Expr::TupleIndex { target, index, .. } => {
-            let target_text = format_expr(target, PREC_POSTFIX);
+            let target_text = format_expr(target, PREC_POSTFIX, layout);
             let body = format!("{target_text}.{index}");
             maybe_parenthesize(body, PREC_POSTFIX, parent_prec)
         }
-        Expr::Prompt(prompt) => format_prompt(prompt),
+        Expr::Prompt(prompt) => format_prompt(prompt, layout),
+    }
+}
+
+/// A string literal exactly as the author wrote it, when `span` still points
+/// at a literal with this value; otherwise the canonical spelling, which
+/// escapes only `\\`, `"`, newlines, tabs, and carriage returns and keeps
+/// every other character (including non-ASCII) as is.
+fn format_string(value: &str, span: Span, source: &str) -> String {
+    if let Some(text) = source.get(span.start..span.end)
+        && let Ok(tokens) = crate::lexer::lex(text)
+        && matches!(tokens.first(), Some(token) if token.kind == TokenKind::String(value.to_string()))
+        && tokens[1..]
+            .iter()
+            .all(|token| matches!(token.kind, TokenKind::Newline | TokenKind::Eof))
+    {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(ch),
+        }
     }
+    out.push('"');
+    out
 }
 
-fn format_prompt(prompt: &PromptExpr) -> String {
+fn format_prompt(prompt: &PromptExpr, layout: Layout<'_>) -> String {
     let mut body = String::new();
     for part in &prompt.parts {
         match part {
             PromptPart::Text(text) => body.push_str(text),
             PromptPart::Interpolation(expr) => {
+                let layout = Layout {
+                    in_prompt: true,
+                    ..layout
+                };
                 body.push('{');
-                body.push_str(&format_expr(expr, 0));
+                body.push_str(&format_expr(expr, 0, layout));
                 body.push('}');
             }
         }
     }
-    format!("${body}$")
+    if body.contains('\n') {
+        body = reindent_prompt_block(&body, layout);
+    }
+    match &prompt.options {
+        Some(options) => format!(
+            "${body}$ with {}",
+            format_expr(options, PREC_POSTFIX, layout)
+        ),
+        None => format!("${body}$"),
+    }
+}
+
+/// Lines of a multi-line prompt block keep their breaks and their indentation
+/// relative to each other, but start one level inside the statement; blank
+/// lines lose their spaces and a closing `$` on its own line lines up with
+/// the statement. Text on the opening `$` line is left alone.
+fn reindent_prompt_block(body: &str, layout: Layout<'_>) -> String {
+    let mut lines = body.split('\n');
+    let mut out = lines.next().unwrap_or_default().to_string();
+    let rest = lines.collect::<Vec<_>>();
+    let (text_lines, closes_alone) = match rest.split_last() {
+        Some((last, init)) if last.trim().is_empty() => (init, true),
+        _ => (rest.as_slice(), false),
+    };
+
+    let common = text_lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start_matches(' ').len())
+        .min()
+        .unwrap_or(0);
+    for line in text_lines {
+        out.push('\n');
+        if !line.trim().is_empty() {
+            write_indent(&mut out, layout.indent + 1, layout.indent_width);
+            out.push_str(&line[common..]);
+        }
+    }
+    if closes_alone {
+        out.push('\n');
+        write_indent(&mut out, layout.indent, layout.indent_width);
+    }
+    out
 }
 
 fn maybe_parenthesize(text: String, my_prec: u8, parent_prec: u8) -> String {
@@ -315,10 +952,8 @@ fn format_float(value: f64) -> String {
     text
 }
 
-fn write_indent(out: &mut String, level: usize) {
-    for _ in 0..level {
-        out.push_str(INDENT);
-    }
+fn write_indent(out: &mut String, level: usize, width: usize) {
+    out.extend(std::iter::repeat_n(' ', level * width));
 }
 
 #[cfg(test)]
@@ -331,4 +966,254 @@ mod tests {
         let formatted = format_source(source).expect("expected formatter to succeed");
         assert!(formatted.contains("20.0"));
     }
+
+    #[test]
+    fn keeps_prompt_options() {
+        let source = "x = $\n    hi\n$ with {provider: \"ollama\", model: \"llama3\"}\n";
+        let formatted = format_source(source).expect("expected formatter to succeed");
+        assert_eq!(formatted, source);
+    }
+
+    #[test]
+    fn keeps_dataframe_schemas() {
+        let source = "df: dataframe{city: string, temp: float?} = read(\"a.csv\")\n";
+        let formatted = format_source(source).expect("expected formatter to succeed");
+        assert_eq!(formatted, source);
+    }
+
+    #[test]
+    fn keeps_comments_in_place() {
+        let source = "\
+// header
+x = 1 // trailing
+f add(a, b): // about add
+    // inside add
+    ret a + b
+    // end of add
+// before if
+if x == 1:
+    y = 2
+    // end of then
+// before else
+else: // else note
+    // first of else
+    y = 3
+// last words
+";
+        let formatted = format_source(source).expect("expected formatter to succeed");
+        assert_eq!(formatted, source);
+    }
+
+    #[test]
+    fn moves_comments_inside_collapsed_expressions_next_to_the_statement() {
+        let source = "o = [\n    // lead\n    1, // one\n    2\n]\n";
+        let formatted = format_source(source).expect("expected formatter to succeed");
+        assert_eq!(formatted, "// lead\no = [1, 2] // one\n");
+    }
+
+    #[test]
+    fn wraps_calls_lists_and_objects_past_the_max_width() {
+        let source = "\
+rows = [{name: \"alice\", city: \"berlin\"}, {name: \"bob\", city: \"paris\"}]
+print(rows, [1, 2])
+";
+        let options = FormatOptions {
+            max_width: 40,
+            ..FormatOptions::default()
+        };
+        let formatted =
+            format_source_with_options(source, &options).expect("expected formatter to succeed");
+        assert_eq!(
+            formatted,
+            "\
+rows = [
+    {name: \"alice\", city: \"berlin\"},
+    {name: \"bob\", city: \"paris\"},
+]
+print(rows, [1, 2])
+"
+        );
+        assert_eq!(
+            format_source_with_options(&formatted, &options).expect("wrapped output parses"),
+            formatted
+        );
+        assert_eq!(
+            format_source(&formatted).expect("wrapped output parses"),
+            source
+        );
+    }
+
+    #[test]
+    fn keeps_up_to_max_blank_lines_between_statements() {
+        let source =
+            "\n\nx = 1\n\n\n\nf add(a, b):\n\n    ret a + b\n\n// done\ny = [\n\n    1\n]\n\n";
+        assert_eq!(
+            format_source(source).expect("expected formatter to succeed"),
+            "x = 1\n\nf add(a, b):\n    ret a + b\n\n// done\ny = [1]\n"
+        );
+
+        let options = FormatOptions {
+            max_blank_lines: 0,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_source_with_options(source, &options).expect("expected formatter to succeed"),
+            "x = 1\nf add(a, b):\n    ret a + b\n// done\ny = [1]\n"
+        );
+    }
+
+    #[test]
+    fn indents_blocks_and_wrapped_items_by_indent_width() {
+        let source = "f pick(rows):\n    for row in rows:\n        ret [row.name, row.city]\n";
+        let options = FormatOptions {
+            indent_width: 2,
+            max_width: 24,
+            ..FormatOptions::default()
+        };
+        let formatted =
+            format_source_with_options(source, &options).expect("expected formatter to succeed");
+        assert_eq!(
+            formatted,
+            "f pick(rows):\n  for row in rows:\n    ret [\n      row.name,\n      row.city,\n    ]\n"
+        );
+        assert_eq!(
+            format_source(&formatted).expect("two-space output parses"),
+            source
+        );
+    }
+
+    #[test]
+    fn reindents_prompt_blocks_keeping_their_line_structure() {
+        let source = "\
+f ask(topic):
+    answer = $
+          Be brief.
+
+            - about {topic}
+        $
+    ret answer
+";
+        let formatted = format_source(source).expect("expected formatter to succeed");
+        assert_eq!(
+            formatted,
+            "\
+f ask(topic):
+    answer = $
+        Be brief.
+
+          - about {topic}
+    $
+    ret answer
+"
+        );
+        assert_eq!(
+            format_source(&formatted).expect("expected formatter to succeed"),
+            formatted
+        );
+    }
+
+    #[test]
+    fn keeps_string_literals_as_written() {
+        let source =
+            "x = [\"caf\u{e9}\", \"a\tb\", \"a\\tb\", \"say \\\"hi\\\"\"]\ny = $echo {\"\\\\\"}$\n";
+        assert_eq!(
+            format_source(source).expect("expected formatter to succeed"),
+            source
+        );
+
+        let tokens = crate::lexer::lex(source).expect("expected source to lex");
+        let program = crate::parser::parse(tokens).expect("expected source to parse");
+        assert_eq!(
+            format_program(&program),
+            "x = [\"caf\u{e9}\", \"a\\tb\", \"a\\tb\", \"say \\\"hi\\\"\"]\ny = $echo {\"\\\\\"}$\n"
+        );
+    }
+
+    #[test]
+    fn keeps_objects_and_object_schemas_written_over_several_lines() {
+        let source = "\
+f label(row: {name: string, langs: [string]}) -> {
+  text: string
+}:
+    ret {text: row.name}
+
+out = {text: \"x\",
+  langs: []
+}
+print(label({
+  name: \"ada\", langs: []
+}), {a: 1})
+";
+        let formatted = format_source(source).expect("expected formatter to succeed");
+        assert_eq!(
+            formatted,
+            "\
+f label(row: {name: string, langs: [string]}) -> {
+    text: string,
+}:
+    ret {text: row.name}
+
+out = {
+    text: \"x\",
+    langs: [],
+}
+print(label({
+    name: \"ada\",
+    langs: [],
+}), {a: 1})
+"
+        );
+        assert_eq!(
+            format_source(&formatted).expect("expected formatter to succeed"),
+            formatted
+        );
+
+        let options = FormatOptions {
+            max_width: 28,
+            ..FormatOptions::default()
+        };
+        let formatted = format_source_with_options("p: {name: string, age: int} = nil\n", &options)
+            .expect("expected formatter to succeed");
+        assert_eq!(
+            formatted,
+            "p: {\n    name: string,\n    age: int,\n} = nil\n"
+        );
+    }
+
+    #[test]
+    fn keeps_comments_inside_expanded_objects_beside_their_fields() {
+        let source = "\
+cfg = {
+    a: 1, // first
+    // about b
+    b: 2,
+}
+";
+        assert_eq!(
+            format_source(source).expect("expected formatter to succeed"),
+            source
+        );
+
+        let source = "\
+print({ // settings
+  x: {
+    // deep
+    y: 1 // why
+  }, // after x
+  // closing
+}) // end
+";
+        assert_eq!(
+            format_source(source).expect("expected formatter to succeed"),
+            "\
+print({ // settings
+    x: {
+        // deep
+        y: 1, // why
+    }, // after x
+    // closing
+}) // end
+"
+        );
+    }
 }
@@ -0,0 +1,84 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::error::{SaftError, SaftResult};
+
+/// Status line marker appended by `curl --write-out`, split off the body again.
+const STATUS_MARKER: &str = "\n__orangensaft_http_status__:";
+
+pub struct HttpResponse {
+    pub status: i64,
+    pub body: String,
+}
+
+/// Sends a request with `curl`, like the model providers do. Non-2xx statuses
+/// are returned to the caller; only transport failures are errors.
+///
+/// Only `http://` and `https://` URLs are accepted, including for redirects,
+/// since the URL may come from a model through a tool function.
+pub fn send(
+    method: &str,
+    url: &str,
+    headers: &[(String, String)],
+    body: Option<&str>,
+) -> SaftResult<HttpResponse> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(SaftError::new(format!(
+            "{method} {url}: only http:// and https:// URLs are supported"
+        )));
+    }
+
+    let mut cmd = Command::new("curl");
+    cmd.arg("-sS")
+        .arg("-X")
+        .arg(method)
+        .arg("--proto")
+        .arg("=http,https")
+        .arg("--proto-redir")
+        .arg("=http,https")
+        .arg("--url")
+        .arg(url)
+        .arg("--write-out")
+        .arg(format!("{STATUS_MARKER}%{{http_code}}"));
+    for (name, value) in headers {
+        cmd.arg("-H").arg(format!("{name}: {value}"));
+    }
+    if body.is_some() {
+        cmd.arg("--data-binary").arg("@-");
+    }
+
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| SaftError::new(format!("failed to execute curl: {err}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(body.unwrap_or("").as_bytes())
+            .map_err(|err| SaftError::new(format!("failed to send {method} body: {err}")))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|err| SaftError::new(format!("failed to execute curl: {err}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SaftError::new(format!(
+            "{method} {url} failed: {}",
+            stderr.trim()
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (body, status) = stdout
+        .rsplit_once(STATUS_MARKER)
+        .ok_or_else(|| SaftError::new(format!("{method} {url} returned no status")))?;
+    let status = status.trim().parse::<i64>().map_err(|_| {
+        SaftError::new(format!("{method} {url} returned invalid status '{status}'"))
+    })?;
+    Ok(HttpResponse {
+        status,
+        body: body.to_string(),
+    })
+}
@@ -1,13 +1,20 @@
 use crate::error::{SaftError, SaftResult, Span};
-use crate::token::{Token, TokenKind};
+use crate::token::{Comment, Token, TokenKind, Trivia};
 
 pub fn lex(source: &str) -> SaftResult<Vec<Token>> {
+    lex_with_trivia(source).map(|(tokens, _)| tokens)
+}
+
+/// Like `lex`, also returning the comments and blank lines (in source order)
+/// that the token stream leaves out; the formatter re-emits them.
+pub fn lex_with_trivia(source: &str) -> SaftResult<(Vec<Token>, Trivia)> {
     Lexer::new(source).lex()
 }
 
 struct Lexer<'a> {
     source: &'a str,
     tokens: Vec<Token>,
+    trivia: Trivia,
     indent_stack: Vec<usize>,
     in_prompt_block: bool,
     prompt_start_span: Option<Span>,
@@ -19,6 +26,7 @@ impl<'a> Lexer<'a> {
         Self {
             source,
             tokens: Vec::new(),
+            trivia: Trivia::default(),
             indent_stack: vec![0],
             in_prompt_block: false,
             prompt_start_span: None,
@@ -26,7 +34,7 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn lex(mut self) -> SaftResult<Vec<Token>> {
+    fn lex(mut self) -> SaftResult<(Vec<Token>, Trivia)> {
         let mut offset = 0usize;
         let mut line_no = 1usize;
 
@@ -51,7 +59,7 @@ impl<'a> Lexer<'a> {
 
         let eof_span = Span::new(offset, offset, eof_line, 1);
         self.tokens.push(Token::new(TokenKind::Eof, eof_span));
-        Ok(self.tokens)
+        Ok((self.tokens, self.trivia))
     }
 
     fn lex_line(&mut self, raw_line: &str, line_no: usize, line_start: usize) -> SaftResult<()> {
@@ -83,12 +91,28 @@ impl<'a> Lexer<'a> {
         }
 
         let rest = &line[idx..];
-        if rest.trim().is_empty() || rest.trim_start().starts_with("//") {
+        if rest.starts_with("//") {
+            self.push_comment(line, line_no, line_start, idx, true);
+            return Ok(());
+        }
+        if rest.trim().is_empty() {
+            self.trivia.blank_lines.push(line_start);
             return Ok(());
         }
 
         self.handle_indentation(indent, line_no, line_start)?;
+        self.lex_tokens(line, line_no, line_start, idx, has_newline)
+    }
 
+    fn lex_tokens(
+        &mut self,
+        line: &str,
+        line_no: usize,
+        line_start: usize,
+        mut idx: usize,
+        has_newline: bool,
+    ) -> SaftResult<()> {
+        let bytes = line.as_bytes();
         while idx < bytes.len() {
             if bytes[idx] == b' ' {
                 idx += 1;
@@ -96,6 +120,7 @@ impl<'a> Lexer<'a> {
             }
 
             if line[idx..].starts_with("//") {
+                self.push_comment(line, line_no, line_start, idx, false);
                 break;
             }
 
@@ -262,9 +287,14 @@ impl<'a> Lexer<'a> {
                                 out.push(escaped);
                                 idx += 1;
                             }
-                            byte => {
-                                out.push(char::from(byte));
-                                idx += 1;
+                            _ => {
+                                // Copy whole UTF-8 characters, not single bytes.
+                                let ch = line[idx..]
+                                    .chars()
+                                    .next()
+                                    .expect("string scanning stays on char boundaries");
+                                out.push(ch);
+                                idx += ch.len_utf8();
                             }
                         }
                     }
@@ -302,6 +332,7 @@ impl<'a> Lexer<'a> {
                         "true" => TokenKind::True,
                         "false" => TokenKind::False,
                         "nil" => TokenKind::Nil,
+                        "with" => TokenKind::With,
                         _ => TokenKind::Ident(text.to_string()),
                     }
                 }
@@ -382,6 +413,28 @@ impl<'a> Lexer<'a> {
         Ok(())
     }
 
+    fn push_comment(
+        &mut self,
+        line: &str,
+        line_no: usize,
+        line_start: usize,
+        idx: usize,
+        own_line: bool,
+    ) {
+        let text = line[idx..].trim_end();
+        let span = Span::new(
+            line_start + idx,
+            line_start + idx + text.len(),
+            line_no,
+            idx + 1,
+        );
+        self.trivia.comments.push(Comment {
+            text: text.to_string(),
+            span,
+            own_line,
+        });
+    }
+
     fn start_prompt(
         &mut self,
         line: &str,
@@ -413,23 +466,7 @@ impl<'a> Lexer<'a> {
                 Span::merge(start_span, close_span),
             ));
 
-            let rest = &line[close_idx + 1..];
-            if !rest.trim().is_empty() && !rest.trim_start().starts_with("//") {
-                return Err(SaftError::with_span(
-                    "unexpected text after closing '$'",
-                    close_span,
-                ));
-            }
-
-            let nl_col = line.len() + 1;
-            let nl_span = Span::new(
-                line_start + line.len(),
-                line_start + line.len(),
-                line_no,
-                nl_col,
-            );
-            self.tokens.push(Token::new(TokenKind::Newline, nl_span));
-            return Ok(());
+            return self.lex_tokens(line, line_no, line_start, close_idx + 1, has_newline);
         }
 
         self.in_prompt_block = true;
@@ -471,23 +508,7 @@ impl<'a> Lexer<'a> {
             ));
             self.in_prompt_block = false;
 
-            let rest = &line[close_idx + 1..];
-            if !rest.trim().is_empty() && !rest.trim_start().starts_with("//") {
-                return Err(SaftError::with_span(
-                    "unexpected text after closing '$'",
-                    close_span,
-                ));
-            }
-
-            let nl_col = line.len() + 1;
-            let nl_span = Span::new(
-                line_start + line.len(),
-                line_start + line.len(),
-                line_no,
-                nl_col,
-            );
-            self.tokens.push(Token::new(TokenKind::Newline, nl_span));
-            return Ok(());
+            return self.lex_tokens(line, line_no, line_start, close_idx + 1, has_newline);
         }
 
         self.prompt_buffer.push_str(line);
@@ -1,24 +1,48 @@
+pub mod approval;
+pub mod artifact;
 pub mod ast;
+#[cfg(feature = "tokio")]
+pub mod async_provider;
+pub mod cache;
 pub mod cli;
+pub mod config;
+pub mod embedding;
 pub mod error;
+pub mod fixture;
 pub mod formatter;
+pub mod hash;
+pub mod http;
 pub mod lexer;
 pub mod parser;
+pub mod profile;
 pub mod provider;
+pub mod random;
+pub mod rate_limit;
 pub mod resolver;
+pub mod response_filter;
 pub mod runtime;
 pub mod schema;
 pub mod stdlib;
 pub mod token;
+pub mod transcript;
 pub mod value;
+pub mod xlsx;
 
 use ast::Program;
 use error::SaftResult;
 
 pub fn check_source(source: &str) -> SaftResult<Program> {
+    check_source_with_globals(source, &[])
+}
+
+/// Like `check_source`, with `globals` (e.g. `run --var` names) treated as
+/// already defined.
+pub fn check_source_with_globals(source: &str, globals: &[&str]) -> SaftResult<Program> {
     let tokens = lexer::lex(source)?;
     let program = parser::parse(tokens)?;
-    resolver::resolve(&program, stdlib::BUILTIN_NAMES)?;
+    let mut names = stdlib::BUILTIN_NAMES.to_vec();
+    names.extend_from_slice(globals);
+    resolver::resolve(&program, &names)?;
     Ok(program)
 }
 
@@ -46,7 +70,23 @@ pub fn run_source_with_provider_and_options(
     provider: Box<dyn provider::PromptProvider>,
     options: runtime::RuntimeOptions,
 ) -> SaftResult<()> {
-    let program = check_source(source)?;
     let mut runtime = runtime::Runtime::with_provider_and_options(provider, options);
+    run_source_with_runtime(source, &mut runtime)
+}
+
+#[cfg(feature = "tokio")]
+pub fn run_source_with_async_provider(
+    source: &str,
+    provider: Box<dyn async_provider::AsyncPromptProvider>,
+) -> SaftResult<()> {
+    let mut runtime = runtime::Runtime::with_async_provider_and_options(
+        provider,
+        runtime::RuntimeOptions::default(),
+    )?;
+    run_source_with_runtime(source, &mut runtime)
+}
+
+pub fn run_source_with_runtime(source: &str, runtime: &mut runtime::Runtime) -> SaftResult<()> {
+    let program = check_source(source)?;
     runtime.run_program(&program)
 }
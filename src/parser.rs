@@ -441,15 +441,7 @@ impl Parser {
 
         loop {
             if self.match_simple(TokenKind::LParen) {
-                let mut args = Vec::new();
-                if !self.check_simple(&TokenKind::RParen) {
-                    loop {
-                        args.push(self.parse_expr()?);
-                        if !self.match_simple(TokenKind::Comma) {
-                            break;
-                        }
-                    }
-                }
+                let args = self.parse_delimited(&TokenKind::RParen, Self::parse_expr)?;
                 let end = self.expect_simple(TokenKind::RParen, "expected ')' after arguments")?;
                 let span = Span::merge(expr.span(), end.span);
                 expr = Expr::Call {
@@ -574,7 +566,21 @@ impl Parser {
         };
 
         let parts = self.parse_prompt_parts(&raw, span)?;
-        Ok(Expr::Prompt(PromptExpr { parts, span }))
+        if self.match_simple(TokenKind::With) {
+            let options = self.parse_postfix()?;
+            let span = Span::merge(span, options.span());
+            return Ok(Expr::Prompt(PromptExpr {
+                parts,
+                options: Some(Box::new(options)),
+                span,
+            }));
+        }
+
+        Ok(Expr::Prompt(PromptExpr {
+            parts,
+            options: None,
+            span,
+        }))
     }
 
     fn parse_prompt_parts(&self, raw: &str, span: Span) -> SaftResult<Vec<PromptPart>> {
@@ -697,16 +703,7 @@ impl Parser {
         let start = self
             .expect_simple(TokenKind::LBracket, "expected '['")?
             .span;
-        let mut items = Vec::new();
-        if !self.check_simple(&TokenKind::RBracket) {
-            loop {
-                items.push(self.parse_expr()?);
-                if !self.match_simple(TokenKind::Comma) {
-                    break;
-                }
-            }
-        }
-
+        let items = self.parse_delimited(&TokenKind::RBracket, Self::parse_expr)?;
         let end = self.expect_simple(TokenKind::RBracket, "expected ']' after list")?;
         Ok(Expr::List(items, Span::merge(start, end.span)))
     }
@@ -733,26 +730,11 @@ impl Parser {
 
     fn parse_object_lit(&mut self) -> SaftResult<Expr> {
         let start = self.expect_simple(TokenKind::LBrace, "expected '{'")?.span;
-        self.consume_soft_breaks();
-        let mut fields = Vec::new();
-
-        if !self.check_simple(&TokenKind::RBrace) {
-            loop {
-                self.consume_soft_breaks();
-                let (name, _) = self.expect_ident("expected object field name")?;
-                self.expect_simple(TokenKind::Colon, "expected ':' after object field name")?;
-                let value = self.parse_expr()?;
-                fields.push((name, value));
-                self.consume_soft_breaks();
-
-                if !self.match_simple(TokenKind::Comma) {
-                    break;
-                }
-                self.consume_soft_breaks();
-            }
-        }
-
-        self.consume_soft_breaks();
+        let fields = self.parse_delimited(&TokenKind::RBrace, |parser| {
+            let (name, _) = parser.expect_ident("expected object field name")?;
+            parser.expect_simple(TokenKind::Colon, "expected ':' after object field name")?;
+            Ok((name, parser.parse_expr()?))
+        })?;
         let end = self.expect_simple(TokenKind::RBrace, "expected '}' after object")?;
         Ok(Expr::Object(fields, Span::merge(start, end.span)))
     }
@@ -789,6 +771,24 @@ impl Parser {
                 "float" => SchemaExpr::Float,
                 "bool" => SchemaExpr::Bool,
                 "string" => SchemaExpr::String,
+                "dataframe" => {
+                    if !self.match_simple(TokenKind::LBrace) {
+                        return Ok(SchemaExpr::DataFrame(Vec::new()));
+                    }
+                    let columns = self.parse_schema_fields("dataframe")?;
+                    for column in &columns {
+                        if !is_column_schema(&column.schema) {
+                            return Err(SaftError::with_span(
+                                format!(
+                                    "dataframe column '{}' must be any, int, float, bool, or string",
+                                    column.name
+                                ),
+                                span,
+                            ));
+                        }
+                    }
+                    SchemaExpr::DataFrame(columns)
+                }
                 _ => {
                     return Err(SaftError::with_span(
                         format!("unknown schema type '{name}'"),
@@ -822,32 +822,11 @@ impl Parser {
             return Ok(first);
         }
 
-        if self.match_simple(TokenKind::LBrace) {
-            self.consume_soft_breaks();
-            let mut fields = Vec::new();
-            if self.check_simple(&TokenKind::RBrace) {
-                return Err(SaftError::with_span(
-                    "object schema requires at least one field",
-                    self.current().span,
-                ));
-            }
-
-            loop {
-                self.consume_soft_breaks();
-                let (name, _) = self.expect_ident("expected field name in object schema")?;
-                self.expect_simple(TokenKind::Colon, "expected ':' after field name")?;
-                let schema = self.parse_schema_expr()?;
-                fields.push(SchemaField { name, schema });
-                self.consume_soft_breaks();
-                if !self.match_simple(TokenKind::Comma) {
-                    break;
-                }
-                self.consume_soft_breaks();
-            }
-
-            self.consume_soft_breaks();
-            self.expect_simple(TokenKind::RBrace, "expected '}' after object schema")?;
-            return Ok(SchemaExpr::Object(fields));
+        if self.check_simple(&TokenKind::LBrace) {
+            let start = self.advance().span;
+            let fields = self.parse_schema_fields("object")?;
+            let span = Span::merge(start, self.previous().span);
+            return Ok(SchemaExpr::Object(fields, span));
         }
 
         Err(SaftError::with_span(
@@ -856,6 +835,29 @@ impl Parser {
         ))
     }
 
+    /// Fields of an object or dataframe schema, after the opening `{`.
+    fn parse_schema_fields(&mut self, kind: &str) -> SaftResult<Vec<SchemaField>> {
+        let fields = self.parse_delimited(&TokenKind::RBrace, |parser| {
+            let (name, _) =
+                parser.expect_ident(&format!("expected field name in {kind} schema"))?;
+            parser.expect_simple(TokenKind::Colon, "expected ':' after field name")?;
+            let schema = parser.parse_schema_expr()?;
+            Ok(SchemaField { name, schema })
+        })?;
+        if fields.is_empty() {
+            return Err(SaftError::with_span(
+                format!("{kind} schema requires at least one field"),
+                self.current().span,
+            ));
+        }
+
+        self.expect_simple(
+            TokenKind::RBrace,
+            &format!("expected '}}' after {kind} schema"),
+        )?;
+        Ok(fields)
+    }
+
     fn is_assign_stmt_start(&self) -> bool {
         matches!(self.current().kind, TokenKind::Ident(_))
             && matches!(self.peek(1).kind, TokenKind::Eq | TokenKind::Colon)
@@ -865,6 +867,29 @@ impl Parser {
         while self.match_simple(TokenKind::Newline) {}
     }
 
+    /// Comma-separated items up to (not including) `close`. Line breaks and a
+    /// trailing comma are allowed, so wrapped `fmt` output parses back.
+    fn parse_delimited<T>(
+        &mut self,
+        close: &TokenKind,
+        mut parse_item: impl FnMut(&mut Self) -> SaftResult<T>,
+    ) -> SaftResult<Vec<T>> {
+        let mut items = Vec::new();
+        loop {
+            self.consume_soft_breaks();
+            if self.check_simple(close) {
+                break;
+            }
+            items.push(parse_item(self)?);
+            self.consume_soft_breaks();
+            if !self.match_simple(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume_soft_breaks();
+        Ok(items)
+    }
+
     fn consume_soft_breaks(&mut self) {
         while matches!(
             self.current().kind,
@@ -951,3 +976,21 @@ impl Parser {
             .unwrap_or_else(|| self.tokens.first().expect("token stream is non-empty"))
     }
 }
+
+/// Dataframe columns hold scalars, so only scalar schemas (and unions or
+/// optionals of them) can describe a column.
+fn is_column_schema(schema: &SchemaExpr) -> bool {
+    match schema {
+        SchemaExpr::Any
+        | SchemaExpr::Int
+        | SchemaExpr::Float
+        | SchemaExpr::Bool
+        | SchemaExpr::String => true,
+        SchemaExpr::Union(variants) => variants.iter().all(is_column_schema),
+        SchemaExpr::Optional(inner) => is_column_schema(inner),
+        SchemaExpr::List(_)
+        | SchemaExpr::Tuple(_)
+        | SchemaExpr::Object(..)
+        | SchemaExpr::DataFrame(_) => false,
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::time::Duration;
+
+/// Rows shown per section of `Profile::report`.
+const REPORT_ROWS: usize = 10;
+
+/// Call count and accumulated wall-clock time of one profiled location.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProfileEntry {
+    pub count: usize,
+    pub total: Duration,
+}
+
+impl ProfileEntry {
+    fn add(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.total += elapsed;
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.total / self.count.max(1) as u32
+    }
+}
+
+/// Timings gathered by a `Runtime` with `RuntimeOptions::profile` set.
+///
+/// Statement and function times are inclusive: a `for` line or a function
+/// that prompts also counts the time spent in its body and provider calls.
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    /// Keyed by source line.
+    pub statements: BTreeMap<usize, ProfileEntry>,
+    /// User functions, keyed by name.
+    pub functions: BTreeMap<String, ProfileEntry>,
+    /// Provider round trips, keyed by the line of the prompt.
+    pub prompt_rounds: BTreeMap<usize, ProfileEntry>,
+    /// Wall-clock time of the whole `run_program`.
+    pub total: Duration,
+}
+
+impl Profile {
+    pub fn record_statement(&mut self, line: usize, elapsed: Duration) {
+        self.statements.entry(line).or_default().add(elapsed);
+    }
+
+    pub fn record_function(&mut self, name: &str, elapsed: Duration) {
+        self.functions
+            .entry(name.to_string())
+            .or_default()
+            .add(elapsed);
+    }
+
+    pub fn record_prompt_round(&mut self, line: usize, elapsed: Duration) {
+        self.prompt_rounds.entry(line).or_default().add(elapsed);
+    }
+
+    /// Time spent waiting on providers, across every prompt round.
+    pub fn provider_time(&self) -> Duration {
+        self.prompt_rounds.values().map(|entry| entry.total).sum()
+    }
+
+    /// Human-readable summary: the provider share of the run, then the
+    /// slowest prompts, functions, and statements.
+    pub fn report(&self) -> String {
+        let provider = self.provider_time();
+        let share = if self.total.is_zero() {
+            0.0
+        } else {
+            provider.as_secs_f64() / self.total.as_secs_f64() * 100.0
+        };
+        let mut out = format!(
+            "profile: {:.2?} total, {:.2?} ({share:.0}%) waiting on providers\n",
+            self.total, provider
+        );
+        let prompts = self
+            .prompt_rounds
+            .iter()
+            .map(|(line, entry)| (format!("prompt at line {line}"), *entry));
+        let functions = self
+            .functions
+            .iter()
+            .map(|(name, entry)| (format!("{name}()"), *entry));
+        let statements = self
+            .statements
+            .iter()
+            .map(|(line, entry)| (format!("line {line}"), *entry));
+        write_section(&mut out, "prompt rounds", prompts);
+        write_section(&mut out, "user functions", functions);
+        write_section(&mut out, "statements", statements);
+        out.truncate(out.trim_end().len());
+        out
+    }
+}
+
+fn write_section(
+    out: &mut String,
+    title: &str,
+    rows: impl Iterator<Item = (String, ProfileEntry)>,
+) {
+    let mut rows = rows.collect::<Vec<_>>();
+    if rows.is_empty() {
+        return;
+    }
+    rows.sort_by(|(left_name, left), (right_name, right)| {
+        right
+            .total
+            .cmp(&left.total)
+            .then_with(|| left_name.cmp(right_name))
+    });
+    let _ = writeln!(out, "\n{title}:");
+    let _ = writeln!(
+        out,
+        "  {:>10} {:>7} {:>10}  location",
+        "total", "count", "mean"
+    );
+    for (name, entry) in rows.iter().take(REPORT_ROWS) {
+        let _ = writeln!(
+            out,
+            "  {:>10} {:>7} {:>10}  {name}",
+            format!("{:.2?}", entry.total),
+            entry.count,
+            format!("{:.2?}", entry.mean())
+        );
+    }
+    if rows.len() > REPORT_ROWS {
+        let _ = writeln!(out, "  ... {} more", rows.len() - REPORT_ROWS);
+    }
+}
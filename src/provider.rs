@@ -1,13 +1,18 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::env;
-use std::process::Command;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
 
 use serde_json::{Map as JsonMap, Value as JsonValue, json};
 
 use crate::error::{SaftError, SaftResult};
 
 const OPENROUTER_CHAT_COMPLETIONS_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+const OPENROUTER_MODELS_URL: &str = "https://openrouter.ai/api/v1/models";
 const DEFAULT_OPENROUTER_MODEL: &str = "openai/gpt-4o-mini";
+const DEFAULT_OLLAMA_HOST: &str = "http://localhost:11434";
+const DEFAULT_OLLAMA_MODEL: &str = "llama3";
 
 #[derive(Debug, Clone)]
 pub struct ToolDefinition {
@@ -30,11 +35,36 @@ pub struct ToolResult {
     pub output: JsonValue,
 }
 
+#[derive(Debug, Clone)]
+pub struct ResponseFormat {
+    pub name: String,
+    pub schema: JsonValue,
+}
+
+/// Image attached to a prompt via `{image(path)}`; `prompt` refers to it as `[image N]`.
+#[derive(Debug, Clone)]
+pub struct PromptImage {
+    pub media_type: String,
+    pub data_base64: String,
+}
+
+impl PromptImage {
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data_base64)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PromptRequest {
     pub prompt: String,
+    pub images: Vec<PromptImage>,
     pub tools: Vec<ToolDefinition>,
     pub tool_results: Vec<ToolResult>,
+    pub response_format: Option<ResponseFormat>,
+    /// Model override from the prompt's `with {model: ...}` option.
+    pub model: Option<String>,
+    /// Time left before the run's `--timeout`; providers should give up by then.
+    pub timeout: Option<Duration>,
 }
 
 #[derive(Debug, Clone)]
@@ -45,6 +75,59 @@ pub enum PromptResponse {
 
 pub trait PromptProvider {
     fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse>;
+
+    /// Model identifier reported in transcripts, when the provider has one.
+    fn model(&self) -> Option<&str> {
+        None
+    }
+
+    /// Sampling temperature sent with every request, when the provider sets one.
+    fn temperature(&self) -> Option<f32> {
+        None
+    }
+}
+
+/// Named prompt providers selectable per prompt with `with {provider: "name"}`;
+/// prompts without a `provider` option go to `default`.
+pub struct ProviderRegistry {
+    default: Box<dyn PromptProvider>,
+    named: HashMap<String, Box<dyn PromptProvider>>,
+}
+
+impl ProviderRegistry {
+    pub fn new(default: Box<dyn PromptProvider>) -> Self {
+        Self {
+            default,
+            named: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, name: impl Into<String>, provider: Box<dyn PromptProvider>) {
+        self.named.insert(name.into(), provider);
+    }
+
+    pub fn get_mut(&mut self, name: Option<&str>) -> SaftResult<&mut dyn PromptProvider> {
+        let Some(name) = name else {
+            return Ok(self.default.as_mut());
+        };
+        if !self.named.contains_key(name) {
+            let mut known = self.named.keys().cloned().collect::<Vec<_>>();
+            known.sort();
+            return Err(SaftError::new(format!(
+                "unknown provider '{name}' (registered: {})",
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )));
+        }
+        Ok(self
+            .named
+            .get_mut(name)
+            .expect("presence checked above")
+            .as_mut())
+    }
 }
 
 #[derive(Default)]
@@ -58,6 +141,31 @@ impl PromptProvider for NoopProvider {
     }
 }
 
+/// Stands in for a named provider that could not be set up (say, a missing API
+/// key), so prompts that pick it get that reason instead of "unknown provider".
+pub struct UnavailableProvider {
+    name: String,
+    reason: String,
+}
+
+impl UnavailableProvider {
+    pub fn new(name: &str, reason: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl PromptProvider for UnavailableProvider {
+    fn complete(&mut self, _request: PromptRequest) -> SaftResult<PromptResponse> {
+        Err(SaftError::new(format!(
+            "provider '{}' is unavailable: {}",
+            self.name, self.reason
+        )))
+    }
+}
+
 pub struct SequenceProvider {
     responses: VecDeque<PromptResponse>,
 }
@@ -78,6 +186,51 @@ impl PromptProvider for SequenceProvider {
     }
 }
 
+/// Outcome of one setup check from `orangensaft providers test`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeStatus {
+    Ok,
+    Fail,
+    /// Not attempted because an earlier check failed.
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbeCheck {
+    pub check: &'static str,
+    pub status: ProbeStatus,
+    pub detail: String,
+}
+
+impl ProbeCheck {
+    pub fn new(check: &'static str, status: ProbeStatus, detail: impl Into<String>) -> Self {
+        Self {
+            check,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Follow-up check for a model listing: skipped when the endpoint failed.
+fn probe_model(
+    model: &str,
+    listed: Option<Vec<String>>,
+    matches: impl Fn(&str) -> bool,
+) -> ProbeCheck {
+    match listed {
+        None => ProbeCheck::new("model", ProbeStatus::Skipped, model),
+        Some(names) if names.iter().any(|name| matches(name)) => {
+            ProbeCheck::new("model", ProbeStatus::Ok, model)
+        }
+        Some(names) => ProbeCheck::new(
+            "model",
+            ProbeStatus::Fail,
+            format!("{model} is not among the {} listed models", names.len()),
+        ),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct OpenRouterConfig {
     pub api_key: String,
@@ -120,13 +273,61 @@ impl OpenRouterProvider {
         }
         Ok(Self { config })
     }
+
+    /// Checks that the API key env var is set, the models endpoint answers,
+    /// and `model` (or the default) is listed there.
+    pub fn probe(api_key_env: &str, model: Option<&str>) -> Vec<ProbeCheck> {
+        let model = model.unwrap_or(DEFAULT_OPENROUTER_MODEL);
+        let api_key = env::var(api_key_env)
+            .ok()
+            .filter(|key| !key.trim().is_empty());
+        let key = match &api_key {
+            Some(_) => ProbeCheck::new("key", ProbeStatus::Ok, format!("{api_key_env} is set")),
+            None => ProbeCheck::new(
+                "key",
+                ProbeStatus::Fail,
+                format!("{api_key_env} is not set"),
+            ),
+        };
+        let headers = api_key
+            .iter()
+            .map(|key| format!("Authorization: Bearer {key}"))
+            .collect::<Vec<_>>();
+        let (endpoint, listed) = match curl_get_json(OPENROUTER_MODELS_URL, &headers, "OpenRouter")
+        {
+            Ok(body) => {
+                let ids = body
+                    .get("data")
+                    .and_then(JsonValue::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|entry| entry.get("id").and_then(JsonValue::as_str))
+                    .map(str::to_string)
+                    .collect();
+                (
+                    ProbeCheck::new("endpoint", ProbeStatus::Ok, OPENROUTER_MODELS_URL),
+                    Some(ids),
+                )
+            }
+            Err(err) => (
+                ProbeCheck::new("endpoint", ProbeStatus::Fail, err.message),
+                None,
+            ),
+        };
+        let model = probe_model(model, listed, |id| id == model);
+        vec![key, endpoint, model]
+    }
 }
 
 impl PromptProvider for OpenRouterProvider {
     fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
         let mut payload = json!({
-            "model": self.config.model,
-            "messages": build_openrouter_messages(&request.prompt, &request.tool_results),
+            "model": request.model.as_deref().unwrap_or(&self.config.model),
+            "messages": build_openrouter_messages(
+                &request.prompt,
+                &request.images,
+                &request.tool_results,
+            ),
             "temperature": self.config.temperature,
         });
 
@@ -142,58 +343,224 @@ impl PromptProvider for OpenRouterProvider {
                 .insert("tools".to_string(), JsonValue::Array(tools));
         }
 
-        let payload_text = serde_json::to_string(&payload).map_err(|err| {
-            SaftError::new(format!("failed to serialize OpenRouter payload: {err}"))
-        })?;
-
-        let mut cmd = Command::new("curl");
-        cmd.arg("-sS")
-            .arg("-X")
-            .arg("POST")
-            .arg(OPENROUTER_CHAT_COMPLETIONS_URL)
-            .arg("-H")
-            .arg(format!("Authorization: Bearer {}", self.config.api_key))
-            .arg("-H")
-            .arg("Content-Type: application/json")
-            .arg("--data")
-            .arg(payload_text);
+        if let Some(format) = &request.response_format {
+            payload
+                .as_object_mut()
+                .expect("payload should be object")
+                .insert(
+                    "response_format".to_string(),
+                    openrouter_response_format(format),
+                );
+        }
 
+        let mut headers = vec![format!("Authorization: Bearer {}", self.config.api_key)];
         if let Some(app_name) = &self.config.app_name {
-            cmd.arg("-H").arg(format!("X-Title: {app_name}"));
+            headers.push(format!("X-Title: {app_name}"));
         }
-
         if let Some(referer) = &self.config.referer {
-            cmd.arg("-H").arg(format!("HTTP-Referer: {referer}"));
+            headers.push(format!("HTTP-Referer: {referer}"));
         }
 
-        let output = cmd
-            .output()
-            .map_err(|err| SaftError::new(format!("failed to execute curl: {err}")))?;
+        let parsed = curl_post_json(
+            OPENROUTER_CHAT_COMPLETIONS_URL,
+            &headers,
+            &payload,
+            "OpenRouter",
+            request.timeout,
+        )?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let message = truncate_for_error(stderr.trim(), 500);
-            return Err(SaftError::new(format!(
-                "OpenRouter request failed via curl (status {}): {}",
-                output.status, message
-            )));
+        parse_openrouter_response(parsed)
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.config.model)
+    }
+
+    fn temperature(&self) -> Option<f32> {
+        Some(self.config.temperature)
+    }
+}
+
+/// Local Ollama chat completions (`{host}/api/chat`) via `curl`.
+pub struct OllamaProvider {
+    host: String,
+    model: String,
+}
+
+impl OllamaProvider {
+    pub fn new(host: Option<String>, model: Option<String>) -> Self {
+        Self {
+            host: host
+                .unwrap_or_else(|| DEFAULT_OLLAMA_HOST.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            model: model.unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.to_string()),
         }
+    }
 
-        let body_text = String::from_utf8(output.stdout)
-            .map_err(|err| SaftError::new(format!("OpenRouter response is not UTF-8: {err}")))?;
+    /// Checks that `{host}/api/tags` answers and lists the model; a bare
+    /// name such as `llama3` matches its `llama3:latest` tag.
+    pub fn probe(&self) -> Vec<ProbeCheck> {
+        let url = format!("{}/api/tags", self.host);
+        let (endpoint, listed) = match curl_get_json(&url, &[], "Ollama") {
+            Ok(body) => {
+                let names = body
+                    .get("models")
+                    .and_then(JsonValue::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|entry| entry.get("name").and_then(JsonValue::as_str))
+                    .map(str::to_string)
+                    .collect();
+                (
+                    ProbeCheck::new("endpoint", ProbeStatus::Ok, url),
+                    Some(names),
+                )
+            }
+            Err(err) => (
+                ProbeCheck::new("endpoint", ProbeStatus::Fail, err.message),
+                None,
+            ),
+        };
+        let model = &self.model;
+        let latest = format!("{model}:latest");
+        let model = probe_model(model, listed, |name| name == model || name == latest);
+        vec![endpoint, model]
+    }
+}
 
-        let parsed = serde_json::from_str::<JsonValue>(&body_text)
-            .map_err(|err| SaftError::new(format!("invalid OpenRouter JSON response: {err}")))?;
+impl PromptProvider for OllamaProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        let mut payload = json!({
+            "model": request.model.as_deref().unwrap_or(&self.model),
+            "messages": build_ollama_messages(&request),
+            "stream": false,
+        });
+        let fields = payload.as_object_mut().expect("payload should be object");
 
-        if let Some(error_obj) = parsed.get("error") {
-            return Err(SaftError::new(format!(
-                "OpenRouter error: {}",
-                truncate_for_error(&error_obj.to_string(), 500)
-            )));
+        if !request.tools.is_empty() {
+            let tools = request
+                .tools
+                .iter()
+                .map(openrouter_tool_definition)
+                .collect::<Vec<_>>();
+            fields.insert("tools".to_string(), JsonValue::Array(tools));
+        }
+        if let Some(format) = &request.response_format {
+            fields.insert("format".to_string(), format.schema.clone());
         }
 
-        parse_openrouter_response(parsed)
+        let url = format!("{}/api/chat", self.host);
+        let parsed = curl_post_json(&url, &[], &payload, "Ollama", request.timeout)?;
+        let message = parsed
+            .get("message")
+            .ok_or_else(|| SaftError::new("Ollama response is missing 'message'"))?;
+        parse_assistant_message(message, "Ollama")
     }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+}
+
+/// GETs `url` with `curl` (giving up after 10 seconds) and returns the parsed
+/// JSON body; used by the setup probes.
+fn curl_get_json(url: &str, headers: &[String], service: &str) -> SaftResult<JsonValue> {
+    let mut cmd = Command::new("curl");
+    cmd.arg("-sS")
+        .arg("--fail")
+        .arg("--max-time")
+        .arg("10")
+        .arg(url);
+    for header in headers {
+        cmd.arg("-H").arg(header);
+    }
+    let output = cmd
+        .output()
+        .map_err(|err| SaftError::new(format!("failed to execute curl: {err}")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SaftError::new(format!(
+            "{url}: {}",
+            truncate_for_error(stderr.trim(), 200)
+        )));
+    }
+    serde_json::from_slice(&output.stdout)
+        .map_err(|err| SaftError::new(format!("invalid {service} JSON response: {err}")))
+}
+
+/// POSTs a JSON payload with `curl` and returns the parsed JSON body, surfacing
+/// transport failures and top-level `error` objects as `SaftError`s. `curl`
+/// gives up after `timeout`, when one is given.
+pub(crate) fn curl_post_json(
+    url: &str,
+    headers: &[String],
+    payload: &JsonValue,
+    service: &str,
+    timeout: Option<Duration>,
+) -> SaftResult<JsonValue> {
+    let payload_text = serde_json::to_string(payload)
+        .map_err(|err| SaftError::new(format!("failed to serialize {service} payload: {err}")))?;
+
+    let mut cmd = Command::new("curl");
+    cmd.arg("-sS")
+        .arg("-X")
+        .arg("POST")
+        .arg(url)
+        .arg("-H")
+        .arg("Content-Type: application/json")
+        // Image payloads easily exceed the argv size limit, so the body goes
+        // through stdin.
+        .arg("--data-binary")
+        .arg("@-");
+    if let Some(timeout) = timeout {
+        // `--max-time 0` means no limit, so an exhausted budget still gets a millisecond.
+        cmd.arg("--max-time")
+            .arg(format!("{:.3}", timeout.as_secs_f64().max(0.001)));
+    }
+
+    for header in headers {
+        cmd.arg("-H").arg(header);
+    }
+
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| SaftError::new(format!("failed to execute curl: {err}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(payload_text.as_bytes())
+            .map_err(|err| SaftError::new(format!("failed to send {service} payload: {err}")))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|err| SaftError::new(format!("failed to execute curl: {err}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = truncate_for_error(stderr.trim(), 500);
+        return Err(SaftError::new(format!(
+            "{service} request failed via curl (status {}): {}",
+            output.status, message
+        )));
+    }
+
+    let body_text = String::from_utf8(output.stdout)
+        .map_err(|err| SaftError::new(format!("{service} response is not UTF-8: {err}")))?;
+
+    let parsed = serde_json::from_str::<JsonValue>(&body_text)
+        .map_err(|err| SaftError::new(format!("invalid {service} JSON response: {err}")))?;
+
+    if let Some(error_obj) = parsed.get("error") {
+        return Err(SaftError::new(format!(
+            "{service} error: {}",
+            truncate_for_error(&error_obj.to_string(), 500)
+        )));
+    }
+
+    Ok(parsed)
 }
 
 #[derive(Default)]
@@ -220,12 +587,30 @@ impl PromptProvider for HeuristicMockProvider {
     }
 }
 
-fn build_openrouter_messages(prompt: &str, tool_results: &[ToolResult]) -> Vec<JsonValue> {
+fn build_openrouter_messages(
+    prompt: &str,
+    images: &[PromptImage],
+    tool_results: &[ToolResult],
+) -> Vec<JsonValue> {
     let mut messages = Vec::new();
-    messages.push(json!({
-        "role": "user",
-        "content": prompt,
-    }));
+    if images.is_empty() {
+        messages.push(json!({
+            "role": "user",
+            "content": prompt,
+        }));
+    } else {
+        let mut content = vec![json!({ "type": "text", "text": prompt })];
+        for image in images {
+            content.push(json!({
+                "type": "image_url",
+                "image_url": { "url": image.data_url() },
+            }));
+        }
+        messages.push(json!({
+            "role": "user",
+            "content": content,
+        }));
+    }
 
     for result in tool_results {
         let args_json = serde_json::to_string(&result.args).unwrap_or_else(|_| "{}".to_string());
@@ -255,6 +640,43 @@ fn build_openrouter_messages(prompt: &str, tool_results: &[ToolResult]) -> Vec<J
     messages
 }
 
+fn build_ollama_messages(request: &PromptRequest) -> Vec<JsonValue> {
+    let mut user = json!({
+        "role": "user",
+        "content": request.prompt,
+    });
+    if !request.images.is_empty() {
+        let images = request
+            .images
+            .iter()
+            .map(|image| JsonValue::String(image.data_base64.clone()))
+            .collect::<Vec<_>>();
+        user.as_object_mut()
+            .expect("message should be object")
+            .insert("images".to_string(), JsonValue::Array(images));
+    }
+
+    let mut messages = vec![user];
+    for result in &request.tool_results {
+        messages.push(json!({
+            "role": "assistant",
+            "content": "",
+            "tool_calls": [{
+                "function": {
+                    "name": result.name,
+                    "arguments": result.args,
+                }
+            }]
+        }));
+        messages.push(json!({
+            "role": "tool",
+            "tool_name": result.name,
+            "content": serde_json::to_string(&result.output).unwrap_or_else(|_| "null".to_string()),
+        }));
+    }
+    messages
+}
+
 fn openrouter_tool_definition(tool: &ToolDefinition) -> JsonValue {
     let mut properties = JsonMap::new();
     for param in &tool.param_names {
@@ -276,6 +698,45 @@ fn openrouter_tool_definition(tool: &ToolDefinition) -> JsonValue {
     })
 }
 
+fn openrouter_response_format(format: &ResponseFormat) -> JsonValue {
+    // OpenAI-style structured output requires an object at the root, so primitive and
+    // array schemas are wrapped in a single `value` field. The runtime unwraps
+    // single-field wrappers before validating typed prompt output.
+    let is_object_root = format.schema.get("type").and_then(JsonValue::as_str) == Some("object");
+    let schema = if is_object_root {
+        format.schema.clone()
+    } else {
+        json!({
+            "type": "object",
+            "properties": { "value": format.schema },
+            "required": ["value"],
+            "additionalProperties": false,
+        })
+    };
+
+    json!({
+        "type": "json_schema",
+        "json_schema": {
+            "name": format.name,
+            "strict": is_strict_compatible(&schema),
+            "schema": schema,
+        }
+    })
+}
+
+fn is_strict_compatible(schema: &JsonValue) -> bool {
+    match schema {
+        JsonValue::Object(map) => {
+            if map.is_empty() || map.contains_key("prefixItems") {
+                return false;
+            }
+            map.values().all(is_strict_compatible)
+        }
+        JsonValue::Array(items) => items.iter().all(is_strict_compatible),
+        _ => true,
+    }
+}
+
 fn parse_openrouter_response(response: JsonValue) -> SaftResult<PromptResponse> {
     let choices = response
         .get("choices")
@@ -290,6 +751,10 @@ fn parse_openrouter_response(response: JsonValue) -> SaftResult<PromptResponse>
         .get("message")
         .ok_or_else(|| SaftError::new("OpenRouter response choice is missing 'message'"))?;
 
+    parse_assistant_message(message, "OpenRouter")
+}
+
+fn parse_assistant_message(message: &JsonValue, service: &str) -> SaftResult<PromptResponse> {
     if let Some(tool_calls) = message.get("tool_calls").and_then(JsonValue::as_array)
         && !tool_calls.is_empty()
     {
@@ -302,7 +767,7 @@ fn parse_openrouter_response(response: JsonValue) -> SaftResult<PromptResponse>
                 .unwrap_or("function");
             if call_type != "function" {
                 return Err(SaftError::new(format!(
-                    "unsupported tool call type from OpenRouter: {call_type}"
+                    "unsupported tool call type from {service}: {call_type}"
                 )));
             }
 
@@ -344,9 +809,9 @@ fn parse_openrouter_response(response: JsonValue) -> SaftResult<PromptResponse>
     let content = message.get("content").cloned().unwrap_or(JsonValue::Null);
     let text = message_content_to_text(content);
     if text.trim().is_empty() {
-        return Err(SaftError::new(
-            "OpenRouter returned empty assistant content and no tool calls",
-        ));
+        return Err(SaftError::new(format!(
+            "{service} returned empty assistant content and no tool calls"
+        )));
     }
 
     Ok(PromptResponse::FinalText(text))
@@ -575,7 +1040,7 @@ fn args_for_item(param_names: &[String], item: JsonValue) -> Option<JsonValue> {
                 return None;
             }
             let mut map = JsonMap::new();
-            for (name, value) in param_names.iter().cloned().zip(values.into_iter()) {
+            for (name, value) in param_names.iter().cloned().zip(values) {
                 map.insert(name, value);
             }
             Some(JsonValue::Object(map))
@@ -625,7 +1090,7 @@ fn extract_talk_pair(prompt: &str) -> Option<(String, String)> {
 
 fn parse_simple_addition(prompt: &str) -> Option<i64> {
     for line in prompt.lines() {
-        let normalized = line.replace('?', " ").replace(',', " ").replace('=', " ");
+        let normalized = line.replace(['?', ',', '='], " ");
         let words = normalized.split_whitespace().collect::<Vec<_>>();
 
         if words.len() < 3 {
@@ -0,0 +1,164 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::{SaftError, SaftResult};
+use crate::provider::{PromptProvider, PromptRequest, PromptResponse};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    /// Maximum requests started within any `window`; `None` disables the check.
+    pub max_requests: Option<usize>,
+    pub window: Duration,
+    /// Maximum requests in flight at once across every provider sharing the limiter.
+    pub max_concurrent: Option<usize>,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            max_requests: None,
+            window: Duration::from_secs(60),
+            max_concurrent: None,
+        }
+    }
+}
+
+impl RateLimit {
+    pub fn per_minute(max_requests: usize) -> Self {
+        Self {
+            max_requests: Some(max_requests),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.max_requests.is_none() && self.max_concurrent.is_none()
+    }
+}
+
+/// Shared limiter state; clones enforce one budget, so several runtimes (for
+/// example on worker threads) can share a provider quota.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    state: Arc<(Mutex<LimiterState>, Condvar)>,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    started: VecDeque<Instant>,
+    in_flight: usize,
+}
+
+struct Permit<'a> {
+    limiter: &'a RateLimiter,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Arc::new((Mutex::new(LimiterState::default()), Condvar::new())),
+        }
+    }
+
+    /// Blocks until both the window budget and the concurrency cap allow one more
+    /// request, giving up once `timeout` has passed.
+    fn acquire(&self, timeout: Option<Duration>) -> SaftResult<Permit<'_>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let (lock, condvar) = &*self.state;
+        let mut state = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        loop {
+            let now = Instant::now();
+            let time_left = deadline.map(|deadline| deadline.saturating_duration_since(now));
+            while let Some(oldest) = state.started.front() {
+                if now.duration_since(*oldest) >= self.limit.window {
+                    state.started.pop_front();
+                } else {
+                    break;
+                }
+            }
+
+            let concurrency_full = self
+                .limit
+                .max_concurrent
+                .is_some_and(|max| state.in_flight >= max.max(1));
+            let window_wait = match (self.limit.max_requests, state.started.front()) {
+                (Some(max), Some(oldest)) if state.started.len() >= max.max(1) => {
+                    Some(self.limit.window - now.duration_since(*oldest))
+                }
+                _ => None,
+            };
+
+            if !concurrency_full && window_wait.is_none() {
+                state.started.push_back(now);
+                state.in_flight += 1;
+                return Ok(Permit { limiter: self });
+            }
+            if time_left.is_some_and(|left| left.is_zero()) {
+                return Err(SaftError::new(
+                    "timed out waiting for the provider rate limit",
+                ));
+            }
+
+            // A full concurrency cap frees up on notification, not after a known wait.
+            let window_wait = window_wait.filter(|_| !concurrency_full);
+            let wait = match (window_wait, time_left) {
+                (Some(wait), Some(left)) => Some(wait.min(left)),
+                (wait, left) => wait.or(left),
+            };
+            state = match wait {
+                Some(wait) => {
+                    condvar
+                        .wait_timeout(state, wait)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .0
+                }
+                None => condvar
+                    .wait(state)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            };
+        }
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.limiter.state;
+        let mut state = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.in_flight -= 1;
+        condvar.notify_all();
+    }
+}
+
+/// Provider wrapper that waits for a `RateLimiter` permit before each request.
+pub struct RateLimitedProvider {
+    inner: Box<dyn PromptProvider>,
+    limiter: RateLimiter,
+}
+
+impl RateLimitedProvider {
+    pub fn new(inner: Box<dyn PromptProvider>, limit: RateLimit) -> Self {
+        Self::with_limiter(inner, RateLimiter::new(limit))
+    }
+
+    pub fn with_limiter(inner: Box<dyn PromptProvider>, limiter: RateLimiter) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+impl PromptProvider for RateLimitedProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        let _permit = self.limiter.acquire(request.timeout)?;
+        self.inner.complete(request)
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.inner.model()
+    }
+
+    fn temperature(&self) -> Option<f32> {
+        self.inner.temperature()
+    }
+}
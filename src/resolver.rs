@@ -152,6 +152,9 @@ impl Resolver {
                         self.resolve_expr(expr, scope)?;
                     }
                 }
+                if let Some(options) = &prompt.options {
+                    self.resolve_expr(options, scope)?;
+                }
                 Ok(())
             }
             Expr::Int(_, _)
@@ -0,0 +1,121 @@
+use regex::{Regex, RegexBuilder};
+
+use crate::error::{SaftError, SaftResult};
+
+/// Outcome of inspecting one final provider response.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterVerdict {
+    Allow,
+    /// Replace the response text (for example with a redacted copy).
+    Replace(String),
+    /// Keep the text but record a note in `Runtime::response_annotations`.
+    Annotate(String),
+    /// Fail the prompt with this reason.
+    Reject(String),
+}
+
+/// Guardrail hook run on every final prompt response before it becomes a value.
+/// Filters run in installation order; each sees the output of the previous one.
+pub trait ResponseFilter {
+    fn inspect(&mut self, text: &str) -> FilterVerdict;
+}
+
+enum PatternAction {
+    Reject,
+    Redact(String),
+    Annotate(String),
+}
+
+/// Regex/keyword guardrail: rejects, redacts, or annotates responses whose text
+/// matches configured patterns. Rules are checked in the order they were added;
+/// a redaction takes precedence over annotations on the same response.
+#[derive(Default)]
+pub struct PatternFilter {
+    rules: Vec<(Regex, PatternAction)>,
+}
+
+impl PatternFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reject(self, pattern: &str) -> SaftResult<Self> {
+        Ok(self.with_rule(compile(pattern)?, PatternAction::Reject))
+    }
+
+    pub fn redact(self, pattern: &str, replacement: &str) -> SaftResult<Self> {
+        Ok(self.with_rule(
+            compile(pattern)?,
+            PatternAction::Redact(replacement.to_string()),
+        ))
+    }
+
+    pub fn annotate(self, pattern: &str, note: &str) -> SaftResult<Self> {
+        Ok(self.with_rule(compile(pattern)?, PatternAction::Annotate(note.to_string())))
+    }
+
+    /// Case-insensitive whole-word keyword variant of `reject`.
+    pub fn reject_keyword(self, keyword: &str) -> SaftResult<Self> {
+        Ok(self.with_rule(keyword_regex(keyword)?, PatternAction::Reject))
+    }
+
+    /// Case-insensitive whole-word keyword variant of `redact`.
+    pub fn redact_keyword(self, keyword: &str, replacement: &str) -> SaftResult<Self> {
+        Ok(self.with_rule(
+            keyword_regex(keyword)?,
+            PatternAction::Redact(replacement.to_string()),
+        ))
+    }
+
+    fn with_rule(mut self, regex: Regex, action: PatternAction) -> Self {
+        self.rules.push((regex, action));
+        self
+    }
+}
+
+impl ResponseFilter for PatternFilter {
+    fn inspect(&mut self, text: &str) -> FilterVerdict {
+        let mut current = text.to_string();
+        let mut notes = Vec::new();
+
+        for (regex, action) in &self.rules {
+            if !regex.is_match(&current) {
+                continue;
+            }
+            match action {
+                PatternAction::Reject => {
+                    return FilterVerdict::Reject(format!(
+                        "response matched blocked pattern '{}'",
+                        regex.as_str()
+                    ));
+                }
+                PatternAction::Redact(replacement) => {
+                    current = regex
+                        .replace_all(&current, replacement.as_str())
+                        .into_owned();
+                }
+                PatternAction::Annotate(note) => notes.push(note.clone()),
+            }
+        }
+
+        if current != text {
+            FilterVerdict::Replace(current)
+        } else if !notes.is_empty() {
+            FilterVerdict::Annotate(notes.join("; "))
+        } else {
+            FilterVerdict::Allow
+        }
+    }
+}
+
+fn compile(pattern: &str) -> SaftResult<Regex> {
+    Regex::new(pattern)
+        .map_err(|err| SaftError::new(format!("invalid filter pattern '{pattern}': {err}")))
+}
+
+fn keyword_regex(keyword: &str) -> SaftResult<Regex> {
+    RegexBuilder::new(&format!(r"\b{}\b", regex::escape(keyword)))
+        .case_insensitive(true)
+        .build()
+        .map_err(|err| SaftError::new(format!("invalid filter keyword '{keyword}': {err}")))
+}
@@ -1,25 +1,35 @@
 use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-use polars::prelude::{AnyValue, ChunkAgg, DataType};
+use polars::prelude::AnyValue;
+use regex::Regex;
 use serde_json::{Map as JsonMap, Value as JsonValue, json};
 
+use crate::approval::ToolApprover;
 use crate::ast::{
     BinaryOp, Expr, FnDef, FnParam, Pattern, Program, PromptExpr, PromptPart, SchemaExpr, Stmt,
     UnaryOp,
 };
-use crate::error::{SaftError, SaftResult, Span};
+use crate::embedding::{EmbeddingProvider, HashingEmbeddingProvider};
+use crate::error::{ErrorKind, SaftError, SaftResult, Span};
+use crate::profile::Profile;
 use crate::provider::{
-    HeuristicMockProvider, PromptProvider, PromptRequest, PromptResponse, ToolCall, ToolDefinition,
-    ToolResult,
+    HeuristicMockProvider, PromptImage, PromptProvider, PromptRequest, PromptResponse,
+    ProviderRegistry, ResponseFormat, ToolCall, ToolDefinition, ToolResult,
 };
+use crate::random::Rng;
+use crate::response_filter::{FilterVerdict, ResponseFilter};
 use crate::schema;
-use crate::stdlib;
+use crate::stdlib::{self, Arity, BuiltinHost, BuiltinImpl};
 use crate::value::{DataFrameValue, FunctionId, Value};
 
 type EnvRef = Rc<RefCell<Env>>;
-type BuiltinFn = fn(Vec<Value>) -> SaftResult<Value>;
+
+/// Distinct patterns kept compiled for the regex builtins; the cache is reset
+/// when full so scripts that build patterns dynamically stay bounded.
+const REGEX_CACHE_LIMIT: usize = 256;
 
 #[derive(Debug)]
 struct Env {
@@ -54,14 +64,27 @@ struct UserFunction {
 #[derive(Clone, Copy)]
 struct BuiltinFunction {
     name: &'static str,
-    arity: usize,
-    func: BuiltinFn,
+    arity: Arity,
+    func: BuiltinImpl,
 }
 
 pub struct Runtime {
     global: EnvRef,
     functions: Vec<RuntimeFunction>,
-    provider: Box<dyn PromptProvider>,
+    providers: ProviderRegistry,
+    embedder: Box<dyn EmbeddingProvider>,
+    response_filters: Vec<Box<dyn ResponseFilter>>,
+    response_annotations: Vec<String>,
+    tool_approver: Option<Box<dyn ToolApprover>>,
+    tool_deadline: Option<Instant>,
+    tool_deadline_hit: bool,
+    run_deadline: Option<Instant>,
+    rng: Rng,
+    /// Span of the host builtin call in progress, used for callbacks it makes.
+    host_call_span: Span,
+    script_args: Vec<String>,
+    regex_cache: HashMap<String, Regex>,
+    profile: Profile,
     options: RuntimeOptions,
 }
 
@@ -69,6 +92,21 @@ pub struct Runtime {
 pub struct RuntimeOptions {
     pub max_tool_rounds: usize,
     pub max_tool_calls: usize,
+    /// Wall-clock budget for each tool call the model requests; `None` disables it.
+    pub tool_timeout: Option<Duration>,
+    /// Wall-clock budget for a whole `run_program`; `None` disables it.
+    pub timeout: Option<Duration>,
+    /// Seed for `random`/`rand_int`/`shuffle`; `None` seeds from the clock.
+    pub seed: Option<u64>,
+    /// Lets `http_get`/`http_post` reach the network; off unless the embedder opts in.
+    pub allow_net: bool,
+    /// Lets `exec` start processes; off unless the embedder opts in.
+    pub allow_exec: bool,
+    /// Logs each prompt round, tool call, and provider latency to stderr.
+    pub verbose: bool,
+    /// Collects per-statement, per-function, and per-prompt-round timings into
+    /// `Runtime::profile`.
+    pub profile: bool,
 }
 
 impl Default for RuntimeOptions {
@@ -76,6 +114,13 @@ impl Default for RuntimeOptions {
         Self {
             max_tool_rounds: 8,
             max_tool_calls: 32,
+            tool_timeout: None,
+            timeout: None,
+            seed: None,
+            allow_net: false,
+            allow_exec: false,
+            verbose: false,
+            profile: false,
         }
     }
 }
@@ -85,6 +130,21 @@ enum Flow {
     Return(Value),
 }
 
+struct RenderedPrompt {
+    text: String,
+    images: Vec<PromptImage>,
+    tools: Vec<ToolDefinition>,
+    tool_map: HashMap<String, FunctionId>,
+    provider: Option<String>,
+    model: Option<String>,
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Runtime {
     pub fn new() -> Self {
         Self::with_provider_and_options(
@@ -105,15 +165,98 @@ impl Runtime {
         let mut runtime = Self {
             global,
             functions: Vec::new(),
-            provider,
+            providers: ProviderRegistry::new(provider),
+            embedder: Box::new(HashingEmbeddingProvider::new()),
+            response_filters: Vec::new(),
+            response_annotations: Vec::new(),
+            tool_approver: None,
+            tool_deadline: None,
+            tool_deadline_hit: false,
+            run_deadline: None,
+            rng: options.seed.map_or_else(Rng::from_time, Rng::seeded),
+            host_call_span: Span::new(0, 0, 1, 1),
+            script_args: Vec::new(),
+            regex_cache: HashMap::new(),
+            profile: Profile::default(),
             options,
         };
         runtime.install_builtins();
         runtime
     }
 
+    /// Installs an async provider; the prompt loop awaits each request through a
+    /// [`BlockOnProvider`](crate::async_provider::BlockOnProvider) bridge, one
+    /// request at a time.
+    #[cfg(feature = "tokio")]
+    pub fn with_async_provider_and_options(
+        provider: Box<dyn crate::async_provider::AsyncPromptProvider>,
+        options: RuntimeOptions,
+    ) -> SaftResult<Self> {
+        let bridge = crate::async_provider::BlockOnProvider::new(provider)?;
+        Ok(Self::with_provider_and_options(Box::new(bridge), options))
+    }
+
+    /// Makes `provider` available to prompts written as `$ ... $ with {provider: name}`.
+    pub fn register_provider(
+        &mut self,
+        name: impl Into<String>,
+        provider: Box<dyn PromptProvider>,
+    ) {
+        self.providers.register(name, provider);
+    }
+
+    /// Adds a guardrail that inspects every final prompt response before it becomes a value.
+    pub fn add_response_filter(&mut self, filter: Box<dyn ResponseFilter>) {
+        self.response_filters.push(filter);
+    }
+
+    /// Notes recorded by `FilterVerdict::Annotate` during this run, in prompt order.
+    pub fn response_annotations(&self) -> &[String] {
+        &self.response_annotations
+    }
+
+    /// Timings collected so far; empty unless `RuntimeOptions::profile` is set.
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Binds `name` in the global scope before the program runs (`run --var`).
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.global
+            .borrow_mut()
+            .values
+            .insert(name.to_string(), value);
+    }
+
+    /// Values returned by the `args()` builtin.
+    pub fn set_script_args(&mut self, args: Vec<String>) {
+        self.script_args = args;
+    }
+
+    /// Requires approval before each model-requested tool call executes.
+    pub fn set_tool_approver(&mut self, approver: Box<dyn ToolApprover>) {
+        self.tool_approver = Some(approver);
+    }
+
+    pub fn set_embedding_provider(&mut self, embedder: Box<dyn EmbeddingProvider>) {
+        self.embedder = embedder;
+    }
+
     pub fn run_program(&mut self, program: &Program) -> SaftResult<()> {
-        let flow = self.exec_block(&program.stmts, self.global.clone())?;
+        let started = Instant::now();
+        self.run_deadline = self.options.timeout.map(|timeout| started + timeout);
+        let result = self
+            .exec_block(&program.stmts, self.global.clone())
+            .and_then(|flow| self.check_run_deadline().map(|()| flow));
+        self.run_deadline = None;
+        if self.options.profile {
+            self.profile.total += started.elapsed();
+        }
+        // Callers may have attached a span on the way out; a timeout has no single location.
+        let flow = result.map_err(|err| match err.kind {
+            ErrorKind::Timeout => SaftError::new(err.message).with_kind(ErrorKind::Timeout),
+            _ => err,
+        })?;
         if let Flow::Return(_) = flow {
             return Err(SaftError::with_span(
                 "return statement is only valid inside a function",
@@ -129,7 +272,7 @@ impl Runtime {
         }
     }
 
-    fn register_builtin(&mut self, name: &'static str, arity: usize, func: BuiltinFn) {
+    fn register_builtin(&mut self, name: &'static str, arity: Arity, func: BuiltinImpl) {
         let id = self.functions.len();
         self.functions
             .push(RuntimeFunction::Builtin(BuiltinFunction {
@@ -157,7 +300,13 @@ impl Runtime {
 
     fn exec_block(&mut self, stmts: &[Stmt], env: EnvRef) -> SaftResult<Flow> {
         for stmt in stmts {
-            match self.exec_stmt(stmt, env.clone())? {
+            let started = self.options.profile.then(Instant::now);
+            let flow = self.exec_stmt(stmt, env.clone());
+            if let Some(started) = started {
+                self.profile
+                    .record_statement(stmt.span().line, started.elapsed());
+            }
+            match flow? {
                 Flow::Continue => {}
                 Flow::Return(value) => return Ok(Flow::Return(value)),
             }
@@ -165,7 +314,34 @@ impl Runtime {
         Ok(Flow::Continue)
     }
 
+    /// Time left before the run's `--timeout`, when one is set.
+    fn time_left(&self) -> Option<Duration> {
+        self.run_deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    fn check_run_deadline(&self) -> SaftResult<()> {
+        match (self.run_deadline, self.options.timeout) {
+            (Some(deadline), Some(timeout)) if Instant::now() >= deadline => Err(SaftError::new(
+                format!("script exceeded its timeout of {timeout:?}"),
+            )
+            .with_kind(ErrorKind::Timeout)),
+            _ => Ok(()),
+        }
+    }
+
     fn exec_stmt(&mut self, stmt: &Stmt, env: EnvRef) -> SaftResult<Flow> {
+        self.check_run_deadline()?;
+        if let Some(deadline) = self.tool_deadline
+            && Instant::now() >= deadline
+        {
+            self.tool_deadline_hit = true;
+            return Err(SaftError::with_span(
+                "tool call exceeded its timeout",
+                stmt.span(),
+            ));
+        }
+
         match stmt {
             Stmt::FnDef(def) => {
                 let id = self.register_user_function(def, env.clone());
@@ -186,13 +362,13 @@ impl Runtime {
                     }
                     _ => {
                         let direct = self.eval_expr(value, env.clone())?;
-                        if let Some(schema) = annotation {
-                            if let Err(detail) = schema::validate(&direct, schema) {
-                                return Err(SaftError::with_span(
-                                    format!("schema validation failed for '{name}': {detail}"),
-                                    *span,
-                                ));
-                            }
+                        if let Some(schema) = annotation
+                            && let Err(detail) = schema::validate(&direct, schema)
+                        {
+                            return Err(SaftError::with_span(
+                                format!("schema validation failed for '{name}': {detail}"),
+                                *span,
+                            ));
                         }
                         direct
                     }
@@ -226,10 +402,12 @@ impl Runtime {
                 let items = match iter_value {
                     Value::List(items) => items,
                     Value::Tuple(items) => items,
+                    Value::DataFrame(df) => stdlib::dataframe_rows(df.frame(), df.rows())
+                        .map_err(|err| err.or_span(*span))?,
                     other => {
                         return Err(SaftError::with_span(
                             format!(
-                                "for-loop expects list or tuple iterable, got {}",
+                                "for-loop expects list, tuple, or dataframe iterable, got {}",
                                 other.type_name()
                             ),
                             *span,
@@ -263,7 +441,8 @@ impl Runtime {
                     Err(SaftError::with_span(
                         format!("assertion failed: expression evaluated to {value}"),
                         *span,
-                    ))
+                    )
+                    .with_kind(ErrorKind::Assertion))
                 }
             }
             Stmt::Expr { expr, .. } => {
@@ -304,7 +483,7 @@ impl Runtime {
                     ));
                 }
 
-                for (name, item) in names.iter().cloned().zip(items.into_iter()) {
+                for (name, item) in names.iter().cloned().zip(items) {
                     env.borrow_mut().values.insert(name, item);
                 }
                 Ok(())
@@ -426,8 +605,8 @@ impl Runtime {
     }
 
     fn eval_prompt(&mut self, prompt: &PromptExpr, env: EnvRef) -> SaftResult<String> {
-        let (rendered_prompt, tools, tool_map) = self.render_prompt(prompt, env)?;
-        self.run_prompt_with_tools(&rendered_prompt, &tools, &tool_map, prompt.span)
+        let rendered = self.render_prompt(prompt, env)?;
+        self.run_prompt_with_tools(&rendered.text, &rendered, None, prompt.span)
     }
 
     fn eval_typed_prompt_assignment(
@@ -438,29 +617,33 @@ impl Runtime {
         env: EnvRef,
         span: Span,
     ) -> SaftResult<Value> {
-        let (rendered_prompt, tools, tool_map) = self.render_prompt(prompt, env)?;
+        let rendered = self.render_prompt(prompt, env)?;
         let schema_json = schema::to_json_schema(schema_expr);
-        let hardened_prompt = self.build_typed_prompt_contract(
-            &rendered_prompt,
-            schema_expr,
-            &schema_json,
-            None,
-            None,
-        );
-        let first_raw = self.run_prompt_with_tools(&hardened_prompt, &tools, &tool_map, span)?;
+        let response_format = ResponseFormat {
+            name: name.to_string(),
+            schema: schema_json.clone(),
+        };
+        let hardened_prompt =
+            self.build_typed_prompt_contract(&rendered.text, schema_expr, &schema_json, None, None);
+        let first_raw =
+            self.run_prompt_with_tools(&hardened_prompt, &rendered, Some(&response_format), span)?;
 
         match self.parse_and_validate_typed_prompt_output(&first_raw, schema_expr, span) {
             Ok(value) => Ok(value),
             Err(first_error) => {
                 let repaired_prompt = self.build_typed_prompt_contract(
-                    &rendered_prompt,
+                    &rendered.text,
                     schema_expr,
                     &schema_json,
                     Some(&first_error),
                     Some(&first_raw),
                 );
-                let second_raw =
-                    self.run_prompt_with_tools(&repaired_prompt, &tools, &tool_map, span)?;
+                let second_raw = self.run_prompt_with_tools(
+                    &repaired_prompt,
+                    &rendered,
+                    Some(&response_format),
+                    span,
+                )?;
 
                 self.parse_and_validate_typed_prompt_output(&second_raw, schema_expr, span)
                     .map_err(|second_error| {
@@ -564,23 +747,56 @@ impl Runtime {
 
     fn run_prompt_with_tools(
         &mut self,
-        rendered_prompt: &str,
-        tools: &[ToolDefinition],
-        tool_map: &HashMap<String, FunctionId>,
+        prompt_text: &str,
+        rendered: &RenderedPrompt,
+        response_format: Option<&ResponseFormat>,
         span: Span,
     ) -> SaftResult<String> {
         let mut tool_results: Vec<ToolResult> = Vec::new();
         let mut total_tool_calls = 0usize;
 
-        for _round in 0..self.options.max_tool_rounds {
+        let max_rounds = self.options.max_tool_rounds;
+        for round in 1..=max_rounds {
+            self.check_run_deadline()?;
             let request = PromptRequest {
-                prompt: rendered_prompt.to_string(),
-                tools: tools.to_vec(),
+                prompt: prompt_text.to_string(),
+                images: rendered.images.clone(),
+                tools: rendered.tools.clone(),
                 tool_results: tool_results.clone(),
+                response_format: response_format.cloned(),
+                model: rendered.model.clone(),
+                timeout: self.time_left(),
             };
 
-            match self.provider.complete(request)? {
-                PromptResponse::FinalText(text) => return Ok(text),
+            let provider = self
+                .providers
+                .get_mut(rendered.provider.as_deref())
+                .map_err(|err| SaftError::with_span(err.message, span))?;
+            let started = Instant::now();
+            let response = provider.complete(request);
+            if self.options.profile {
+                self.profile
+                    .record_prompt_round(span.line, started.elapsed());
+            }
+            // A request cut short by the timeout reports the timeout, not its own failure.
+            self.check_run_deadline()?;
+            let response = response.map_err(|err| err.with_kind(ErrorKind::Provider))?;
+            self.trace(|| {
+                let outcome = match &response {
+                    PromptResponse::FinalText(text) => {
+                        format!("final text ({} chars)", text.chars().count())
+                    }
+                    PromptResponse::ToolCalls(calls) => format!("{} tool call(s)", calls.len()),
+                };
+                format!(
+                    "prompt at line {}: round {round}/{max_rounds} via {} took {}ms -> {outcome}",
+                    span.line,
+                    rendered.provider.as_deref().unwrap_or("default provider"),
+                    started.elapsed().as_millis()
+                )
+            });
+            match response {
+                PromptResponse::FinalText(text) => return self.apply_response_filters(text, span),
                 PromptResponse::ToolCalls(calls) => {
                     if calls.is_empty() {
                         return Err(SaftError::with_span(
@@ -589,7 +805,7 @@ impl Runtime {
                         ));
                     }
 
-                    if tool_map.is_empty() {
+                    if rendered.tool_map.is_empty() {
                         return Err(SaftError::with_span(
                             "provider attempted tool calls but no tools are exposed in prompt",
                             span,
@@ -608,7 +824,18 @@ impl Runtime {
                             ));
                         }
 
-                        let result = self.execute_tool_call(&call, tool_map, span)?;
+                        let started = Instant::now();
+                        let result = self.execute_tool_call(&call, &rendered.tool_map, span)?;
+                        self.trace(|| {
+                            format!(
+                                "tool call {total_tool_calls}/{}: {}({}) took {}ms -> {}",
+                                self.options.max_tool_calls,
+                                call.name,
+                                call.args,
+                                started.elapsed().as_millis(),
+                                result.output
+                            )
+                        });
                         tool_results.push(result);
                     }
                 }
@@ -624,12 +851,34 @@ impl Runtime {
         ))
     }
 
-    fn render_prompt(
-        &mut self,
-        prompt: &PromptExpr,
-        env: EnvRef,
-    ) -> SaftResult<(String, Vec<ToolDefinition>, HashMap<String, FunctionId>)> {
+    /// Writes a `verbose` trace line to stderr; `message` is only built when enabled.
+    fn trace(&self, message: impl FnOnce() -> String) {
+        if self.options.verbose {
+            eprintln!("[orangensaft] {}", message());
+        }
+    }
+
+    fn apply_response_filters(&mut self, text: String, span: Span) -> SaftResult<String> {
+        let mut current = text;
+        for filter in &mut self.response_filters {
+            match filter.inspect(&current) {
+                FilterVerdict::Allow => {}
+                FilterVerdict::Replace(replacement) => current = replacement,
+                FilterVerdict::Annotate(note) => self.response_annotations.push(note),
+                FilterVerdict::Reject(reason) => {
+                    return Err(SaftError::with_span(
+                        format!("prompt response rejected by filter: {reason}"),
+                        span,
+                    ));
+                }
+            }
+        }
+        Ok(current)
+    }
+
+    fn render_prompt(&mut self, prompt: &PromptExpr, env: EnvRef) -> SaftResult<RenderedPrompt> {
         let mut rendered = String::new();
+        let mut images: Vec<PromptImage> = Vec::new();
         let mut tools: Vec<ToolDefinition> = Vec::new();
         let mut tool_map: HashMap<String, FunctionId> = HashMap::new();
         let mut generated_counter = 1usize;
@@ -675,6 +924,13 @@ impl Runtime {
 
                             rendered.push_str(&tool_name);
                         }
+                        Value::Image(image) => {
+                            images.push(PromptImage {
+                                media_type: image.media_type,
+                                data_base64: image.data_base64.to_string(),
+                            });
+                            rendered.push_str(&format!("[image {}]", images.len()));
+                        }
                         other => {
                             let serialized = self.serialize_prompt_value(&other, expr.span())?;
                             rendered.push_str(&serialized);
@@ -684,7 +940,61 @@ impl Runtime {
             }
         }
 
-        Ok((rendered, tools, tool_map))
+        let (provider, model) = match &prompt.options {
+            Some(options) => self.eval_prompt_options(options, env)?,
+            None => (None, None),
+        };
+
+        Ok(RenderedPrompt {
+            text: rendered,
+            images,
+            tools,
+            tool_map,
+            provider,
+            model,
+        })
+    }
+
+    fn eval_prompt_options(
+        &mut self,
+        options: &Expr,
+        env: EnvRef,
+    ) -> SaftResult<(Option<String>, Option<String>)> {
+        let span = options.span();
+        let Value::Object(map) = self.eval_expr(options, env)? else {
+            return Err(SaftError::with_span(
+                "prompt options after 'with' must be an object",
+                span,
+            ));
+        };
+
+        let mut provider = None;
+        let mut model = None;
+        for (key, value) in map {
+            let slot = match key.as_str() {
+                "provider" => &mut provider,
+                "model" => &mut model,
+                other => {
+                    return Err(SaftError::with_span(
+                        format!("unknown prompt option '{other}' (expected 'provider' or 'model')"),
+                        span,
+                    ));
+                }
+            };
+            match value {
+                Value::String(text) => *slot = Some(text),
+                other => {
+                    return Err(SaftError::with_span(
+                        format!(
+                            "prompt option '{key}' must be a string, got {}",
+                            other.type_name()
+                        ),
+                        span,
+                    ));
+                }
+            }
+        }
+        Ok((provider, model))
     }
 
     fn function_param_names(&self, id: FunctionId, span: Span) -> SaftResult<Vec<String>> {
@@ -695,9 +1005,9 @@ impl Runtime {
 
         match function {
             RuntimeFunction::User(user) => Ok(user.params.iter().map(|p| p.name.clone()).collect()),
-            RuntimeFunction::Builtin(builtin) => {
-                Ok((0..builtin.arity).map(|idx| format!("arg{idx}")).collect())
-            }
+            RuntimeFunction::Builtin(builtin) => Ok((0..builtin.arity.min())
+                .map(|idx| format!("arg{idx}"))
+                .collect()),
         }
     }
 
@@ -715,7 +1025,38 @@ impl Runtime {
         })?;
 
         let args = self.tool_args_to_values(function_id, &call.args, span)?;
-        let output_value = self.call_function(function_id, args, span)?;
+        if let Some(approver) = &mut self.tool_approver
+            && !approver
+                .approve(call)
+                .map_err(|err| SaftError::with_span(err.message, span))?
+        {
+            return Ok(ToolResult {
+                id: call.id.clone(),
+                name: call.name.clone(),
+                args: call.args.clone(),
+                output: json!({"error": "tool call denied by user"}),
+            });
+        }
+
+        let output_value = match self.options.tool_timeout {
+            Some(timeout) => {
+                match self.call_function_with_timeout(function_id, args, timeout, span)? {
+                    Some(value) => value,
+                    None => {
+                        return Ok(ToolResult {
+                            id: call.id.clone(),
+                            name: call.name.clone(),
+                            args: call.args.clone(),
+                            output: json!({
+                                "error": "tool call timed out",
+                                "timeout_ms": timeout.as_millis() as u64,
+                            }),
+                        });
+                    }
+                }
+            }
+            None => self.call_function(function_id, args, span)?,
+        };
         let output_json = self.value_to_json(&output_value, span)?;
 
         Ok(ToolResult {
@@ -726,6 +1067,38 @@ impl Runtime {
         })
     }
 
+    /// Runs a tool function under a deadline checked before every statement (and
+    /// by `sleep`).
+    /// Returns `Ok(None)` when this call's own budget ran out; an enclosing tool
+    /// call's expired deadline is propagated as an error instead.
+    fn call_function_with_timeout(
+        &mut self,
+        function_id: FunctionId,
+        args: Vec<Value>,
+        timeout: Duration,
+        span: Span,
+    ) -> SaftResult<Option<Value>> {
+        let outer_deadline = self.tool_deadline;
+        let own_deadline = Instant::now() + timeout;
+        self.tool_deadline =
+            Some(outer_deadline.map_or(own_deadline, |outer| outer.min(own_deadline)));
+
+        let result = self.call_function(function_id, args, span);
+        self.tool_deadline = outer_deadline;
+
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(_)
+                if self.tool_deadline_hit
+                    && outer_deadline.is_none_or(|outer| Instant::now() < outer) =>
+            {
+                self.tool_deadline_hit = false;
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
     fn tool_args_to_values(
         &self,
         function_id: FunctionId,
@@ -815,7 +1188,7 @@ impl Runtime {
         let mut values = Vec::new();
         match args {
             JsonValue::Array(items) => {
-                if items.len() != builtin.arity {
+                if !builtin.arity.accepts(items.len()) {
                     return Err(SaftError::with_span(
                         format!(
                             "builtin '{}' expects {} arguments, got {}",
@@ -830,7 +1203,7 @@ impl Runtime {
                     values.push(self.json_to_value(item.clone(), span)?);
                 }
             }
-            JsonValue::Object(map) if builtin.arity == 1 => {
+            JsonValue::Object(map) if builtin.arity.accepts(1) => {
                 let value = map.values().next().ok_or_else(|| {
                     SaftError::with_span(
                         format!("builtin '{}' missing argument", builtin.name),
@@ -839,7 +1212,7 @@ impl Runtime {
                 })?;
                 values.push(self.json_to_value(value.clone(), span)?);
             }
-            JsonValue::Null if builtin.arity == 0 => {}
+            JsonValue::Null if builtin.arity.accepts(0) => {}
             _ => {
                 return Err(SaftError::with_span(
                     format!(
@@ -901,6 +1274,20 @@ impl Runtime {
                 Ok(JsonValue::Object(out))
             }
             Value::DataFrame(df) => self.dataframe_to_context_json(df, span),
+            Value::LazyFrame(_) => Err(SaftError::with_span(
+                "lazyframe cannot be serialized; collect(...) it first",
+                span,
+            )),
+            Value::Image(image) => Ok(json!({
+                "__kind": "image",
+                "path": image.path,
+                "media_type": image.media_type,
+            })),
+            Value::VectorStore(store) => Ok(json!({
+                "__kind": "vector_store",
+                "size": store.len(),
+                "dimensions": store.dimensions(),
+            })),
             Value::Function(_) => Err(SaftError::with_span(
                 "function interpolation requires tool-calling (Milestone 3)",
                 span,
@@ -981,50 +1368,35 @@ impl Runtime {
         dataframe: &DataFrameValue,
         max_columns: usize,
     ) -> (Vec<JsonValue>, usize) {
-        let mut profile = Vec::new();
-        let mut numeric_count = 0usize;
-
-        for column in dataframe.frame().get_columns() {
-            let casted = match column.cast(&DataType::Float64) {
-                Ok(series) => series,
-                Err(_) => continue,
-            };
-            let as_float = match casted.f64() {
-                Ok(values) => values,
-                Err(_) => continue,
-            };
-
-            let non_null_count = as_float.len().saturating_sub(as_float.null_count());
-            if non_null_count == 0 {
-                continue;
-            }
-
-            numeric_count += 1;
-            if profile.len() >= max_columns {
-                continue;
-            }
-
-            let mut column_profile = JsonMap::new();
-            column_profile.insert(
-                "column".to_string(),
-                JsonValue::String(column.name().to_string()),
-            );
-            column_profile.insert(
-                "non_null_count".to_string(),
-                JsonValue::Number((non_null_count as u64).into()),
-            );
-            if let Some(value) = as_float.mean().and_then(serde_json::Number::from_f64) {
-                column_profile.insert("mean".to_string(), JsonValue::Number(value));
-            }
-            if let Some(value) = as_float.min().and_then(serde_json::Number::from_f64) {
-                column_profile.insert("min".to_string(), JsonValue::Number(value));
-            }
-            if let Some(value) = as_float.max().and_then(serde_json::Number::from_f64) {
-                column_profile.insert("max".to_string(), JsonValue::Number(value));
-            }
+        let numeric = dataframe
+            .column_profiles()
+            .into_iter()
+            .filter(|column| column.numeric)
+            .collect::<Vec<_>>();
+        let numeric_count = numeric.len();
 
-            profile.push(JsonValue::Object(column_profile));
-        }
+        let profile = numeric
+            .into_iter()
+            .take(max_columns)
+            .map(|column| {
+                let mut column_profile = JsonMap::new();
+                column_profile.insert("column".to_string(), JsonValue::String(column.name));
+                column_profile.insert(
+                    "non_null_count".to_string(),
+                    JsonValue::Number((column.non_null_count as u64).into()),
+                );
+                for (key, value) in [
+                    ("mean", column.mean),
+                    ("min", column.min),
+                    ("max", column.max),
+                ] {
+                    if let Some(value) = value.and_then(serde_json::Number::from_f64) {
+                        column_profile.insert(key.to_string(), JsonValue::Number(value));
+                    }
+                }
+                JsonValue::Object(column_profile)
+            })
+            .collect();
 
         (profile, numeric_count)
     }
@@ -1253,6 +1625,17 @@ impl Runtime {
                     .cloned()
                     .ok_or_else(|| SaftError::with_span(format!("missing key '{key}'"), span))
             }
+            Value::DataFrame(df) => {
+                let Value::String(column) = index else {
+                    return Err(SaftError::with_span(
+                        "dataframe index expects string column name",
+                        span,
+                    ));
+                };
+                stdlib::dataframe_column(df.frame(), &column)
+                    .map(Value::List)
+                    .map_err(|err| err.or_span(span))
+            }
             other => Err(SaftError::with_span(
                 format!("indexing is not supported on {}", other.type_name()),
                 span,
@@ -1285,7 +1668,7 @@ impl Runtime {
 
         match function {
             RuntimeFunction::Builtin(builtin) => {
-                if args.len() != builtin.arity {
+                if !builtin.arity.accepts(args.len()) {
                     return Err(SaftError::with_span(
                         format!(
                             "builtin '{}' expects {} arguments, got {}",
@@ -1296,60 +1679,83 @@ impl Runtime {
                         call_span,
                     ));
                 }
-                (builtin.func)(args)
+                let result = match builtin.func {
+                    BuiltinImpl::Pure(func) => func(args),
+                    BuiltinImpl::Host(func) => {
+                        let outer_span = std::mem::replace(&mut self.host_call_span, call_span);
+                        let result = func(self, args);
+                        self.host_call_span = outer_span;
+                        result
+                    }
+                };
+                result.map_err(|err| err.or_span(call_span))
             }
             RuntimeFunction::User(user) => {
-                if args.len() != user.params.len() {
-                    return Err(SaftError::with_span(
-                        format!(
-                            "function '{}' expects {} arguments, got {}",
-                            user.name,
-                            user.params.len(),
-                            args.len()
-                        ),
-                        call_span,
-                    ));
+                let started = self.options.profile.then(Instant::now);
+                let result = self.call_user_function(&user, args, call_span);
+                if let Some(started) = started {
+                    self.profile.record_function(&user.name, started.elapsed());
                 }
+                result
+            }
+        }
+    }
 
-                let call_env = Rc::new(RefCell::new(Env::new(Some(user.closure.clone()))));
-                for (arg, param) in args.into_iter().zip(user.params.iter()) {
-                    if let Some(schema) = &param.schema {
-                        if let Err(detail) = schema::validate(&arg, schema) {
-                            return Err(SaftError::with_span(
-                                format!(
-                                    "invalid argument for parameter '{}' in '{}': {}",
-                                    param.name, user.name, detail
-                                ),
-                                call_span,
-                            ));
-                        }
-                    }
-                    call_env.borrow_mut().values.insert(param.name.clone(), arg);
-                }
+    fn call_user_function(
+        &mut self,
+        user: &UserFunction,
+        args: Vec<Value>,
+        call_span: Span,
+    ) -> SaftResult<Value> {
+        if args.len() != user.params.len() {
+            return Err(SaftError::with_span(
+                format!(
+                    "function '{}' expects {} arguments, got {}",
+                    user.name,
+                    user.params.len(),
+                    args.len()
+                ),
+                call_span,
+            ));
+        }
 
-                let flow = self.exec_block(&user.body, call_env)?;
-                let result = match flow {
-                    Flow::Continue => Value::Nil,
-                    Flow::Return(value) => value,
-                };
+        let call_env = Rc::new(RefCell::new(Env::new(Some(user.closure.clone()))));
+        for (arg, param) in args.into_iter().zip(user.params.iter()) {
+            if let Some(schema) = &param.schema
+                && let Err(detail) = schema::validate(&arg, schema)
+            {
+                return Err(SaftError::with_span(
+                    format!(
+                        "invalid argument for parameter '{}' in '{}': {}",
+                        param.name, user.name, detail
+                    ),
+                    call_span,
+                ));
+            }
+            call_env.borrow_mut().values.insert(param.name.clone(), arg);
+        }
 
-                if let Some(schema) = &user.return_schema {
-                    if let Err(detail) = schema::validate(&result, schema) {
-                        return Err(SaftError::with_span(
-                            format!(
-                                "function '{}' returned invalid value for schema {}: {}",
-                                user.name,
-                                schema::schema_to_string(schema),
-                                detail
-                            ),
-                            call_span,
-                        ));
-                    }
-                }
+        let flow = self.exec_block(&user.body, call_env)?;
+        let result = match flow {
+            Flow::Continue => Value::Nil,
+            Flow::Return(value) => value,
+        };
 
-                Ok(result)
-            }
+        if let Some(schema) = &user.return_schema
+            && let Err(detail) = schema::validate(&result, schema)
+        {
+            return Err(SaftError::with_span(
+                format!(
+                    "function '{}' returned invalid value for schema {}: {}",
+                    user.name,
+                    schema::schema_to_string(schema),
+                    detail
+                ),
+                call_span,
+            ));
         }
+
+        Ok(result)
     }
 
     fn get_var(&self, env: EnvRef, name: &str) -> Option<Value> {
@@ -1364,6 +1770,73 @@ impl Runtime {
     }
 }
 
+impl BuiltinHost for Runtime {
+    fn embed(&mut self, text: &str) -> SaftResult<Vec<f64>> {
+        self.embedder.embed(text)
+    }
+
+    fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    fn call(&mut self, function: FunctionId, args: Vec<Value>) -> SaftResult<Value> {
+        self.call_function(function, args, self.host_call_span)
+    }
+
+    fn encode_json(&self, value: &Value) -> SaftResult<JsonValue> {
+        self.value_to_json(value, self.host_call_span)
+    }
+
+    fn decode_json(&self, json: JsonValue) -> SaftResult<Value> {
+        self.json_to_value(json, self.host_call_span)
+    }
+
+    fn script_args(&self) -> &[String] {
+        &self.script_args
+    }
+
+    fn allow_net(&self) -> bool {
+        self.options.allow_net
+    }
+
+    fn allow_exec(&self) -> bool {
+        self.options.allow_exec
+    }
+
+    /// Wakes early at whichever comes first: the run's `--timeout` or the
+    /// running tool call's deadline.
+    fn sleep(&mut self, duration: Duration) -> SaftResult<()> {
+        let wake = [self.run_deadline, self.tool_deadline]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        std::thread::sleep(wake.map_or(duration, |left| left.min(duration)));
+        self.check_run_deadline()?;
+        if self
+            .tool_deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.tool_deadline_hit = true;
+            return Err(SaftError::new("tool call exceeded its timeout"));
+        }
+        Ok(())
+    }
+
+    fn regex(&mut self, pattern: &str) -> SaftResult<Regex> {
+        if let Some(regex) = self.regex_cache.get(pattern) {
+            return Ok(regex.clone());
+        }
+        let regex = Regex::new(pattern)
+            .map_err(|err| SaftError::new(format!("invalid regex '{pattern}': {err}")))?;
+        if self.regex_cache.len() >= REGEX_CACHE_LIMIT {
+            self.regex_cache.clear();
+        }
+        self.regex_cache.insert(pattern.to_string(), regex.clone());
+        Ok(regex)
+    }
+}
+
 fn truncate_text(text: &str, max_chars: usize) -> String {
     if text.chars().count() <= max_chars {
         return text.to_string();
@@ -1387,7 +1860,7 @@ fn schema_example_json(schema: &SchemaExpr) -> Option<JsonValue> {
                 .map(|item| schema_example_json(item).unwrap_or(JsonValue::Null))
                 .collect(),
         )),
-        SchemaExpr::Object(fields) => {
+        SchemaExpr::Object(fields, _) => {
             let mut obj = serde_json::Map::new();
             for field in fields {
                 let example = schema_example_json(&field.schema).unwrap_or(JsonValue::Null);
@@ -1395,6 +1868,7 @@ fn schema_example_json(schema: &SchemaExpr) -> Option<JsonValue> {
             }
             Some(JsonValue::Object(obj))
         }
+        SchemaExpr::DataFrame(_) => None,
         SchemaExpr::Union(variants) => variants.first().and_then(schema_example_json),
         SchemaExpr::Optional(inner) => schema_example_json(inner).or(Some(JsonValue::Null)),
     }
@@ -1,4 +1,6 @@
-use crate::ast::SchemaExpr;
+use polars::prelude::DataType;
+
+use crate::ast::{SchemaExpr, SchemaField};
 use crate::value::Value;
 use serde_json::{Map as JsonMap, Value as JsonValue};
 
@@ -52,7 +54,7 @@ fn validate_inner(value: &Value, schema: &SchemaExpr, path: &str) -> Result<(),
             }
             _ => Err(type_mismatch(path, schema, value)),
         },
-        SchemaExpr::Object(fields) => match value {
+        SchemaExpr::Object(fields, _) => match value {
             Value::Object(map) => {
                 for field in fields {
                     let Some(field_value) = map.get(&field.name) else {
@@ -75,6 +77,20 @@ fn validate_inner(value: &Value, schema: &SchemaExpr, path: &str) -> Result<(),
             }
             _ => Err(type_mismatch(path, schema, value)),
         },
+        SchemaExpr::DataFrame(columns) => match value {
+            Value::DataFrame(df) => {
+                let problems = dataframe_column_problems(df.frame(), columns);
+                if problems.is_empty() {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{path}: dataframe columns do not match schema ({})",
+                        problems.join("; ")
+                    ))
+                }
+            }
+            _ => Err(type_mismatch(path, schema, value)),
+        },
         SchemaExpr::Union(variants) => {
             let mut variant_errors = Vec::new();
             for variant in variants {
@@ -99,6 +115,58 @@ fn validate_inner(value: &Value, schema: &SchemaExpr, path: &str) -> Result<(),
     }
 }
 
+/// Every missing or mistyped column, so one failure reports them all. Nulls
+/// are allowed in any column.
+fn dataframe_column_problems(
+    frame: &polars::prelude::DataFrame,
+    columns: &[SchemaField],
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    for column in columns {
+        match frame.column(&column.name) {
+            Ok(series) if column_matches(series.dtype(), &column.schema) => {}
+            Ok(series) => problems.push(format!(
+                "column '{}' expected {}, got {}",
+                column.name,
+                schema_to_string(&column.schema),
+                dtype_name(series.dtype())
+            )),
+            Err(_) => problems.push(format!("missing column '{}'", column.name)),
+        }
+    }
+    problems
+}
+
+fn column_matches(dtype: &DataType, schema: &SchemaExpr) -> bool {
+    match schema {
+        SchemaExpr::Any => true,
+        SchemaExpr::Int => dtype.is_integer(),
+        SchemaExpr::Float => dtype.is_float(),
+        SchemaExpr::Bool => matches!(dtype, DataType::Boolean),
+        SchemaExpr::String => matches!(dtype, DataType::String),
+        SchemaExpr::Union(variants) => variants
+            .iter()
+            .any(|variant| column_matches(dtype, variant)),
+        SchemaExpr::Optional(inner) => column_matches(dtype, inner),
+        _ => false,
+    }
+}
+
+/// Column dtypes in schema vocabulary where one exists (`int`, not `i64`).
+fn dtype_name(dtype: &DataType) -> String {
+    if dtype.is_integer() {
+        "int".to_string()
+    } else if dtype.is_float() {
+        "float".to_string()
+    } else {
+        match dtype {
+            DataType::Boolean => "bool".to_string(),
+            DataType::String => "string".to_string(),
+            other => other.to_string(),
+        }
+    }
+}
+
 fn type_mismatch(path: &str, schema: &SchemaExpr, value: &Value) -> String {
     format!(
         "{path}: expected {}, got {}",
@@ -123,7 +191,7 @@ pub fn schema_to_string(schema: &SchemaExpr) -> String {
                 .join(", ");
             format!("({body})")
         }
-        SchemaExpr::Object(fields) => {
+        SchemaExpr::Object(fields, _) => {
             let body = fields
                 .iter()
                 .map(|field| format!("{}: {}", field.name, schema_to_string(&field.schema)))
@@ -131,6 +199,15 @@ pub fn schema_to_string(schema: &SchemaExpr) -> String {
                 .join(", ");
             format!("{{{body}}}")
         }
+        SchemaExpr::DataFrame(columns) if columns.is_empty() => "dataframe".to_string(),
+        SchemaExpr::DataFrame(columns) => {
+            let body = columns
+                .iter()
+                .map(|column| format!("{}: {}", column.name, schema_to_string(&column.schema)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("dataframe{{{body}}}")
+        }
         SchemaExpr::Union(variants) => variants
             .iter()
             .map(schema_to_string)
@@ -171,7 +248,7 @@ pub fn to_json_schema(schema: &SchemaExpr) -> JsonValue {
             obj.insert("items".to_string(), JsonValue::Bool(false));
             JsonValue::Object(obj)
         }
-        SchemaExpr::Object(fields) => {
+        SchemaExpr::Object(fields, _) => {
             let mut properties = JsonMap::new();
             let mut required = Vec::with_capacity(fields.len());
             for field in fields {
@@ -186,6 +263,21 @@ pub fn to_json_schema(schema: &SchemaExpr) -> JsonValue {
             obj.insert("additionalProperties".to_string(), JsonValue::Bool(false));
             JsonValue::Object(obj)
         }
+        // Dataframes cannot come back from a model; describe them as row objects so
+        // a contract is still meaningful, and validation reports the kind mismatch.
+        SchemaExpr::DataFrame(columns) => {
+            let mut row = JsonMap::new();
+            row.insert("type".to_string(), JsonValue::String("object".to_string()));
+            let properties = columns
+                .iter()
+                .map(|column| (column.name.clone(), to_json_schema(&column.schema)))
+                .collect::<JsonMap<_, _>>();
+            row.insert("properties".to_string(), JsonValue::Object(properties));
+            let mut obj = JsonMap::new();
+            obj.insert("type".to_string(), JsonValue::String("array".to_string()));
+            obj.insert("items".to_string(), JsonValue::Object(row));
+            JsonValue::Object(obj)
+        }
         SchemaExpr::Union(variants) => {
             let mut obj = JsonMap::new();
             obj.insert(
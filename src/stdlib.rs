@@ -1,105 +1,1234 @@
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Cursor, Write};
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
-use polars::prelude::{AnyValue, ChunkAgg, CsvReader, DataType, SerReader};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike, Utc};
+use polars::io::mmap::MmapBytesReader;
+use polars::prelude::{
+    AnyValue, ChunkAgg, ChunkQuantile, ChunkVar, CsvReader, CsvWriter, DataFrame, DataType, Expr,
+    IdxCa, IdxSize, IntoLazy, LazyCsvReader, LazyFileListReader, NamedFrom, ParquetReader,
+    ParquetWriter, QuantileInterpolOptions, Schema, SerReader, SerWriter, Series,
+    UniqueKeepStrategy, col, lit,
+};
+use polars::sql::SQLContext;
+use regex::Regex;
+use serde_json::Value as JsonValue;
 
 use crate::error::{SaftError, SaftResult};
-use crate::value::{DataFrameValue, Value};
+use crate::hash;
+use crate::http;
+use crate::random::Rng;
+use crate::value::{
+    ColumnProfile, DataFrameValue, FunctionId, ImageValue, LazyFrameValue, Value, VectorEntry,
+    VectorStoreValue,
+};
+use crate::xlsx;
 
 pub type BuiltinFn = fn(Vec<Value>) -> SaftResult<Value>;
+pub type HostBuiltinFn = fn(&mut dyn BuiltinHost, Vec<Value>) -> SaftResult<Value>;
+
+/// Runtime services available to builtins that need more than their arguments.
+pub trait BuiltinHost {
+    fn embed(&mut self, text: &str) -> SaftResult<Vec<f64>>;
+    fn rng(&mut self) -> &mut Rng;
+    /// Calls a function value (user-defined or builtin) with positional arguments.
+    fn call(&mut self, function: FunctionId, args: Vec<Value>) -> SaftResult<Value>;
+    /// Same Value -> JSON encoding used for prompt interpolation and tool results.
+    fn encode_json(&self, value: &Value) -> SaftResult<JsonValue>;
+    fn decode_json(&self, json: JsonValue) -> SaftResult<Value>;
+    /// Arguments given to the script after `--` on the command line.
+    fn script_args(&self) -> &[String];
+    /// Compiles `pattern`, reusing earlier compilations of the same pattern.
+    fn regex(&mut self, pattern: &str) -> SaftResult<Regex>;
+    /// Whether network builtins may run (`--allow-net`).
+    fn allow_net(&self) -> bool;
+    /// Whether `exec` may start processes (`--allow-exec`).
+    fn allow_exec(&self) -> bool;
+    /// Sleeps for `duration`, or until the run's `--timeout`, which is then reported.
+    fn sleep(&mut self, duration: Duration) -> SaftResult<()>;
+}
+
+#[derive(Clone, Copy)]
+pub enum BuiltinImpl {
+    Pure(BuiltinFn),
+    Host(HostBuiltinFn),
+}
+
+/// Number of arguments a builtin accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exact(usize),
+    /// Variadic builtins: at least this many arguments.
+    AtLeast(usize),
+    /// Optional trailing arguments: between `min` and `max` inclusive.
+    Range(usize, usize),
+}
+
+impl Arity {
+    pub fn accepts(self, count: usize) -> bool {
+        match self {
+            Arity::Exact(n) => count == n,
+            Arity::AtLeast(n) => count >= n,
+            Arity::Range(min, max) => (min..=max).contains(&count),
+        }
+    }
+
+    pub fn min(self) -> usize {
+        match self {
+            Arity::Exact(n) | Arity::AtLeast(n) | Arity::Range(n, _) => n,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arity::Exact(n) => write!(f, "{n}"),
+            Arity::AtLeast(n) => write!(f, "at least {n}"),
+            Arity::Range(min, max) => write!(f, "{min} to {max}"),
+        }
+    }
+}
 
 #[derive(Clone, Copy)]
 pub struct BuiltinSpec {
     pub name: &'static str,
-    pub arity: usize,
-    pub func: BuiltinFn,
+    /// Call shape with accepted types, e.g. `split(string, sep: string) -> [string]`.
+    pub signature: &'static str,
+    /// One-line description shown by `orangensaft doc`.
+    pub doc: &'static str,
+    pub arity: Arity,
+    pub func: BuiltinImpl,
 }
 
 const DEFAULT_HEAD_ROWS: usize = 5;
+const MAX_RANGE_LEN: usize = 10_000_000;
 
 pub const BUILTIN_NAMES: &[&str] = &[
-    "upper", "print", "len", "type", "read", "shape", "columns", "head", "select", "mean", "sum",
-    "min", "max",
+    "upper",
+    "lower",
+    "trim",
+    "lstrip",
+    "rstrip",
+    "split",
+    "join",
+    "replace",
+    "contains",
+    "starts_with",
+    "ends_with",
+    "substr",
+    "char_at",
+    "re_match",
+    "re_find_all",
+    "re_replace",
+    "str",
+    "format",
+    "parse_int",
+    "parse_float",
+    "abs",
+    "round",
+    "floor",
+    "ceil",
+    "sqrt",
+    "pow",
+    "exp",
+    "log",
+    "sin",
+    "cos",
+    "tan",
+    "pi",
+    "random",
+    "rand_int",
+    "shuffle",
+    "uuid",
+    "sha256",
+    "md5",
+    "http_get",
+    "http_post",
+    "exec",
+    "sort",
+    "sort_by",
+    "map",
+    "filter",
+    "reduce",
+    "zip",
+    "enumerate",
+    "range",
+    "unique",
+    "reverse",
+    "last",
+    "keys",
+    "values",
+    "items",
+    "get",
+    "has",
+    "merge",
+    "copy",
+    "json_parse",
+    "json_stringify",
+    "print",
+    "eprint",
+    "pprint",
+    "error",
+    "input",
+    "len",
+    "type",
+    "is_int",
+    "is_float",
+    "is_number",
+    "is_bool",
+    "is_string",
+    "is_list",
+    "is_tuple",
+    "is_object",
+    "is_dataframe",
+    "is_lazyframe",
+    "is_vstore",
+    "is_image",
+    "is_function",
+    "is_nil",
+    "read",
+    "write_csv",
+    "read_parquet",
+    "write_parquet",
+    "read_excel",
+    "scan",
+    "lazy",
+    "collect",
+    "sql",
+    "read_text",
+    "write_text",
+    "read_lines",
+    "append_file",
+    "exists",
+    "file_size",
+    "list_dir",
+    "args",
+    "now",
+    "format_date",
+    "parse_date",
+    "sleep",
+    "shape",
+    "columns",
+    "head",
+    "tail",
+    "sample",
+    "to_records",
+    "select",
+    "group_by",
+    "sort_df",
+    "with_column",
+    "drop",
+    "rename",
+    "distinct",
+    "distinct_on",
+    "describe",
+    "null_counts",
+    "drop_nulls",
+    "fill_null",
+    "concat",
+    "mean",
+    "sum",
+    "min",
+    "max",
+    "median",
+    "std",
+    "quantile",
+    "count",
+    "nunique",
+    "value_counts",
+    "embed",
+    "cosine",
+    "vstore",
+    "vstore_add",
+    "vstore_query",
+    "image",
 ];
 
 pub const BUILTINS: &[BuiltinSpec] = &[
     BuiltinSpec {
         name: "upper",
-        arity: 1,
-        func: builtin_upper,
+        signature: "upper(string) -> string",
+        doc: "Uppercases a string.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_upper),
+    },
+    BuiltinSpec {
+        name: "lower",
+        signature: "lower(string) -> string",
+        doc: "Lowercases a string.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_lower),
+    },
+    BuiltinSpec {
+        name: "trim",
+        signature: "trim(string) -> string",
+        doc: "Strips leading and trailing whitespace.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_trim),
+    },
+    BuiltinSpec {
+        name: "lstrip",
+        signature: "lstrip(string) -> string",
+        doc: "Strips leading whitespace.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_lstrip),
+    },
+    BuiltinSpec {
+        name: "rstrip",
+        signature: "rstrip(string) -> string",
+        doc: "Strips trailing whitespace.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_rstrip),
+    },
+    BuiltinSpec {
+        name: "split",
+        signature: "split(string, sep: string) -> [string]",
+        doc: "Splits a string on every occurrence of `sep`.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_split),
+    },
+    BuiltinSpec {
+        name: "join",
+        signature: "join([string], sep: string) -> string",
+        doc: "Joins strings with `sep` between them.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_join),
+    },
+    BuiltinSpec {
+        name: "replace",
+        signature: "replace(string, from: string, to: string) -> string",
+        doc: "Replaces every occurrence of `from` with `to`.",
+        arity: Arity::Exact(3),
+        func: BuiltinImpl::Pure(builtin_replace),
+    },
+    BuiltinSpec {
+        name: "contains",
+        signature: "contains(string|list|tuple, any) -> bool",
+        doc: "Tests for a substring or a member element.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_contains),
+    },
+    BuiltinSpec {
+        name: "starts_with",
+        signature: "starts_with(string, prefix: string) -> bool",
+        doc: "Tests whether a string starts with `prefix`.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_starts_with),
+    },
+    BuiltinSpec {
+        name: "ends_with",
+        signature: "ends_with(string, suffix: string) -> bool",
+        doc: "Tests whether a string ends with `suffix`.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_ends_with),
+    },
+    BuiltinSpec {
+        name: "substr",
+        signature: "substr(text: string, start: int, len?: int) -> string",
+        doc: "Slices characters from `start`, to the end or for `len` characters.",
+        arity: Arity::Range(2, 3),
+        func: BuiltinImpl::Pure(builtin_substr),
+    },
+    BuiltinSpec {
+        name: "char_at",
+        signature: "char_at(text: string, index: int) -> string",
+        doc: "Returns the character at `index`.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_char_at),
+    },
+    BuiltinSpec {
+        name: "re_match",
+        signature: "re_match(pattern: string, text: string) -> list | nil",
+        doc: "Returns the capture groups of the first match, or nil.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Host(builtin_re_match),
+    },
+    BuiltinSpec {
+        name: "re_find_all",
+        signature: "re_find_all(pattern: string, text: string) -> list",
+        doc: "Returns every match of `pattern` in `text`.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Host(builtin_re_find_all),
+    },
+    BuiltinSpec {
+        name: "re_replace",
+        signature: "re_replace(pattern: string, text: string, replacement: string) -> string",
+        doc: "Replaces every match of `pattern`; `$1` refers to capture groups.",
+        arity: Arity::Exact(3),
+        func: BuiltinImpl::Host(builtin_re_replace),
+    },
+    BuiltinSpec {
+        name: "str",
+        signature: "str(any) -> string",
+        doc: "Converts a value to its display string.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_str),
+    },
+    BuiltinSpec {
+        name: "format",
+        signature: "format(template: string, any...) -> string",
+        doc: "Fills each `{}` in the template with the next argument.",
+        arity: Arity::AtLeast(1),
+        func: BuiltinImpl::Pure(builtin_format),
+    },
+    BuiltinSpec {
+        name: "parse_int",
+        signature: "parse_int(string) -> int?",
+        doc: "Parses an integer, or returns nil.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_parse_int),
+    },
+    BuiltinSpec {
+        name: "parse_float",
+        signature: "parse_float(string) -> float?",
+        doc: "Parses a float, or returns nil.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_parse_float),
+    },
+    BuiltinSpec {
+        name: "abs",
+        signature: "abs(int|float) -> int|float",
+        doc: "Absolute value.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_abs),
+    },
+    BuiltinSpec {
+        name: "round",
+        signature: "round(int|float, digits?: int) -> int|float",
+        doc: "Rounds to an int, or to `digits` decimal places as a float.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_round),
+    },
+    BuiltinSpec {
+        name: "floor",
+        signature: "floor(int|float) -> int",
+        doc: "Rounds down to an int.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_floor),
+    },
+    BuiltinSpec {
+        name: "ceil",
+        signature: "ceil(int|float) -> int",
+        doc: "Rounds up to an int.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_ceil),
+    },
+    BuiltinSpec {
+        name: "sqrt",
+        signature: "sqrt(int|float) -> float",
+        doc: "Square root.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_sqrt),
+    },
+    BuiltinSpec {
+        name: "pow",
+        signature: "pow(int|float, int|float) -> int|float",
+        doc: "Raises the first argument to the power of the second.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_pow),
+    },
+    BuiltinSpec {
+        name: "exp",
+        signature: "exp(int|float) -> float",
+        doc: "e raised to the given power.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_exp),
+    },
+    BuiltinSpec {
+        name: "log",
+        signature: "log(int|float, base?: int|float) -> float",
+        doc: "Natural logarithm, or the logarithm in `base`.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_log),
+    },
+    BuiltinSpec {
+        name: "sin",
+        signature: "sin(int|float) -> float",
+        doc: "Sine of an angle in radians.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_sin),
+    },
+    BuiltinSpec {
+        name: "cos",
+        signature: "cos(int|float) -> float",
+        doc: "Cosine of an angle in radians.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_cos),
+    },
+    BuiltinSpec {
+        name: "tan",
+        signature: "tan(int|float) -> float",
+        doc: "Tangent of an angle in radians.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_tan),
+    },
+    BuiltinSpec {
+        name: "pi",
+        signature: "pi() -> float",
+        doc: "The constant pi.",
+        arity: Arity::Exact(0),
+        func: BuiltinImpl::Pure(builtin_pi),
+    },
+    BuiltinSpec {
+        name: "random",
+        signature: "random() -> float",
+        doc: "Uniform random float in [0, 1); reproducible with `--seed`.",
+        arity: Arity::Exact(0),
+        func: BuiltinImpl::Host(builtin_random),
+    },
+    BuiltinSpec {
+        name: "rand_int",
+        signature: "rand_int(lo: int, hi: int) -> int",
+        doc: "Uniform random int between `lo` and `hi` inclusive.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Host(builtin_rand_int),
+    },
+    BuiltinSpec {
+        name: "shuffle",
+        signature: "shuffle(list) -> list",
+        doc: "Returns a shuffled copy of a list.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Host(builtin_shuffle),
+    },
+    BuiltinSpec {
+        name: "uuid",
+        signature: "uuid() -> string",
+        doc: "Random version 4 UUID.",
+        arity: Arity::Exact(0),
+        func: BuiltinImpl::Host(builtin_uuid),
+    },
+    BuiltinSpec {
+        name: "sha256",
+        signature: "sha256(string) -> string",
+        doc: "Hex SHA-256 digest.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_sha256),
+    },
+    BuiltinSpec {
+        name: "md5",
+        signature: "md5(string) -> string",
+        doc: "Hex MD5 digest.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_md5),
+    },
+    BuiltinSpec {
+        name: "http_get",
+        signature: "http_get(url: string, headers?: object|[string]) -> object",
+        doc: "GET request returning `{status, body}`; needs `--allow-net`.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Host(builtin_http_get),
+    },
+    BuiltinSpec {
+        name: "http_post",
+        signature: "http_post(url: string, body: any, headers?: object|[string]) -> object",
+        doc: "POST request returning `{status, body}`; non-string bodies are sent as JSON; needs `--allow-net`.",
+        arity: Arity::Range(2, 3),
+        func: BuiltinImpl::Host(builtin_http_post),
+    },
+    BuiltinSpec {
+        name: "exec",
+        signature: "exec(cmd: string, args?: [string]) -> object",
+        doc: "Runs a process and returns `{status, stdout, stderr}`; needs `--allow-exec`.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Host(builtin_exec),
+    },
+    BuiltinSpec {
+        name: "sort",
+        signature: "sort(list) -> list",
+        doc: "Returns a sorted copy of a list.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_sort),
+    },
+    BuiltinSpec {
+        name: "sort_by",
+        signature: "sort_by(list, function) -> list",
+        doc: "Sorts a list by the key the function returns.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Host(builtin_sort_by),
+    },
+    BuiltinSpec {
+        name: "map",
+        signature: "map(list, function) -> list",
+        doc: "Applies the function to every element.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Host(builtin_map),
+    },
+    BuiltinSpec {
+        name: "filter",
+        signature: "filter(list, function) | filter(df: dataframe|lazyframe, column: string, op: string, value: any) -> list|dataframe|lazyframe",
+        doc: "Keeps list elements the function accepts, or dataframe rows where `column op value` holds.",
+        arity: Arity::Range(2, 4),
+        func: BuiltinImpl::Host(builtin_filter),
+    },
+    BuiltinSpec {
+        name: "reduce",
+        signature: "reduce(list, function, init) -> any",
+        doc: "Folds the list into one value, starting from `init`.",
+        arity: Arity::Exact(3),
+        func: BuiltinImpl::Host(builtin_reduce),
+    },
+    BuiltinSpec {
+        name: "zip",
+        signature: "zip(list|tuple, list|tuple) -> [(any, any)]",
+        doc: "Pairs up elements, stopping at the shorter input.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_zip),
+    },
+    BuiltinSpec {
+        name: "enumerate",
+        signature: "enumerate(list|tuple) -> [(int, any)]",
+        doc: "Pairs each element with its index.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_enumerate),
+    },
+    BuiltinSpec {
+        name: "range",
+        signature: "range(start?: int, end: int, step?: int) -> [int]",
+        doc: "Integers from `start` (default 0) up to, not including, `end`.",
+        arity: Arity::Range(1, 3),
+        func: BuiltinImpl::Pure(builtin_range),
+    },
+    BuiltinSpec {
+        name: "unique",
+        signature: "unique(list) -> list",
+        doc: "Drops repeated elements, keeping first occurrences.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_unique),
+    },
+    BuiltinSpec {
+        name: "reverse",
+        signature: "reverse(list|tuple|string) -> list|tuple|string",
+        doc: "Reverses the order of elements or characters.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_reverse),
+    },
+    BuiltinSpec {
+        name: "last",
+        signature: "last(list|tuple) -> any",
+        doc: "Returns the last element.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_last),
+    },
+    BuiltinSpec {
+        name: "keys",
+        signature: "keys(object) -> [string]",
+        doc: "Keys of an object, sorted.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_keys),
+    },
+    BuiltinSpec {
+        name: "values",
+        signature: "values(object) -> list",
+        doc: "Values of an object, in key order.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_values),
+    },
+    BuiltinSpec {
+        name: "items",
+        signature: "items(object) -> [(string, any)]",
+        doc: "Key/value pairs of an object, in key order.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_items),
+    },
+    BuiltinSpec {
+        name: "get",
+        signature: "get(object, key: string, default?) -> any",
+        doc: "Looks up `key`, returning `default` (or nil) when it is missing.",
+        arity: Arity::Range(2, 3),
+        func: BuiltinImpl::Pure(builtin_get),
+    },
+    BuiltinSpec {
+        name: "has",
+        signature: "has(object, key: string) -> bool",
+        doc: "Tests whether an object has `key`.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_has),
+    },
+    BuiltinSpec {
+        name: "merge",
+        signature: "merge(object, object) -> object",
+        doc: "Combines two objects; keys in the second win.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_merge),
+    },
+    BuiltinSpec {
+        name: "copy",
+        signature: "copy(any) -> any",
+        doc: "Deep copy of a value.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_copy),
+    },
+    BuiltinSpec {
+        name: "json_parse",
+        signature: "json_parse(string) -> any",
+        doc: "Parses JSON text into a value.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Host(builtin_json_parse),
+    },
+    BuiltinSpec {
+        name: "json_stringify",
+        signature: "json_stringify(any, pretty?: bool) -> string",
+        doc: "Serializes a value as JSON.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Host(builtin_json_stringify),
     },
     BuiltinSpec {
         name: "print",
-        arity: 1,
-        func: builtin_print,
+        signature: "print(...any) -> nil",
+        doc: "Writes the arguments to stdout, separated by spaces.",
+        arity: Arity::AtLeast(0),
+        func: BuiltinImpl::Pure(builtin_print),
+    },
+    BuiltinSpec {
+        name: "eprint",
+        signature: "eprint(...any) -> nil",
+        doc: "Writes the arguments to stderr, separated by spaces.",
+        arity: Arity::AtLeast(0),
+        func: BuiltinImpl::Pure(builtin_eprint),
+    },
+    BuiltinSpec {
+        name: "pprint",
+        signature: "pprint(any) -> nil",
+        doc: "Pretty-prints a value; dataframes print as tables.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_pprint),
+    },
+    BuiltinSpec {
+        name: "error",
+        signature: "error(message: any) -> nil",
+        doc: "Stops the script with an error.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_error),
+    },
+    BuiltinSpec {
+        name: "input",
+        signature: "input(prompt?: string) -> string | nil",
+        doc: "Reads one line from stdin; nil at end of input.",
+        arity: Arity::Range(0, 1),
+        func: BuiltinImpl::Pure(builtin_input),
     },
     BuiltinSpec {
         name: "len",
-        arity: 1,
-        func: builtin_len,
+        signature: "len(string|list|tuple|object|dataframe|vstore) -> int",
+        doc: "Number of characters, elements, keys, rows, or entries.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_len),
     },
     BuiltinSpec {
         name: "type",
-        arity: 1,
-        func: builtin_type,
+        signature: "type(any) -> string",
+        doc: "Name of a value's type.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_type),
+    },
+    BuiltinSpec {
+        name: "is_int",
+        signature: "is_int(any) -> bool",
+        doc: "Tests whether a value is an int.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_int),
+    },
+    BuiltinSpec {
+        name: "is_float",
+        signature: "is_float(any) -> bool",
+        doc: "Tests whether a value is a float.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_float),
+    },
+    BuiltinSpec {
+        name: "is_number",
+        signature: "is_number(any) -> bool",
+        doc: "Tests whether a value is an int or a float.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_number),
+    },
+    BuiltinSpec {
+        name: "is_bool",
+        signature: "is_bool(any) -> bool",
+        doc: "Tests whether a value is a bool.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_bool),
+    },
+    BuiltinSpec {
+        name: "is_string",
+        signature: "is_string(any) -> bool",
+        doc: "Tests whether a value is a string.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_string),
+    },
+    BuiltinSpec {
+        name: "is_list",
+        signature: "is_list(any) -> bool",
+        doc: "Tests whether a value is a list.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_list),
+    },
+    BuiltinSpec {
+        name: "is_tuple",
+        signature: "is_tuple(any) -> bool",
+        doc: "Tests whether a value is a tuple.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_tuple),
+    },
+    BuiltinSpec {
+        name: "is_object",
+        signature: "is_object(any) -> bool",
+        doc: "Tests whether a value is an object.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_object),
+    },
+    BuiltinSpec {
+        name: "is_dataframe",
+        signature: "is_dataframe(any) -> bool",
+        doc: "Tests whether a value is a dataframe.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_dataframe),
+    },
+    BuiltinSpec {
+        name: "is_lazyframe",
+        signature: "is_lazyframe(any) -> bool",
+        doc: "Tests whether a value is a lazyframe.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_lazyframe),
+    },
+    BuiltinSpec {
+        name: "is_vstore",
+        signature: "is_vstore(any) -> bool",
+        doc: "Tests whether a value is a vector store.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_vstore),
+    },
+    BuiltinSpec {
+        name: "is_image",
+        signature: "is_image(any) -> bool",
+        doc: "Tests whether a value is an image.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_image),
+    },
+    BuiltinSpec {
+        name: "is_function",
+        signature: "is_function(any) -> bool",
+        doc: "Tests whether a value is a function.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_function),
+    },
+    BuiltinSpec {
+        name: "is_nil",
+        signature: "is_nil(any) -> bool",
+        doc: "Tests whether a value is nil.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_is_nil),
     },
     BuiltinSpec {
         name: "read",
-        arity: 1,
-        func: builtin_read,
+        signature: "read(path: string, options?: object) -> dataframe",
+        doc: "Reads a CSV file or URL; options are `sep`, `header`, `skip_rows`, and `dtypes`.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Host(builtin_read),
+    },
+    BuiltinSpec {
+        name: "write_csv",
+        signature: "write_csv(df: dataframe, path: string, options?: object) -> nil",
+        doc: "Writes a dataframe as CSV; options are `header` and `sep`.",
+        arity: Arity::Range(2, 3),
+        func: BuiltinImpl::Pure(builtin_write_csv),
+    },
+    BuiltinSpec {
+        name: "read_parquet",
+        signature: "read_parquet(path: string) -> dataframe",
+        doc: "Reads a Parquet file.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_read_parquet),
+    },
+    BuiltinSpec {
+        name: "write_parquet",
+        signature: "write_parquet(df: dataframe, path: string) -> nil",
+        doc: "Writes a dataframe as Parquet.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_write_parquet),
+    },
+    BuiltinSpec {
+        name: "read_excel",
+        signature: "read_excel(path: string, sheet?: string|int) -> dataframe",
+        doc: "Reads one `.xlsx` worksheet; the first row is the header.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_read_excel),
+    },
+    BuiltinSpec {
+        name: "scan",
+        signature: "scan(path: string) -> lazyframe",
+        doc: "Lazily reads a CSV file, only when the plan is collected.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_scan),
+    },
+    BuiltinSpec {
+        name: "lazy",
+        signature: "lazy(df: dataframe) -> lazyframe",
+        doc: "Starts a lazy query plan from a dataframe.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_lazy),
+    },
+    BuiltinSpec {
+        name: "collect",
+        signature: "collect(lf: lazyframe) -> dataframe",
+        doc: "Runs a lazy query plan.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_collect),
+    },
+    BuiltinSpec {
+        name: "sql",
+        signature: "sql(query: string, tables: object) -> dataframe",
+        doc: "Runs a SQL query over the dataframes or lazyframes named by `tables`.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_sql),
+    },
+    BuiltinSpec {
+        name: "read_text",
+        signature: "read_text(path: string) -> string",
+        doc: "Reads a whole file as text.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_read_text),
+    },
+    BuiltinSpec {
+        name: "write_text",
+        signature: "write_text(path: string, text: string) -> nil",
+        doc: "Writes text to a file, replacing it.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_write_text),
+    },
+    BuiltinSpec {
+        name: "read_lines",
+        signature: "read_lines(path: string) -> [string]",
+        doc: "Reads a file as a list of lines.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_read_lines),
+    },
+    BuiltinSpec {
+        name: "append_file",
+        signature: "append_file(path: string, text: string) -> nil",
+        doc: "Appends text to a file, creating it if needed.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_append_file),
+    },
+    BuiltinSpec {
+        name: "exists",
+        signature: "exists(path: string) -> bool",
+        doc: "Tests whether a path exists.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_exists),
+    },
+    BuiltinSpec {
+        name: "file_size",
+        signature: "file_size(path: string) -> int",
+        doc: "Size of a file in bytes.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_file_size),
+    },
+    BuiltinSpec {
+        name: "list_dir",
+        signature: "list_dir(path: string) -> [string]",
+        doc: "Sorted names of the entries in a directory.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_list_dir),
+    },
+    BuiltinSpec {
+        name: "args",
+        signature: "args() -> [string]",
+        doc: "Arguments given after `--` on `orangensaft run`.",
+        arity: Arity::Exact(0),
+        func: BuiltinImpl::Host(builtin_args),
+    },
+    BuiltinSpec {
+        name: "now",
+        signature: "now() -> object",
+        doc: "Current UTC time as a date object.",
+        arity: Arity::Exact(0),
+        func: BuiltinImpl::Pure(builtin_now),
+    },
+    BuiltinSpec {
+        name: "format_date",
+        signature: "format_date(date: object|int, format?: string) -> string",
+        doc: "Formats a date object or Unix timestamp with a strftime format.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_format_date),
+    },
+    BuiltinSpec {
+        name: "parse_date",
+        signature: "parse_date(text: string, format?: string) -> object | nil",
+        doc: "Parses a date, or returns nil when it does not match.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_parse_date),
+    },
+    BuiltinSpec {
+        name: "sleep",
+        signature: "sleep(seconds: int|float) -> nil",
+        doc: "Pauses the script.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Host(builtin_sleep),
     },
     BuiltinSpec {
         name: "shape",
-        arity: 1,
-        func: builtin_shape,
+        signature: "shape(df: dataframe) -> (int, int)",
+        doc: "Row and column counts.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_shape),
     },
     BuiltinSpec {
         name: "columns",
-        arity: 1,
-        func: builtin_columns,
+        signature: "columns(df: dataframe) -> [string]",
+        doc: "Column names in order.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_columns),
     },
     BuiltinSpec {
         name: "head",
-        arity: 1,
-        func: builtin_head,
+        signature: "head(df: dataframe|lazyframe) -> [object]",
+        doc: "First 5 rows as objects.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_head),
+    },
+    BuiltinSpec {
+        name: "tail",
+        signature: "tail(df: dataframe, n?: int) -> [object]",
+        doc: "Last `n` rows (default 5) as objects.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_tail),
+    },
+    BuiltinSpec {
+        name: "sample",
+        signature: "sample(df: dataframe, n: int, seed?: int) -> [object]",
+        doc: "`n` random rows in their original order, as objects.",
+        arity: Arity::Range(2, 3),
+        func: BuiltinImpl::Host(builtin_sample),
+    },
+    BuiltinSpec {
+        name: "to_records",
+        signature: "to_records(df: dataframe) -> [object]",
+        doc: "Every row as an object.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_to_records),
     },
     BuiltinSpec {
         name: "select",
-        arity: 2,
-        func: builtin_select,
+        signature: "select(df: dataframe|lazyframe, cols: [string]) -> dataframe|lazyframe",
+        doc: "Keeps the named columns.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_select),
+    },
+    BuiltinSpec {
+        name: "group_by",
+        signature: "group_by(df: dataframe|lazyframe, keys: [string], aggs: object) -> dataframe|lazyframe",
+        doc: "Groups rows by `keys` and aggregates columns, e.g. `{temp: \"mean\"}`.",
+        arity: Arity::Exact(3),
+        func: BuiltinImpl::Pure(builtin_group_by),
+    },
+    BuiltinSpec {
+        name: "sort_df",
+        signature: "sort_df(df: dataframe, by: string|[string], descending?: bool) -> dataframe",
+        doc: "Sorts rows by one or more columns.",
+        arity: Arity::Range(2, 3),
+        func: BuiltinImpl::Pure(builtin_sort_df),
+    },
+    BuiltinSpec {
+        name: "with_column",
+        signature: "with_column(df: dataframe, name: string, source: function|list) -> dataframe",
+        doc: "Adds or replaces a column from a row function or a list of values.",
+        arity: Arity::Exact(3),
+        func: BuiltinImpl::Host(builtin_with_column),
+    },
+    BuiltinSpec {
+        name: "drop",
+        signature: "drop(df: dataframe, cols: [string]) -> dataframe",
+        doc: "Removes the named columns.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_drop),
+    },
+    BuiltinSpec {
+        name: "rename",
+        signature: "rename(df: dataframe, mapping: object) -> dataframe",
+        doc: "Renames columns with `{old: \"new\"}`.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_rename),
+    },
+    BuiltinSpec {
+        name: "distinct",
+        signature: "distinct(df: dataframe) -> dataframe",
+        doc: "Drops duplicate rows.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_distinct),
+    },
+    BuiltinSpec {
+        name: "distinct_on",
+        signature: "distinct_on(df: dataframe, cols: [string]) -> dataframe",
+        doc: "Keeps the first row for each combination of `cols`.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_distinct_on),
+    },
+    BuiltinSpec {
+        name: "describe",
+        signature: "describe(df: dataframe) -> dataframe",
+        doc: "Summary statistics for every column.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_describe),
+    },
+    BuiltinSpec {
+        name: "null_counts",
+        signature: "null_counts(df: dataframe) -> object",
+        doc: "Number of nulls in each column.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_null_counts),
+    },
+    BuiltinSpec {
+        name: "drop_nulls",
+        signature: "drop_nulls(df: dataframe, cols?: [string]) -> dataframe",
+        doc: "Drops rows with a null in any of `cols` (default every column).",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_drop_nulls),
+    },
+    BuiltinSpec {
+        name: "fill_null",
+        signature: "fill_null(df: dataframe, value: any, cols?: [string]) -> dataframe",
+        doc: "Replaces nulls with `value`.",
+        arity: Arity::Range(2, 3),
+        func: BuiltinImpl::Pure(builtin_fill_null),
+    },
+    BuiltinSpec {
+        name: "concat",
+        signature: "concat(frames: [dataframe], how?: string) -> dataframe",
+        doc: "Stacks dataframes `\"vertical\"`ly (default) or side by side with `\"horizontal\"`.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_concat),
     },
     BuiltinSpec {
         name: "mean",
-        arity: 2,
-        func: builtin_mean,
+        signature: "mean(df: dataframe, column: string) | mean([int|float]) -> float",
+        doc: "Arithmetic mean of a column or list.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_mean),
     },
     BuiltinSpec {
         name: "sum",
-        arity: 2,
-        func: builtin_sum,
+        signature: "sum(df: dataframe, column: string) | sum([int|float]) -> int|float",
+        doc: "Sum of a column or list.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_sum),
     },
     BuiltinSpec {
         name: "min",
-        arity: 2,
-        func: builtin_min,
+        signature: "min(df: dataframe, column: string) | min([int|float]) -> int|float",
+        doc: "Smallest value of a column or list.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_min),
     },
     BuiltinSpec {
         name: "max",
-        arity: 2,
-        func: builtin_max,
+        signature: "max(df: dataframe, column: string) | max([int|float]) -> int|float",
+        doc: "Largest value of a column or list.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_max),
     },
-];
-
-fn take_one_arg(args: Vec<Value>, name: &str) -> SaftResult<Value> {
-    if args.len() != 1 {
-        return Err(SaftError::new(format!("{name} expects one argument")));
-    }
-    Ok(args
-        .into_iter()
-        .next()
-        .expect("len check above guarantees one argument"))
-}
-
+    BuiltinSpec {
+        name: "median",
+        signature: "median(df: dataframe, column: string) | median([int|float]) -> float",
+        doc: "Median of a column or list.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_median),
+    },
+    BuiltinSpec {
+        name: "std",
+        signature: "std(df: dataframe, column: string) | std([int|float]) -> float",
+        doc: "Sample standard deviation of a column or list.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_std),
+    },
+    BuiltinSpec {
+        name: "quantile",
+        signature: "quantile(df: dataframe, column: string, q: float) | quantile([int|float], q: float) -> float",
+        doc: "Linearly interpolated quantile `q` of a column or list.",
+        arity: Arity::Range(2, 3),
+        func: BuiltinImpl::Pure(builtin_quantile),
+    },
+    BuiltinSpec {
+        name: "count",
+        signature: "count(df: dataframe, column?: string) -> int",
+        doc: "Row count, or non-null cells in `column`.",
+        arity: Arity::Range(1, 2),
+        func: BuiltinImpl::Pure(builtin_count),
+    },
+    BuiltinSpec {
+        name: "nunique",
+        signature: "nunique(df: dataframe, column: string) -> int",
+        doc: "Number of distinct values in a column.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_nunique),
+    },
+    BuiltinSpec {
+        name: "value_counts",
+        signature: "value_counts(df: dataframe, column: string) -> dataframe",
+        doc: "Occurrences of each value in a column, most frequent first.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_value_counts),
+    },
+    BuiltinSpec {
+        name: "embed",
+        signature: "embed(text: string) -> [float]",
+        doc: "Embeds text with the configured embedding provider.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Host(builtin_embed),
+    },
+    BuiltinSpec {
+        name: "cosine",
+        signature: "cosine(a: [float], b: [float]) -> float",
+        doc: "Cosine similarity of two vectors.",
+        arity: Arity::Exact(2),
+        func: BuiltinImpl::Pure(builtin_cosine),
+    },
+    BuiltinSpec {
+        name: "vstore",
+        signature: "vstore() -> vstore",
+        doc: "New in-memory vector store.",
+        arity: Arity::Exact(0),
+        func: BuiltinImpl::Pure(builtin_vstore),
+    },
+    BuiltinSpec {
+        name: "vstore_add",
+        signature: "vstore_add(store: vstore, id: string, vec: [float], meta: any) -> nil",
+        doc: "Adds an entry, replacing an existing `id`.",
+        arity: Arity::Exact(4),
+        func: BuiltinImpl::Pure(builtin_vstore_add),
+    },
+    BuiltinSpec {
+        name: "vstore_query",
+        signature: "vstore_query(store: vstore, vec: [float], k: int) -> [object]",
+        doc: "Top `k` entries by cosine similarity, best first.",
+        arity: Arity::Exact(3),
+        func: BuiltinImpl::Pure(builtin_vstore_query),
+    },
+    BuiltinSpec {
+        name: "image",
+        signature: "image(path: string) -> image",
+        doc: "Loads an image to attach by interpolating it into a prompt.",
+        arity: Arity::Exact(1),
+        func: BuiltinImpl::Pure(builtin_image),
+    },
+];
+
+fn take_one_arg(args: Vec<Value>, name: &str) -> SaftResult<Value> {
+    if args.len() != 1 {
+        return Err(SaftError::new(format!("{name} expects one argument")));
+    }
+    Ok(args
+        .into_iter()
+        .next()
+        .expect("len check above guarantees one argument"))
+}
+
 fn take_two_args(args: Vec<Value>, name: &str) -> SaftResult<(Value, Value)> {
     if args.len() != 2 {
         return Err(SaftError::new(format!("{name} expects two arguments")));
@@ -114,6 +1243,43 @@ fn take_two_args(args: Vec<Value>, name: &str) -> SaftResult<(Value, Value)> {
     Ok((first, second))
 }
 
+fn take_three_args(args: Vec<Value>, name: &str) -> SaftResult<(Value, Value, Value)> {
+    if args.len() != 3 {
+        return Err(SaftError::new(format!("{name} expects three arguments")));
+    }
+    let mut iter = args.into_iter();
+    let first = iter
+        .next()
+        .expect("len check above guarantees three arguments");
+    let second = iter
+        .next()
+        .expect("len check above guarantees three arguments");
+    let third = iter
+        .next()
+        .expect("len check above guarantees three arguments");
+    Ok((first, second, third))
+}
+
+fn take_four_args(args: Vec<Value>, name: &str) -> SaftResult<(Value, Value, Value, Value)> {
+    if args.len() != 4 {
+        return Err(SaftError::new(format!("{name} expects four arguments")));
+    }
+    let mut iter = args.into_iter();
+    let first = iter
+        .next()
+        .expect("len check above guarantees four arguments");
+    let second = iter
+        .next()
+        .expect("len check above guarantees four arguments");
+    let third = iter
+        .next()
+        .expect("len check above guarantees four arguments");
+    let fourth = iter
+        .next()
+        .expect("len check above guarantees four arguments");
+    Ok((first, second, third, fourth))
+}
+
 fn expect_dataframe(value: Value, name: &str) -> SaftResult<DataFrameValue> {
     match value {
         Value::DataFrame(df) => Ok(df),
@@ -134,6 +1300,107 @@ fn expect_string(value: Value, name: &str) -> SaftResult<String> {
     }
 }
 
+fn expect_vector_store(value: Value, name: &str) -> SaftResult<VectorStoreValue> {
+    match value {
+        Value::VectorStore(store) => Ok(store),
+        other => Err(SaftError::new(format!(
+            "{name} expects vstore, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn expect_number_list(value: Value, name: &str) -> SaftResult<Vec<f64>> {
+    match value {
+        Value::List(items) => {
+            let mut out = Vec::with_capacity(items.len());
+            for item in items {
+                match item {
+                    Value::Int(v) => out.push(v as f64),
+                    Value::Float(v) => out.push(v),
+                    other => {
+                        return Err(SaftError::new(format!(
+                            "{name} expects [int|float], got list containing {}",
+                            other.type_name()
+                        )));
+                    }
+                }
+            }
+            Ok(out)
+        }
+        other => Err(SaftError::new(format!(
+            "{name} expects [int|float], got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn expect_number(value: Value, name: &str) -> SaftResult<f64> {
+    match value {
+        Value::Int(v) => Ok(v as f64),
+        Value::Float(v) => Ok(v),
+        other => Err(SaftError::new(format!(
+            "{name} expects int|float, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn expect_list(value: Value, name: &str) -> SaftResult<Vec<Value>> {
+    match value {
+        Value::List(items) => Ok(items),
+        other => Err(SaftError::new(format!(
+            "{name} expects list, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn expect_sequence(value: Value, name: &str) -> SaftResult<Vec<Value>> {
+    match value {
+        Value::List(items) | Value::Tuple(items) => Ok(items),
+        other => Err(SaftError::new(format!(
+            "{name} expects list or tuple, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn expect_object(value: Value, name: &str) -> SaftResult<BTreeMap<String, Value>> {
+    match value {
+        Value::Object(map) => Ok(map),
+        other => Err(SaftError::new(format!(
+            "{name} expects object, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn expect_function(value: Value, name: &str) -> SaftResult<FunctionId> {
+    match value {
+        Value::Function(id) => Ok(id),
+        other => Err(SaftError::new(format!(
+            "{name} expects function, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+/// Like `expect_number_list` but keeps the original int/float values.
+fn expect_numeric_items(value: Value, name: &str) -> SaftResult<Vec<Value>> {
+    let items = expect_list(value, name)?;
+    if let Some(other) = items
+        .iter()
+        .find(|item| !matches!(item, Value::Int(_) | Value::Float(_)))
+    {
+        return Err(SaftError::new(format!(
+            "{name} expects [int|float], got list containing {}",
+            other.type_name()
+        )));
+    }
+    Ok(items)
+}
+
 fn expect_string_list(value: Value, name: &str) -> SaftResult<Vec<String>> {
     match value {
         Value::List(items) => {
@@ -143,7 +1410,7 @@ fn expect_string_list(value: Value, name: &str) -> SaftResult<Vec<String>> {
                     Value::String(text) => out.push(text),
                     other => {
                         return Err(SaftError::new(format!(
-                            "{name} expects list[string], got list containing {}",
+                            "{name} expects [string], got list containing {}",
                             other.type_name()
                         )));
                     }
@@ -152,7 +1419,7 @@ fn expect_string_list(value: Value, name: &str) -> SaftResult<Vec<String>> {
             Ok(out)
         }
         other => Err(SaftError::new(format!(
-            "{name} expects list[string], got {}",
+            "{name} expects [string], got {}",
             other.type_name()
         ))),
     }
@@ -169,48 +1436,1841 @@ fn builtin_upper(args: Vec<Value>) -> SaftResult<Value> {
     }
 }
 
-fn builtin_print(args: Vec<Value>) -> SaftResult<Value> {
-    let arg = take_one_arg(args, "print")?;
-    match arg {
-        Value::String(text) => println!("{text}"),
-        other => println!("{other}"),
-    }
+fn builtin_lower(args: Vec<Value>) -> SaftResult<Value> {
+    let arg = take_one_arg(args, "lower")?;
+    match arg {
+        Value::String(value) => Ok(Value::String(value.to_lowercase())),
+        other => Err(SaftError::new(format!(
+            "lower expects string, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn builtin_trim(args: Vec<Value>) -> SaftResult<Value> {
+    let text = expect_string(take_one_arg(args, "trim")?, "trim")?;
+    Ok(Value::String(text.trim().to_string()))
+}
+
+fn builtin_lstrip(args: Vec<Value>) -> SaftResult<Value> {
+    let text = expect_string(take_one_arg(args, "lstrip")?, "lstrip")?;
+    Ok(Value::String(text.trim_start().to_string()))
+}
+
+fn builtin_rstrip(args: Vec<Value>) -> SaftResult<Value> {
+    let text = expect_string(take_one_arg(args, "rstrip")?, "rstrip")?;
+    Ok(Value::String(text.trim_end().to_string()))
+}
+
+fn builtin_split(args: Vec<Value>) -> SaftResult<Value> {
+    let (text, sep) = take_two_args(args, "split")?;
+    let text = expect_string(text, "split")?;
+    let sep = expect_string(sep, "split")?;
+    let parts = if sep.is_empty() {
+        text.chars()
+            .map(|ch| Value::String(ch.to_string()))
+            .collect()
+    } else {
+        text.split(sep.as_str())
+            .map(|part| Value::String(part.to_string()))
+            .collect()
+    };
+    Ok(Value::List(parts))
+}
+
+fn builtin_join(args: Vec<Value>) -> SaftResult<Value> {
+    let (items, sep) = take_two_args(args, "join")?;
+    let items = expect_string_list(items, "join")?;
+    let sep = expect_string(sep, "join")?;
+    Ok(Value::String(items.join(&sep)))
+}
+
+fn builtin_replace(args: Vec<Value>) -> SaftResult<Value> {
+    let (text, from, to) = take_three_args(args, "replace")?;
+    let text = expect_string(text, "replace")?;
+    let from = expect_string(from, "replace")?;
+    let to = expect_string(to, "replace")?;
+    if from.is_empty() {
+        return Err(SaftError::new("replace expects a non-empty search string"));
+    }
+    Ok(Value::String(text.replace(&from, &to)))
+}
+
+fn builtin_contains(args: Vec<Value>) -> SaftResult<Value> {
+    let (haystack, needle) = take_two_args(args, "contains")?;
+    let found = match haystack {
+        Value::String(text) => text.contains(expect_string(needle, "contains")?.as_str()),
+        Value::List(items) | Value::Tuple(items) => items.contains(&needle),
+        other => {
+            return Err(SaftError::new(format!(
+                "contains expects string/list/tuple, got {}",
+                other.type_name()
+            )));
+        }
+    };
+    Ok(Value::Bool(found))
+}
+
+fn builtin_starts_with(args: Vec<Value>) -> SaftResult<Value> {
+    let (text, prefix) = take_two_args(args, "starts_with")?;
+    let text = expect_string(text, "starts_with")?;
+    let prefix = expect_string(prefix, "starts_with")?;
+    Ok(Value::Bool(text.starts_with(&prefix)))
+}
+
+fn builtin_ends_with(args: Vec<Value>) -> SaftResult<Value> {
+    let (text, suffix) = take_two_args(args, "ends_with")?;
+    let text = expect_string(text, "ends_with")?;
+    let suffix = expect_string(suffix, "ends_with")?;
+    Ok(Value::Bool(text.ends_with(&suffix)))
+}
+
+/// Up to `len` characters starting at character `start` (to the end when `len`
+/// is omitted). Ranges past the end are clamped, like list slicing elsewhere.
+fn builtin_substr(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let (Some(text), Some(start)) = (iter.next(), iter.next()) else {
+        return Err(SaftError::new("substr expects a string and a start index"));
+    };
+    let text = expect_string(text, "substr")?;
+    let start = expect_non_negative_int(start, "substr", "start")?;
+    let chars = text.chars().skip(start);
+    let out = match iter.next() {
+        Some(len) => chars
+            .take(expect_non_negative_int(len, "substr", "len")?)
+            .collect(),
+        None => chars.collect(),
+    };
+    Ok(Value::String(out))
+}
+
+fn builtin_char_at(args: Vec<Value>) -> SaftResult<Value> {
+    let (text, index) = take_two_args(args, "char_at")?;
+    let text = expect_string(text, "char_at")?;
+    let index = expect_non_negative_int(index, "char_at", "index")?;
+    text.chars()
+        .nth(index)
+        .map(|ch| Value::String(ch.to_string()))
+        .ok_or_else(|| {
+            SaftError::new(format!(
+                "char_at index {index} out of bounds for string of length {}",
+                text.chars().count()
+            ))
+        })
+}
+
+fn expect_non_negative_int(value: Value, name: &str, what: &str) -> SaftResult<usize> {
+    match value {
+        Value::Int(v) if v >= 0 => Ok(v as usize),
+        Value::Int(v) => Err(SaftError::new(format!(
+            "{name} expects non-negative {what}, got {v}"
+        ))),
+        other => Err(SaftError::new(format!(
+            "{name} expects int {what}, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+/// First match of `pattern` in `text` as `[match, group1, ...]` (unmatched
+/// groups are `nil`), or `nil` when there is no match.
+fn builtin_re_match(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let (pattern, text) = take_two_args(args, "re_match")?;
+    let regex = host.regex(&expect_string(pattern, "re_match")?)?;
+    let text = expect_string(text, "re_match")?;
+    Ok(regex
+        .captures(&text)
+        .map(|captures| {
+            Value::List(
+                captures
+                    .iter()
+                    .map(|group| {
+                        group.map_or(Value::Nil, |group| {
+                            Value::String(group.as_str().to_string())
+                        })
+                    })
+                    .collect(),
+            )
+        })
+        .unwrap_or(Value::Nil))
+}
+
+/// All non-overlapping matches. Without groups each item is the matched text;
+/// with one group it is that group; with several it is a tuple of groups.
+fn builtin_re_find_all(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let (pattern, text) = take_two_args(args, "re_find_all")?;
+    let regex = host.regex(&expect_string(pattern, "re_find_all")?)?;
+    let text = expect_string(text, "re_find_all")?;
+    let group_text = |group: Option<regex::Match<'_>>| {
+        group.map_or(Value::Nil, |group| {
+            Value::String(group.as_str().to_string())
+        })
+    };
+
+    let matches = regex
+        .captures_iter(&text)
+        .map(|captures| match captures.len() {
+            1 => group_text(captures.get(0)),
+            2 => group_text(captures.get(1)),
+            _ => Value::Tuple(captures.iter().skip(1).map(group_text).collect()),
+        })
+        .collect();
+    Ok(Value::List(matches))
+}
+
+/// Replaces every match; `replacement` may reference groups as `$1` or `${name}`.
+fn builtin_re_replace(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let (pattern, text, replacement) = take_three_args(args, "re_replace")?;
+    let regex = host.regex(&expect_string(pattern, "re_replace")?)?;
+    let text = expect_string(text, "re_replace")?;
+    let replacement = expect_string(replacement, "re_replace")?;
+    Ok(Value::String(
+        regex.replace_all(&text, replacement.as_str()).into_owned(),
+    ))
+}
+
+fn builtin_str(args: Vec<Value>) -> SaftResult<Value> {
+    let arg = take_one_arg(args, "str")?;
+    Ok(Value::String(value_to_text(&arg)))
+}
+
+fn builtin_format(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let template = expect_string(
+        iter.next()
+            .ok_or_else(|| SaftError::new("format expects a template string"))?,
+        "format",
+    )?;
+    let values: Vec<Value> = iter.collect();
+
+    let mut out = String::with_capacity(template.len());
+    let mut next_value = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match (ch, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                out.push(ch);
+                chars.next();
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                let value = values.get(next_value).ok_or_else(|| {
+                    SaftError::new(format!(
+                        "format template has more '{{}}' placeholders than the {} value(s) given",
+                        values.len()
+                    ))
+                })?;
+                out.push_str(&value_to_text(value));
+                next_value += 1;
+            }
+            ('{', _) | ('}', _) => {
+                return Err(SaftError::new(format!(
+                    "format template has unmatched '{ch}' (use '{ch}{ch}' for a literal brace)"
+                )));
+            }
+            _ => out.push(ch),
+        }
+    }
+
+    if next_value != values.len() {
+        return Err(SaftError::new(format!(
+            "format got {} value(s) but the template has {next_value} placeholder(s)",
+            values.len()
+        )));
+    }
+    Ok(Value::String(out))
+}
+
+fn builtin_parse_int(args: Vec<Value>) -> SaftResult<Value> {
+    let text = expect_string(take_one_arg(args, "parse_int")?, "parse_int")?;
+    Ok(text
+        .trim()
+        .parse::<i64>()
+        .map(Value::Int)
+        .unwrap_or(Value::Nil))
+}
+
+fn builtin_parse_float(args: Vec<Value>) -> SaftResult<Value> {
+    let text = expect_string(take_one_arg(args, "parse_float")?, "parse_float")?;
+    Ok(text
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .map(Value::Float)
+        .unwrap_or(Value::Nil))
+}
+
+fn builtin_abs(args: Vec<Value>) -> SaftResult<Value> {
+    match take_one_arg(args, "abs")? {
+        Value::Int(value) => value
+            .checked_abs()
+            .map(Value::Int)
+            .ok_or_else(|| SaftError::new("abs overflowed int range")),
+        Value::Float(value) => Ok(Value::Float(value.abs())),
+        other => Err(SaftError::new(format!(
+            "abs expects int|float, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn builtin_round(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let value = iter
+        .next()
+        .ok_or_else(|| SaftError::new("round expects a number"))?;
+    match iter.next() {
+        None => round_to_int(value, "round", f64::round),
+        Some(Value::Int(digits)) => {
+            let number = expect_number(value, "round")?;
+            let scale = 10f64.powi(digits.clamp(-308, 308) as i32);
+            Ok(Value::Float((number * scale).round() / scale))
+        }
+        Some(other) => Err(SaftError::new(format!(
+            "round expects int digits, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn builtin_floor(args: Vec<Value>) -> SaftResult<Value> {
+    round_to_int(take_one_arg(args, "floor")?, "floor", f64::floor)
+}
+
+fn builtin_ceil(args: Vec<Value>) -> SaftResult<Value> {
+    round_to_int(take_one_arg(args, "ceil")?, "ceil", f64::ceil)
+}
+
+fn builtin_sqrt(args: Vec<Value>) -> SaftResult<Value> {
+    let value = expect_number(take_one_arg(args, "sqrt")?, "sqrt")?;
+    if value < 0.0 {
+        return Err(SaftError::new(format!(
+            "sqrt expects a non-negative number, got {value}"
+        )));
+    }
+    Ok(Value::Float(value.sqrt()))
+}
+
+fn builtin_pow(args: Vec<Value>) -> SaftResult<Value> {
+    let (base, exponent) = take_two_args(args, "pow")?;
+    if let (Value::Int(base), Value::Int(exponent)) = (&base, &exponent)
+        && let Ok(exponent) = u32::try_from(*exponent)
+    {
+        return base
+            .checked_pow(exponent)
+            .map(Value::Int)
+            .ok_or_else(|| SaftError::new("pow overflowed int range"));
+    }
+    let base = expect_number(base, "pow")?;
+    let exponent = expect_number(exponent, "pow")?;
+    finite_float(base.powf(exponent), "pow")
+}
+
+fn builtin_exp(args: Vec<Value>) -> SaftResult<Value> {
+    let value = expect_number(take_one_arg(args, "exp")?, "exp")?;
+    finite_float(value.exp(), "exp")
+}
+
+fn builtin_log(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let value = expect_number(
+        iter.next()
+            .ok_or_else(|| SaftError::new("log expects a number"))?,
+        "log",
+    )?;
+    if value <= 0.0 {
+        return Err(SaftError::new(format!(
+            "log expects a positive number, got {value}"
+        )));
+    }
+    match iter.next() {
+        None => Ok(Value::Float(value.ln())),
+        Some(base) => {
+            let base = expect_number(base, "log")?;
+            if base <= 0.0 || base == 1.0 {
+                return Err(SaftError::new(format!(
+                    "log expects a positive base other than 1, got {base}"
+                )));
+            }
+            let result = if base == 10.0 {
+                value.log10()
+            } else if base == 2.0 {
+                value.log2()
+            } else {
+                value.log(base)
+            };
+            Ok(Value::Float(result))
+        }
+    }
+}
+
+fn builtin_sin(args: Vec<Value>) -> SaftResult<Value> {
+    let value = expect_number(take_one_arg(args, "sin")?, "sin")?;
+    Ok(Value::Float(value.sin()))
+}
+
+fn builtin_cos(args: Vec<Value>) -> SaftResult<Value> {
+    let value = expect_number(take_one_arg(args, "cos")?, "cos")?;
+    Ok(Value::Float(value.cos()))
+}
+
+fn builtin_tan(args: Vec<Value>) -> SaftResult<Value> {
+    let value = expect_number(take_one_arg(args, "tan")?, "tan")?;
+    Ok(Value::Float(value.tan()))
+}
+
+fn builtin_pi(args: Vec<Value>) -> SaftResult<Value> {
+    if !args.is_empty() {
+        return Err(SaftError::new("pi expects no arguments"));
+    }
+    Ok(Value::Float(std::f64::consts::PI))
+}
+
+fn builtin_random(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    if !args.is_empty() {
+        return Err(SaftError::new("random expects no arguments"));
+    }
+    Ok(Value::Float(host.rng().next_f64()))
+}
+
+fn builtin_rand_int(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let (lo, hi) = take_two_args(args, "rand_int")?;
+    let (lo, hi) = match (lo, hi) {
+        (Value::Int(lo), Value::Int(hi)) => (lo, hi),
+        (lo, hi) => {
+            return Err(SaftError::new(format!(
+                "rand_int expects (int, int), got ({}, {})",
+                lo.type_name(),
+                hi.type_name()
+            )));
+        }
+    };
+    if lo > hi {
+        return Err(SaftError::new(format!(
+            "rand_int expects lo <= hi, got {lo} > {hi}"
+        )));
+    }
+    let span = (i128::from(hi) - i128::from(lo) + 1) as u128;
+    let offset = host.rng().below(span) as i128;
+    Ok(Value::Int((i128::from(lo) + offset) as i64))
+}
+
+fn builtin_shuffle(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let mut items = expect_list(take_one_arg(args, "shuffle")?, "shuffle")?;
+    let rng = host.rng();
+    for i in (1..items.len()).rev() {
+        let j = rng.below(i as u128 + 1) as usize;
+        items.swap(i, j);
+    }
+    Ok(Value::List(items))
+}
+
+/// Random (version 4) UUID drawn from the script RNG, so `--seed` makes it repeatable.
+fn builtin_uuid(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    if !args.is_empty() {
+        return Err(SaftError::new("uuid expects no arguments"));
+    }
+    let rng = host.rng();
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&rng.next_u64().to_be_bytes());
+    bytes[8..].copy_from_slice(&rng.next_u64().to_be_bytes());
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    Ok(Value::String(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )))
+}
+
+fn builtin_sha256(args: Vec<Value>) -> SaftResult<Value> {
+    let text = expect_string(take_one_arg(args, "sha256")?, "sha256")?;
+    Ok(Value::String(hash::sha256_hex(text.as_bytes())))
+}
+
+fn builtin_md5(args: Vec<Value>) -> SaftResult<Value> {
+    let text = expect_string(take_one_arg(args, "md5")?, "md5")?;
+    Ok(Value::String(hash::md5_hex(text.as_bytes())))
+}
+
+fn builtin_http_get(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    require_capability(host.allow_net(), "http_get", "--allow-net")?;
+    let mut iter = args.into_iter();
+    let url = iter
+        .next()
+        .ok_or_else(|| SaftError::new("http_get expects a url"))?;
+    let url = expect_string(url, "http_get")?;
+    let headers = http_headers(iter.next(), "http_get")?;
+    http_response_value(http::send("GET", &url, &headers, None)?)
+}
+
+/// String bodies are sent as-is; any other value is sent as JSON.
+fn builtin_http_post(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    require_capability(host.allow_net(), "http_post", "--allow-net")?;
+    let mut iter = args.into_iter();
+    let (Some(url), Some(body)) = (iter.next(), iter.next()) else {
+        return Err(SaftError::new("http_post expects a url and a body"));
+    };
+    let url = expect_string(url, "http_post")?;
+    let mut headers = http_headers(iter.next(), "http_post")?;
+    let body = match body {
+        Value::String(text) => text,
+        other => {
+            if !headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            {
+                headers.push(("Content-Type".to_string(), "application/json".to_string()));
+            }
+            host.encode_json(&other)?.to_string()
+        }
+    };
+    http_response_value(http::send("POST", &url, &headers, Some(&body))?)
+}
+
+/// Runs a program directly (no shell) and waits for it. A non-zero exit status
+/// is returned, not raised; `status` is `nil` when the process was killed by a signal.
+fn builtin_exec(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    require_capability(host.allow_exec(), "exec", "--allow-exec")?;
+    let mut iter = args.into_iter();
+    let program = iter
+        .next()
+        .ok_or_else(|| SaftError::new("exec expects a command"))?;
+    let program = expect_string(program, "exec")?;
+    let program_args = match iter.next() {
+        Some(list) => expect_string_list(list, "exec")?,
+        None => Vec::new(),
+    };
+
+    let output = std::process::Command::new(&program)
+        .args(&program_args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|err| SaftError::new(format!("exec could not run '{program}': {err}")))?;
+
+    let mut object = BTreeMap::new();
+    object.insert(
+        "status".to_string(),
+        output
+            .status
+            .code()
+            .map_or(Value::Nil, |code| Value::Int(i64::from(code))),
+    );
+    object.insert(
+        "stdout".to_string(),
+        Value::String(String::from_utf8_lossy(&output.stdout).into_owned()),
+    );
+    object.insert(
+        "stderr".to_string(),
+        Value::String(String::from_utf8_lossy(&output.stderr).into_owned()),
+    );
+    Ok(Value::Object(object))
+}
+
+fn require_capability(allowed: bool, name: &str, flag: &str) -> SaftResult<()> {
+    if allowed {
+        Ok(())
+    } else {
+        Err(SaftError::new(format!(
+            "{name} is disabled; run with {flag} to allow it"
+        )))
+    }
+}
+
+/// Headers come as an object, or as `"Name: value"` strings for names that are
+/// not valid object keys (e.g. `Content-Type`).
+fn http_headers(value: Option<Value>, name: &str) -> SaftResult<Vec<(String, String)>> {
+    match value {
+        None => Ok(Vec::new()),
+        Some(Value::Object(map)) => map
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::String(text) => Ok((key, text)),
+                other => Err(SaftError::new(format!(
+                    "{name} header '{key}' must be a string, got {}",
+                    other.type_name()
+                ))),
+            })
+            .collect(),
+        Some(other) => expect_string_list(other, name)?
+            .into_iter()
+            .map(|line| match line.split_once(':') {
+                Some((key, value)) => Ok((key.trim().to_string(), value.trim().to_string())),
+                None => Err(SaftError::new(format!(
+                    "{name} header '{line}' must look like 'Name: value'"
+                ))),
+            })
+            .collect(),
+    }
+}
+
+fn http_response_value(response: http::HttpResponse) -> SaftResult<Value> {
+    let mut object = BTreeMap::new();
+    object.insert("status".to_string(), Value::Int(response.status));
+    object.insert("body".to_string(), Value::String(response.body));
+    Ok(Value::Object(object))
+}
+
+fn builtin_sort(args: Vec<Value>) -> SaftResult<Value> {
+    let items = expect_list(take_one_arg(args, "sort")?, "sort")?;
+    let mut keyed: Vec<(Value, Value)> =
+        items.into_iter().map(|item| (item.clone(), item)).collect();
+    sort_keyed(&mut keyed, "sort")?;
+    Ok(Value::List(
+        keyed.into_iter().map(|(_, item)| item).collect(),
+    ))
+}
+
+fn builtin_sort_by(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let (items, key_fn) = take_two_args(args, "sort_by")?;
+    let items = expect_list(items, "sort_by")?;
+    let key_fn = expect_function(key_fn, "sort_by")?;
+    let mut keyed = Vec::with_capacity(items.len());
+    for item in items {
+        let key = host.call(key_fn, vec![item.clone()])?;
+        keyed.push((key, item));
+    }
+    sort_keyed(&mut keyed, "sort_by")?;
+    Ok(Value::List(
+        keyed.into_iter().map(|(_, item)| item).collect(),
+    ))
+}
+
+fn builtin_map(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let (items, func) = take_two_args(args, "map")?;
+    let items = expect_list(items, "map")?;
+    let func = expect_function(func, "map")?;
+    let mut out = Vec::with_capacity(items.len());
+    for item in items {
+        out.push(host.call(func, vec![item])?);
+    }
+    Ok(Value::List(out))
+}
+
+fn builtin_filter(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    if args.len() == 4 {
+        return filter_dataframe(args);
+    }
+    let (items, func) = take_two_args(args, "filter")?;
+    let items = expect_list(items, "filter")?;
+    let func = expect_function(func, "filter")?;
+    let mut out = Vec::new();
+    for item in items {
+        if host.call(func, vec![item.clone()])?.is_truthy() {
+            out.push(item);
+        }
+    }
+    Ok(Value::List(out))
+}
+
+/// Operators accepted by `filter(df, column, op, value)`.
+const FILTER_OPS: &[&str] = &["==", "!=", "<", "<=", ">", ">=", "contains", "in"];
+
+/// `filter(df, column, op, value)`: keeps the rows whose `column` cell satisfies
+/// `cell op value`, evaluated by polars (see `filter_predicate`): ints and
+/// floats mix, other kind mismatches are errors, and a null cell only matches
+/// `== nil` / `!= <non-nil>`.
+fn filter_dataframe(args: Vec<Value>) -> SaftResult<Value> {
+    let (df, column, op, value) = take_four_args(args, "filter")?;
+    let column = expect_string(column, "filter")?;
+    let op = expect_string(op, "filter")?;
+    if !FILTER_OPS.contains(&op.as_str()) {
+        return Err(SaftError::new(format!(
+            "filter got unknown operator '{op}' (expected one of {})",
+            FILTER_OPS.join(", ")
+        )));
+    }
+    let predicate = filter_predicate(&column, &op, value)?;
+    if let Value::LazyFrame(lf) = df {
+        return Ok(Value::LazyFrame(LazyFrameValue::new(
+            lf.plan().filter(predicate),
+        )));
+    }
+    let df = expect_dataframe(df, "filter")?;
+    let filtered = df
+        .frame()
+        .clone()
+        .lazy()
+        .filter(predicate)
+        .collect()
+        .map_err(|err| SaftError::new(format!("filter failed: {err}")))?;
+    Ok(Value::DataFrame(DataFrameValue::new(filtered)))
+}
+
+/// `filter_dataframe`'s row test as a polars predicate, shared by dataframes
+/// and lazyframes. Comparisons use polars semantics, so comparing a column
+/// with a value of another kind fails (for a lazyframe, when the plan is
+/// collected) rather than matching nothing.
+fn filter_predicate(column: &str, op: &str, value: Value) -> SaftResult<Expr> {
+    let cell = col(column);
+    let predicate = match (op, value) {
+        ("==", Value::Nil) => cell.is_null(),
+        ("!=", Value::Nil) => cell.is_not_null(),
+        ("contains", Value::String(needle)) => cell.str().contains_literal(lit(needle)),
+        ("contains", other) => {
+            return Err(SaftError::new(format!(
+                "filter 'contains' expects a string, got {}",
+                other.type_name()
+            )));
+        }
+        ("in", Value::List(options) | Value::Tuple(options)) => {
+            cell.is_in(lit(values_to_series(column, &options, "filter")?))
+        }
+        ("in", other) => {
+            return Err(SaftError::new(format!(
+                "filter 'in' expects a list of values, got {}",
+                other.type_name()
+            )));
+        }
+        (op, value) => {
+            let value = match value {
+                Value::Int(v) => lit(v),
+                Value::Float(v) => lit(v),
+                Value::String(v) => lit(v),
+                Value::Bool(v) => lit(v),
+                other => {
+                    return Err(SaftError::new(format!(
+                        "filter '{op}' cannot compare with {}",
+                        other.type_name()
+                    )));
+                }
+            };
+            match op {
+                "==" => cell.eq(value),
+                "!=" => cell.neq_missing(value),
+                "<" => cell.lt(value),
+                "<=" => cell.lt_eq(value),
+                ">" => cell.gt(value),
+                _ => cell.gt_eq(value),
+            }
+        }
+    };
+    Ok(predicate)
+}
+
+fn builtin_reduce(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let (items, func, init) = take_three_args(args, "reduce")?;
+    let items = expect_list(items, "reduce")?;
+    let func = expect_function(func, "reduce")?;
+    let mut acc = init;
+    for item in items {
+        acc = host.call(func, vec![acc, item])?;
+    }
+    Ok(acc)
+}
+
+fn builtin_zip(args: Vec<Value>) -> SaftResult<Value> {
+    let (left, right) = take_two_args(args, "zip")?;
+    let left = expect_sequence(left, "zip")?;
+    let right = expect_sequence(right, "zip")?;
+    Ok(Value::List(
+        left.into_iter()
+            .zip(right)
+            .map(|(a, b)| Value::Tuple(vec![a, b]))
+            .collect(),
+    ))
+}
+
+fn builtin_enumerate(args: Vec<Value>) -> SaftResult<Value> {
+    let items = expect_sequence(take_one_arg(args, "enumerate")?, "enumerate")?;
+    Ok(Value::List(
+        items
+            .into_iter()
+            .enumerate()
+            .map(|(idx, item)| Value::Tuple(vec![Value::Int(idx as i64), item]))
+            .collect(),
+    ))
+}
+
+fn builtin_range(args: Vec<Value>) -> SaftResult<Value> {
+    let mut bounds = Vec::with_capacity(args.len());
+    for arg in args {
+        match arg {
+            Value::Int(value) => bounds.push(value),
+            other => {
+                return Err(SaftError::new(format!(
+                    "range expects int arguments, got {}",
+                    other.type_name()
+                )));
+            }
+        }
+    }
+    let (start, end, step) = match bounds.as_slice() {
+        [end] => (0, *end, 1),
+        [start, end] => (*start, *end, 1),
+        [start, end, step] => (*start, *end, *step),
+        _ => return Err(SaftError::new("range expects 1 to 3 arguments")),
+    };
+    if step == 0 {
+        return Err(SaftError::new("range step must not be zero"));
+    }
+
+    let span = i128::from(end) - i128::from(start);
+    let count = if (span > 0) == (step > 0) && span != 0 {
+        (span.abs() + i128::from(step).abs() - 1) / i128::from(step).abs()
+    } else {
+        0
+    };
+    if count > MAX_RANGE_LEN as i128 {
+        return Err(SaftError::new(format!(
+            "range would produce {count} items (limit is {MAX_RANGE_LEN})"
+        )));
+    }
+    // Items lie between `start` and `end`, but `idx * step` alone can overflow.
+    (0..count)
+        .map(|idx| {
+            i64::try_from(i128::from(start) + idx * i128::from(step))
+                .map(Value::Int)
+                .map_err(|_| SaftError::new("range item does not fit in an int"))
+        })
+        .collect::<SaftResult<Vec<_>>>()
+        .map(Value::List)
+}
+
+fn builtin_unique(args: Vec<Value>) -> SaftResult<Value> {
+    let items = expect_list(take_one_arg(args, "unique")?, "unique")?;
+    let mut out: Vec<Value> = Vec::with_capacity(items.len());
+    for item in items {
+        if !out.contains(&item) {
+            out.push(item);
+        }
+    }
+    Ok(Value::List(out))
+}
+
+fn builtin_reverse(args: Vec<Value>) -> SaftResult<Value> {
+    match take_one_arg(args, "reverse")? {
+        Value::List(mut items) => {
+            items.reverse();
+            Ok(Value::List(items))
+        }
+        Value::Tuple(mut items) => {
+            items.reverse();
+            Ok(Value::Tuple(items))
+        }
+        Value::String(text) => Ok(Value::String(text.chars().rev().collect())),
+        other => Err(SaftError::new(format!(
+            "reverse expects list/tuple/string, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn builtin_last(args: Vec<Value>) -> SaftResult<Value> {
+    let items = expect_sequence(take_one_arg(args, "last")?, "last")?;
+    items
+        .into_iter()
+        .next_back()
+        .ok_or_else(|| SaftError::new("last expects a non-empty list"))
+}
+
+fn builtin_keys(args: Vec<Value>) -> SaftResult<Value> {
+    let map = expect_object(take_one_arg(args, "keys")?, "keys")?;
+    Ok(Value::List(map.into_keys().map(Value::String).collect()))
+}
+
+fn builtin_values(args: Vec<Value>) -> SaftResult<Value> {
+    let map = expect_object(take_one_arg(args, "values")?, "values")?;
+    Ok(Value::List(map.into_values().collect()))
+}
+
+fn builtin_items(args: Vec<Value>) -> SaftResult<Value> {
+    let map = expect_object(take_one_arg(args, "items")?, "items")?;
+    Ok(Value::List(
+        map.into_iter()
+            .map(|(key, value)| Value::Tuple(vec![Value::String(key), value]))
+            .collect(),
+    ))
+}
+
+fn builtin_get(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let (Some(target), Some(key)) = (iter.next(), iter.next()) else {
+        return Err(SaftError::new("get expects an object and a key"));
+    };
+    let default = iter.next().unwrap_or(Value::Nil);
+    let mut map = expect_object(target, "get")?;
+    let key = expect_string(key, "get")?;
+    Ok(map.remove(&key).unwrap_or(default))
+}
+
+fn builtin_has(args: Vec<Value>) -> SaftResult<Value> {
+    let (target, key) = take_two_args(args, "has")?;
+    let map = expect_object(target, "has")?;
+    let key = expect_string(key, "has")?;
+    Ok(Value::Bool(map.contains_key(&key)))
+}
+
+fn builtin_merge(args: Vec<Value>) -> SaftResult<Value> {
+    let (base, overrides) = take_two_args(args, "merge")?;
+    let mut merged = expect_object(base, "merge")?;
+    merged.extend(expect_object(overrides, "merge")?);
+    Ok(Value::Object(merged))
+}
+
+fn builtin_copy(args: Vec<Value>) -> SaftResult<Value> {
+    Ok(deep_copy(&take_one_arg(args, "copy")?))
+}
+
+/// Lists, tuples and objects already copy on assignment; the only shared
+/// mutable value is a vector store, so `copy` gives nested stores their own entries.
+fn deep_copy(value: &Value) -> Value {
+    match value {
+        Value::List(items) => Value::List(items.iter().map(deep_copy).collect()),
+        Value::Tuple(items) => Value::Tuple(items.iter().map(deep_copy).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| (key.clone(), deep_copy(item)))
+                .collect(),
+        ),
+        Value::VectorStore(store) => {
+            let copied = VectorStoreValue::new();
+            for entry in store.entries() {
+                copied.upsert(VectorEntry {
+                    meta: deep_copy(&entry.meta),
+                    ..entry
+                });
+            }
+            Value::VectorStore(copied)
+        }
+        other => other.clone(),
+    }
+}
+
+fn builtin_json_parse(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let text = expect_string(take_one_arg(args, "json_parse")?, "json_parse")?;
+    let json = serde_json::from_str(&text)
+        .map_err(|err| SaftError::new(format!("json_parse failed: {err}")))?;
+    host.decode_json(json)
+}
+
+fn builtin_json_stringify(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let value = iter
+        .next()
+        .ok_or_else(|| SaftError::new("json_stringify expects a value"))?;
+    let pretty = match iter.next() {
+        None => false,
+        Some(Value::Bool(pretty)) => pretty,
+        Some(other) => {
+            return Err(SaftError::new(format!(
+                "json_stringify expects bool for pretty, got {}",
+                other.type_name()
+            )));
+        }
+    };
+    let json = host.encode_json(&value)?;
+    let text = if pretty {
+        serde_json::to_string_pretty(&json)
+    } else {
+        serde_json::to_string(&json)
+    }
+    .map_err(|err| SaftError::new(format!("json_stringify failed: {err}")))?;
+    Ok(Value::String(text))
+}
+
+/// Stable sort on precomputed keys; fails if any two keys are not comparable.
+fn sort_keyed(keyed: &mut [(Value, Value)], name: &str) -> SaftResult<()> {
+    let mut failure = None;
+    keyed.sort_by(|(a, _), (b, _)| {
+        compare_values(a, b).unwrap_or_else(|| {
+            failure.get_or_insert_with(|| {
+                format!(
+                    "{name} cannot compare {} with {}",
+                    a.type_name(),
+                    b.type_name()
+                )
+            });
+            Ordering::Equal
+        })
+    });
+    match failure {
+        Some(message) => Err(SaftError::new(message)),
+        None => Ok(()),
+    }
+}
+
+/// Ordering used by `sort`: numbers (int and float mix), strings, bools, and
+/// lists/tuples compared element by element. Anything else is incomparable.
+fn compare_values(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            let a = expect_number(a.clone(), "sort").ok()?;
+            let b = expect_number(b.clone(), "sort").ok()?;
+            a.partial_cmp(&b)
+        }
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::List(a), Value::List(b)) | (Value::Tuple(a), Value::Tuple(b)) => {
+            for (left, right) in a.iter().zip(b.iter()) {
+                match compare_values(left, right)? {
+                    Ordering::Equal => {}
+                    other => return Some(other),
+                }
+            }
+            Some(a.len().cmp(&b.len()))
+        }
+        _ => None,
+    }
+}
+
+fn finite_float(value: f64, name: &str) -> SaftResult<Value> {
+    if value.is_finite() {
+        Ok(Value::Float(value))
+    } else {
+        Err(SaftError::new(format!(
+            "{name} result is not a finite number"
+        )))
+    }
+}
+
+fn round_to_int(value: Value, name: &str, op: fn(f64) -> f64) -> SaftResult<Value> {
+    match value {
+        Value::Int(value) => Ok(Value::Int(value)),
+        Value::Float(value) => {
+            let rounded = op(value);
+            if rounded.is_finite() && rounded >= i64::MIN as f64 && rounded < i64::MAX as f64 {
+                Ok(Value::Int(rounded as i64))
+            } else {
+                Err(SaftError::new(format!(
+                    "{name} cannot convert {value} to int"
+                )))
+            }
+        }
+        other => Err(SaftError::new(format!(
+            "{name} expects int|float, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+fn builtin_print(args: Vec<Value>) -> SaftResult<Value> {
+    println!("{}", join_print_args(&args));
+    Ok(Value::Nil)
+}
+
+fn builtin_eprint(args: Vec<Value>) -> SaftResult<Value> {
+    eprintln!("{}", join_print_args(&args));
+    Ok(Value::Nil)
+}
+
+/// Like `print`, but breaks long lists/tuples/objects across indented lines and
+/// shows dataframes as an aligned table of their first rows.
+fn builtin_pprint(args: Vec<Value>) -> SaftResult<Value> {
+    let value = take_one_arg(args, "pprint")?;
+    let text = match &value {
+        Value::DataFrame(df) => dataframe_table(df, PPRINT_PREVIEW_ROWS)?,
+        other => pretty_value(other, 0, 0),
+    };
+    println!("{text}");
+    Ok(Value::Nil)
+}
+
+/// Raises a script-defined error at the call site. Non-string messages use
+/// their `str()` form.
+fn builtin_error(args: Vec<Value>) -> SaftResult<Value> {
+    let message = take_one_arg(args, "error")?;
+    Err(SaftError::new(value_to_text(&message)))
+}
+
+const PPRINT_WIDTH: usize = 80;
+const PPRINT_INDENT: usize = 2;
+const PPRINT_PREVIEW_ROWS: usize = 10;
+
+/// Keeps a value on one line when it fits from `column`; otherwise puts each
+/// item on its own line, `PPRINT_INDENT` deeper than `indent`.
+fn pretty_value(value: &Value, indent: usize, column: usize) -> String {
+    let inline = value.to_string();
+    if column + inline.chars().count() <= PPRINT_WIDTH {
+        return inline;
+    }
+    let (open, close, items): (&str, &str, Vec<(Option<&String>, &Value)>) = match value {
+        Value::List(items) if !items.is_empty() => {
+            ("[", "]", items.iter().map(|item| (None, item)).collect())
+        }
+        Value::Tuple(items) if !items.is_empty() => {
+            ("(", ")", items.iter().map(|item| (None, item)).collect())
+        }
+        Value::Object(map) if !map.is_empty() => (
+            "{",
+            "}",
+            map.iter().map(|(key, item)| (Some(key), item)).collect(),
+        ),
+        _ => return inline,
+    };
+
+    let inner = indent + PPRINT_INDENT;
+    let mut out = format!("{open}\n");
+    for (idx, (key, item)) in items.iter().enumerate() {
+        let prefix = key.map(|key| format!("{key}: ")).unwrap_or_default();
+        out.push_str(&" ".repeat(inner));
+        out.push_str(&prefix);
+        out.push_str(&pretty_value(item, inner, inner + prefix.len()));
+        if idx + 1 < items.len() {
+            out.push(',');
+        }
+        out.push('\n');
+    }
+    out.push_str(&" ".repeat(indent));
+    out.push_str(close);
+    out
+}
+
+/// Header, dashes, then up to `max_rows` rows; numeric columns are right-aligned.
+fn dataframe_table(df: &DataFrameValue, max_rows: usize) -> SaftResult<String> {
+    let frame = df.frame();
+    let shown = frame.height().min(max_rows);
+    let mut columns = Vec::with_capacity(frame.width());
+    for column in frame.get_columns() {
+        let mut cells = vec![column.name().to_string()];
+        for row_idx in 0..shown {
+            let cell = column
+                .get(row_idx)
+                .map_err(|err| SaftError::new(format!("pprint failed to read cell: {err}")))?;
+            cells.push(value_to_text(&anyvalue_to_value(cell)));
+        }
+        let width = cells
+            .iter()
+            .map(|cell| cell.chars().count())
+            .max()
+            .unwrap_or(0);
+        columns.push((cells, width, column.dtype().is_numeric()));
+    }
+
+    let mut lines = Vec::with_capacity(shown + 3);
+    for line_idx in 0..=shown + 1 {
+        // Line 0 is the header, line 1 the dashes, then one line per row.
+        let cell_idx = line_idx.saturating_sub(1);
+        let cells = columns
+            .iter()
+            .map(|(cells, width, numeric)| match line_idx {
+                1 => "-".repeat(*width),
+                _ if *numeric => format!("{:>width$}", cells[cell_idx]),
+                _ => format!("{:<width$}", cells[cell_idx]),
+            })
+            .collect::<Vec<_>>();
+        lines.push(cells.join("  ").trim_end().to_string());
+    }
+    let more = frame.height() - shown;
+    if more > 0 {
+        lines.push(format!("... {more} more rows"));
+    }
+    lines.push(format!(
+        "({} rows x {} cols)",
+        frame.height(),
+        frame.width()
+    ));
+    Ok(lines.join("\n"))
+}
+
+/// `print`/`eprint` arguments joined by single spaces, each in `str()` form.
+fn join_print_args(args: &[Value]) -> String {
+    args.iter().map(value_to_text).collect::<Vec<_>>().join(" ")
+}
+
+/// Reads one line from stdin without its line ending; `nil` at end of input so
+/// interactive scripts can tell when the user is done.
+fn builtin_input(args: Vec<Value>) -> SaftResult<Value> {
+    if let Some(prompt) = args.into_iter().next() {
+        let prompt = expect_string(prompt, "input")?;
+        let mut stdout = std::io::stdout();
+        write!(stdout, "{prompt}")
+            .and_then(|_| stdout.flush())
+            .map_err(|err| SaftError::new(format!("input could not write prompt: {err}")))?;
+    }
+
+    let mut line = String::new();
+    let read = std::io::stdin()
+        .read_line(&mut line)
+        .map_err(|err| SaftError::new(format!("input could not read stdin: {err}")))?;
+    if read == 0 {
+        return Ok(Value::Nil);
+    }
+    let trimmed = line.strip_suffix('\n').unwrap_or(&line);
+    let trimmed = trimmed.strip_suffix('\r').unwrap_or(trimmed);
+    Ok(Value::String(trimmed.to_string()))
+}
+
+/// Text form used by `print`/`str`/`format`: strings stay raw, everything else
+/// uses the value's display form.
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn builtin_len(args: Vec<Value>) -> SaftResult<Value> {
+    let arg = take_one_arg(args, "len")?;
+    let length = match arg {
+        Value::String(text) => text.chars().count() as i64,
+        Value::List(items) => items.len() as i64,
+        Value::Tuple(items) => items.len() as i64,
+        Value::Object(map) => map.len() as i64,
+        Value::DataFrame(df) => df.rows() as i64,
+        Value::VectorStore(store) => store.len() as i64,
+        other => {
+            return Err(SaftError::new(format!(
+                "len expects string/list/tuple/object/dataframe/vstore, got {}",
+                other.type_name()
+            )));
+        }
+    };
+    Ok(Value::Int(length))
+}
+
+fn builtin_type(args: Vec<Value>) -> SaftResult<Value> {
+    let arg = take_one_arg(args, "type")?;
+    Ok(Value::String(arg.type_name().to_string()))
+}
+
+fn builtin_is_int(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_int", &["int"])
+}
+
+fn builtin_is_float(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_float", &["float"])
+}
+
+fn builtin_is_number(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_number", &["int", "float"])
+}
+
+fn builtin_is_bool(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_bool", &["bool"])
+}
+
+fn builtin_is_string(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_string", &["string"])
+}
+
+fn builtin_is_list(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_list", &["list"])
+}
+
+fn builtin_is_tuple(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_tuple", &["tuple"])
+}
+
+fn builtin_is_object(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_object", &["object"])
+}
+
+fn builtin_is_dataframe(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_dataframe", &["dataframe"])
+}
+
+fn builtin_is_lazyframe(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_lazyframe", &["lazyframe"])
+}
+
+fn builtin_is_vstore(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_vstore", &["vstore"])
+}
+
+fn builtin_is_image(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_image", &["image"])
+}
+
+fn builtin_is_function(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_function", &["function"])
+}
+
+fn builtin_is_nil(args: Vec<Value>) -> SaftResult<Value> {
+    is_kind(args, "is_nil", &["nil"])
+}
+
+/// Shared body of the `is_*` predicates: whether the value's `type()` is one of `kinds`.
+fn is_kind(args: Vec<Value>, name: &str, kinds: &[&str]) -> SaftResult<Value> {
+    let arg = take_one_arg(args, name)?;
+    Ok(Value::Bool(kinds.contains(&arg.type_name())))
+}
+
+/// `http://`/`https://` paths are fetched (behind `--allow-net`) and parsed
+/// from memory; anything else is read from disk.
+fn builtin_read(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let path = iter
+        .next()
+        .ok_or_else(|| SaftError::new("read expects a path"))?;
+    let path = expect_string(path, "read")?;
+    let options = CsvReadOptions::parse(iter.next())?;
+    if path.starts_with("http://") || path.starts_with("https://") {
+        require_capability(host.allow_net(), "read from a URL", "--allow-net")?;
+        let response = http::send("GET", &path, &[], None)?;
+        if !(200..300).contains(&response.status) {
+            return Err(SaftError::new(format!(
+                "read could not fetch csv '{path}': HTTP {}",
+                response.status
+            )));
+        }
+        let frame = options
+            .apply(CsvReader::new(Cursor::new(response.body.into_bytes())))
+            .finish()
+            .map_err(|err| SaftError::new(format!("read failed to parse csv '{path}': {err}")))?;
+        return Ok(Value::DataFrame(DataFrameValue::new(frame)));
+    }
+
+    let normalized_path = Path::new(&path);
+    let reader = CsvReader::from_path(normalized_path)
+        .map_err(|err| SaftError::new(format!("read could not open csv '{path}': {err}")))?;
+    let frame = options
+        .apply(reader)
+        .finish()
+        .map_err(|err| SaftError::new(format!("read failed to parse csv '{path}': {err}")))?;
+
+    Ok(Value::DataFrame(DataFrameValue::new(frame)))
+}
+
+/// `read(path, {sep, header, dtypes, skip_rows})`. Without a header, columns are
+/// named `column_1`, `column_2`, ...; `dtypes` overrides inference per column.
+struct CsvReadOptions {
+    separator: u8,
+    header: bool,
+    skip_rows: usize,
+    dtypes: Option<Schema>,
+}
+
+impl CsvReadOptions {
+    fn parse(value: Option<Value>) -> SaftResult<Self> {
+        let mut options = Self {
+            separator: b',',
+            header: true,
+            skip_rows: 0,
+            dtypes: None,
+        };
+        let Some(value) = value else {
+            return Ok(options);
+        };
+        for (key, value) in expect_object(value, "read")? {
+            match (key.as_str(), value) {
+                ("sep", Value::String(sep)) if sep.len() == 1 => {
+                    options.separator = sep.as_bytes()[0];
+                }
+                ("sep", _) => {
+                    return Err(SaftError::new(
+                        "read option 'sep' expects a single-character string",
+                    ));
+                }
+                ("header", Value::Bool(header)) => options.header = header,
+                ("header", other) => {
+                    return Err(SaftError::new(format!(
+                        "read option 'header' expects bool, got {}",
+                        other.type_name()
+                    )));
+                }
+                ("skip_rows", value) => {
+                    options.skip_rows = expect_non_negative_int(value, "read", "skip_rows")?;
+                }
+                ("dtypes", value) => {
+                    let mut schema = Schema::new();
+                    for (column, dtype) in expect_object(value, "read")? {
+                        let dtype = match expect_string(dtype, "read")?.as_str() {
+                            "int" => DataType::Int64,
+                            "float" => DataType::Float64,
+                            "string" => DataType::String,
+                            "bool" => DataType::Boolean,
+                            other => {
+                                return Err(SaftError::new(format!(
+                                    "read got unknown dtype '{other}' for column '{column}' (expected int, float, string, or bool)"
+                                )));
+                            }
+                        };
+                        schema.with_column(column.into(), dtype);
+                    }
+                    options.dtypes = Some(schema);
+                }
+                (other, _) => {
+                    return Err(SaftError::new(format!(
+                        "read got unknown option '{other}'; expected sep, header, dtypes, or skip_rows"
+                    )));
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    fn apply<'a, R: MmapBytesReader + 'a>(&self, reader: CsvReader<'a, R>) -> CsvReader<'a, R> {
+        reader
+            .with_separator(self.separator)
+            .has_header(self.header)
+            .with_skip_rows(self.skip_rows)
+            .with_dtypes(self.dtypes.clone().map(Arc::new))
+    }
+}
+
+fn builtin_write_csv(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let (Some(df), Some(path)) = (iter.next(), iter.next()) else {
+        return Err(SaftError::new("write_csv expects a dataframe and a path"));
+    };
+    let df = expect_dataframe(df, "write_csv")?;
+    let path = expect_string(path, "write_csv")?;
+    let mut header = true;
+    let mut separator = b',';
+    if let Some(options) = iter.next() {
+        for (key, value) in expect_object(options, "write_csv")? {
+            match (key.as_str(), value) {
+                ("header", Value::Bool(flag)) => header = flag,
+                ("sep", Value::String(sep)) if sep.len() == 1 => separator = sep.as_bytes()[0],
+                ("header", other) => {
+                    return Err(SaftError::new(format!(
+                        "write_csv option 'header' expects bool, got {}",
+                        other.type_name()
+                    )));
+                }
+                ("sep", _) => {
+                    return Err(SaftError::new(
+                        "write_csv option 'sep' expects a single-character string",
+                    ));
+                }
+                _ => {
+                    return Err(SaftError::new(format!(
+                        "write_csv got unknown option '{key}'; expected header or sep"
+                    )));
+                }
+            }
+        }
+    }
+
+    let mut file = std::fs::File::create(&path)
+        .map_err(|err| SaftError::new(format!("write_csv could not create '{path}': {err}")))?;
+    let mut frame = df.frame().clone();
+    CsvWriter::new(&mut file)
+        .include_header(header)
+        .with_separator(separator)
+        .finish(&mut frame)
+        .map_err(|err| SaftError::new(format!("write_csv failed to write '{path}': {err}")))?;
+    Ok(Value::Nil)
+}
+
+fn builtin_read_parquet(args: Vec<Value>) -> SaftResult<Value> {
+    let path = expect_string(take_one_arg(args, "read_parquet")?, "read_parquet")?;
+    let file = std::fs::File::open(&path)
+        .map_err(|err| SaftError::new(format!("read_parquet could not open '{path}': {err}")))?;
+    let frame = ParquetReader::new(file)
+        .finish()
+        .map_err(|err| SaftError::new(format!("read_parquet failed to parse '{path}': {err}")))?;
+    Ok(Value::DataFrame(DataFrameValue::new(frame)))
+}
+
+fn builtin_write_parquet(args: Vec<Value>) -> SaftResult<Value> {
+    let (df, path) = take_two_args(args, "write_parquet")?;
+    let df = expect_dataframe(df, "write_parquet")?;
+    let path = expect_string(path, "write_parquet")?;
+    let mut file = std::fs::File::create(&path)
+        .map_err(|err| SaftError::new(format!("write_parquet could not create '{path}': {err}")))?;
+    let mut frame = df.frame().clone();
+    ParquetWriter::new(&mut file)
+        .finish(&mut frame)
+        .map_err(|err| SaftError::new(format!("write_parquet failed to write '{path}': {err}")))?;
+    Ok(Value::Nil)
+}
+
+/// Reads one worksheet into a dataframe. The first row holds the column names;
+/// a column that mixes kinds of cells is read as strings.
+fn builtin_read_excel(args: Vec<Value>) -> SaftResult<Value> {
+    let (path, sheet) = match args.len() {
+        1 => (take_one_arg(args, "read_excel")?, None),
+        _ => {
+            let (path, sheet) = take_two_args(args, "read_excel")?;
+            (path, Some(sheet))
+        }
+    };
+    let path = expect_string(path, "read_excel")?;
+    let sheet = match &sheet {
+        None => xlsx::SheetRef::Index(0),
+        Some(Value::String(name)) => xlsx::SheetRef::Name(name),
+        Some(index @ Value::Int(_)) => xlsx::SheetRef::Index(expect_non_negative_int(
+            index.clone(),
+            "read_excel",
+            "sheet index",
+        )?),
+        Some(other) => {
+            return Err(SaftError::new(format!(
+                "read_excel expects sheet name or index, got {}",
+                other.type_name()
+            )));
+        }
+    };
+
+    let mut rows = xlsx::read_sheet(&path, sheet)?.into_iter();
+    let Some(header) = rows.next() else {
+        return Ok(Value::DataFrame(DataFrameValue::new(DataFrame::default())));
+    };
+    let mut columns = vec![Vec::new(); header.len()];
+    for row in rows {
+        for (column, cell) in columns.iter_mut().zip(row) {
+            column.push(match cell {
+                xlsx::Cell::Empty => Value::Nil,
+                xlsx::Cell::Bool(value) => Value::Bool(value),
+                xlsx::Cell::Text(text) => Value::String(text),
+                xlsx::Cell::Number(text) => match text.parse::<i64>() {
+                    Ok(value) => Value::Int(value),
+                    Err(_) => Value::Float(text.parse::<f64>().map_err(|_| {
+                        SaftError::new(format!(
+                            "read_excel found invalid number '{text}' in '{path}'"
+                        ))
+                    })?),
+                },
+            });
+        }
+    }
+
+    let series = header
+        .into_iter()
+        .zip(columns)
+        .enumerate()
+        .map(|(index, (name, values))| {
+            let name = match name {
+                xlsx::Cell::Empty => format!("column_{}", index + 1),
+                xlsx::Cell::Text(text) | xlsx::Cell::Number(text) => text,
+                xlsx::Cell::Bool(value) => value.to_string(),
+            };
+            values_to_series(&name, &values, "read_excel").or_else(|_| {
+                let text = values
+                    .iter()
+                    .map(|value| match value {
+                        Value::Nil => Value::Nil,
+                        value => Value::String(value_to_text(value)),
+                    })
+                    .collect::<Vec<_>>();
+                values_to_series(&name, &text, "read_excel")
+            })
+        })
+        .collect::<SaftResult<Vec<_>>>()?;
+    let frame = DataFrame::new(series).map_err(|err| {
+        SaftError::new(format!(
+            "read_excel failed to build dataframe from '{path}': {err}"
+        ))
+    })?;
+    Ok(Value::DataFrame(DataFrameValue::new(frame)))
+}
+
+/// Lazy counterpart of `read`: the CSV is only read when the plan is collected.
+fn builtin_scan(args: Vec<Value>) -> SaftResult<Value> {
+    let path = expect_string(take_one_arg(args, "scan")?, "scan")?;
+    let plan = LazyCsvReader::new(&path)
+        .has_header(true)
+        .finish()
+        .map_err(|err| SaftError::new(format!("scan could not open csv '{path}': {err}")))?;
+    Ok(Value::LazyFrame(LazyFrameValue::new(plan)))
+}
+
+fn builtin_lazy(args: Vec<Value>) -> SaftResult<Value> {
+    let df = expect_dataframe(take_one_arg(args, "lazy")?, "lazy")?;
+    Ok(Value::LazyFrame(LazyFrameValue::new(
+        df.frame().clone().lazy(),
+    )))
+}
+
+fn builtin_collect(args: Vec<Value>) -> SaftResult<Value> {
+    let frame = match take_one_arg(args, "collect")? {
+        Value::LazyFrame(lf) => lf
+            .plan()
+            .collect()
+            .map_err(|err| SaftError::new(format!("collect failed: {err}")))?,
+        other => {
+            return Err(SaftError::new(format!(
+                "collect expects lazyframe, got {}",
+                other.type_name()
+            )));
+        }
+    };
+    Ok(Value::DataFrame(DataFrameValue::new(frame)))
+}
+
+/// Runs a SQL query over the dataframes (or lazyframes) in `tables`, each
+/// registered under its key.
+fn builtin_sql(args: Vec<Value>) -> SaftResult<Value> {
+    let (query, tables) = take_two_args(args, "sql")?;
+    let query = expect_string(query, "sql")?;
+    let mut context = SQLContext::new();
+    for (name, table) in expect_object(tables, "sql")? {
+        let plan = match table {
+            Value::DataFrame(df) => df.frame().clone().lazy(),
+            Value::LazyFrame(lf) => lf.plan(),
+            other => {
+                return Err(SaftError::new(format!(
+                    "sql expects table '{name}' to be a dataframe or lazyframe, got {}",
+                    other.type_name()
+                )));
+            }
+        };
+        context.register(&name, plan);
+    }
+    let frame = context
+        .execute(&query)
+        .and_then(|plan| plan.collect())
+        .map_err(|err| SaftError::new(format!("sql query failed: {err}")))?;
+    Ok(Value::DataFrame(DataFrameValue::new(frame)))
+}
+
+fn builtin_read_text(args: Vec<Value>) -> SaftResult<Value> {
+    let path = expect_string(take_one_arg(args, "read_text")?, "read_text")?;
+    std::fs::read_to_string(&path)
+        .map(Value::String)
+        .map_err(|err| SaftError::new(format!("read_text could not read '{path}': {err}")))
+}
+
+fn builtin_write_text(args: Vec<Value>) -> SaftResult<Value> {
+    let (path, text) = take_two_args(args, "write_text")?;
+    let path = expect_string(path, "write_text")?;
+    let text = expect_string(text, "write_text")?;
+    std::fs::write(&path, text)
+        .map_err(|err| SaftError::new(format!("write_text could not write '{path}': {err}")))?;
+    Ok(Value::Nil)
+}
+
+fn builtin_read_lines(args: Vec<Value>) -> SaftResult<Value> {
+    let path = expect_string(take_one_arg(args, "read_lines")?, "read_lines")?;
+    let text = std::fs::read_to_string(&path)
+        .map_err(|err| SaftError::new(format!("read_lines could not read '{path}': {err}")))?;
+    Ok(Value::List(
+        text.lines()
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    ))
+}
+
+fn builtin_append_file(args: Vec<Value>) -> SaftResult<Value> {
+    let (path, text) = take_two_args(args, "append_file")?;
+    let path = expect_string(path, "append_file")?;
+    let text = expect_string(text, "append_file")?;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|err| SaftError::new(format!("append_file could not write '{path}': {err}")))?;
+    Ok(Value::Nil)
+}
+
+fn builtin_exists(args: Vec<Value>) -> SaftResult<Value> {
+    let path = expect_string(take_one_arg(args, "exists")?, "exists")?;
+    Ok(Value::Bool(Path::new(&path).exists()))
+}
+
+fn builtin_file_size(args: Vec<Value>) -> SaftResult<Value> {
+    let path = expect_string(take_one_arg(args, "file_size")?, "file_size")?;
+    let metadata = std::fs::metadata(&path)
+        .map_err(|err| SaftError::new(format!("file_size could not stat '{path}': {err}")))?;
+    if !metadata.is_file() {
+        return Err(SaftError::new(format!(
+            "file_size expects a file, but '{path}' is a directory"
+        )));
+    }
+    Ok(Value::Int(metadata.len() as i64))
+}
+
+fn builtin_list_dir(args: Vec<Value>) -> SaftResult<Value> {
+    let path = expect_string(take_one_arg(args, "list_dir")?, "list_dir")?;
+    let entries = std::fs::read_dir(&path)
+        .map_err(|err| SaftError::new(format!("list_dir could not read '{path}': {err}")))?;
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry
+            .map_err(|err| SaftError::new(format!("list_dir could not read '{path}': {err}")))?;
+        names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    names.sort();
+    Ok(Value::List(names.into_iter().map(Value::String).collect()))
+}
+
+fn builtin_args(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    if !args.is_empty() {
+        return Err(SaftError::new("args expects no arguments"));
+    }
+    Ok(Value::List(
+        host.script_args()
+            .iter()
+            .map(|arg| Value::String(arg.clone()))
+            .collect(),
+    ))
+}
+
+/// Format used by `format_date` when none is given (ISO 8601, UTC).
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// Formats `parse_date` tries, in order, when no format is given.
+const PARSE_DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+fn builtin_now(args: Vec<Value>) -> SaftResult<Value> {
+    if !args.is_empty() {
+        return Err(SaftError::new("now expects no arguments"));
+    }
+    Ok(date_to_value(Utc::now().naive_utc()))
+}
+
+fn builtin_format_date(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let date = iter
+        .next()
+        .ok_or_else(|| SaftError::new("format_date expects a date"))?;
+    let date = expect_date(date, "format_date")?;
+    let format = match iter.next() {
+        Some(format) => expect_string(format, "format_date")?,
+        None => DEFAULT_DATE_FORMAT.to_string(),
+    };
+
+    let items = StrftimeItems::new(&format).collect::<Vec<_>>();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(SaftError::new(format!(
+            "format_date got an invalid format '{format}'"
+        )));
+    }
+    // Zone specifiers such as `%z` have nothing to render for a UTC-naive date.
+    let mut out = String::new();
+    fmt::Write::write_fmt(
+        &mut out,
+        format_args!("{}", date.format_with_items(items.into_iter())),
+    )
+    .map_err(|_| {
+        SaftError::new(format!(
+            "format_date cannot render format '{format}' for a UTC date"
+        ))
+    })?;
+    Ok(Value::String(out))
+}
+
+/// Parses a date/time string as UTC. Without a format it accepts RFC 3339,
+/// `YYYY-MM-DD[ HH:MM[:SS]]` and `YYYY-MM-DDTHH:MM[:SS]`; returns `nil` when the
+/// text does not match, like `parse_int`.
+fn builtin_parse_date(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let text = iter
+        .next()
+        .ok_or_else(|| SaftError::new("parse_date expects a string"))?;
+    let text = expect_string(text, "parse_date")?;
+    let text = text.trim();
+
+    let parsed = match iter.next() {
+        Some(format) => {
+            let format = expect_string(format, "parse_date")?;
+            parse_date_with(text, &format)
+        }
+        None => DateTime::parse_from_rfc3339(text)
+            .ok()
+            .map(|date| date.naive_utc())
+            .or_else(|| {
+                PARSE_DATE_FORMATS
+                    .iter()
+                    .find_map(|format| parse_date_with(text, format))
+            })
+            .or_else(|| parse_date_with(text, "%Y-%m-%d")),
+    };
+    Ok(parsed.map(date_to_value).unwrap_or(Value::Nil))
+}
+
+fn builtin_sleep(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let seconds = expect_number(take_one_arg(args, "sleep")?, "sleep")?;
+    let duration = Duration::try_from_secs_f64(seconds).map_err(|_| {
+        SaftError::new(format!(
+            "sleep expects a non-negative number of seconds, got {seconds}"
+        ))
+    })?;
+    host.sleep(duration)?;
     Ok(Value::Nil)
 }
 
-fn builtin_len(args: Vec<Value>) -> SaftResult<Value> {
-    let arg = take_one_arg(args, "len")?;
-    let length = match arg {
-        Value::String(text) => text.chars().count() as i64,
-        Value::List(items) => items.len() as i64,
-        Value::Tuple(items) => items.len() as i64,
-        Value::Object(map) => map.len() as i64,
-        Value::DataFrame(df) => df.rows() as i64,
+/// Accepts a full date-time format, or a date-only one (midnight).
+fn parse_date_with(text: &str, format: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(text, format)
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, format)
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// Date values are plain objects so fields read naturally (`d.year`) and
+/// `timestamp` (Unix seconds) supports arithmetic and comparisons.
+fn date_to_value(date: NaiveDateTime) -> Value {
+    let fields = [
+        ("year", Value::Int(i64::from(date.year()))),
+        ("month", Value::Int(i64::from(date.month()))),
+        ("day", Value::Int(i64::from(date.day()))),
+        ("hour", Value::Int(i64::from(date.hour()))),
+        ("minute", Value::Int(i64::from(date.minute()))),
+        ("second", Value::Int(i64::from(date.second()))),
+        ("weekday", Value::String(date.weekday().to_string())),
+        ("timestamp", Value::Int(date.and_utc().timestamp())),
+    ];
+    Value::Object(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+/// Accepts a date object (read via its `timestamp`) or a Unix timestamp.
+fn expect_date(value: Value, name: &str) -> SaftResult<NaiveDateTime> {
+    let timestamp = match value {
+        Value::Int(seconds) => seconds,
+        Value::Float(seconds) if seconds.is_finite() => seconds.floor() as i64,
+        Value::Object(mut map) => match map.remove("timestamp") {
+            Some(Value::Int(seconds)) => seconds,
+            _ => {
+                return Err(SaftError::new(format!(
+                    "{name} expects a date object with an int 'timestamp'"
+                )));
+            }
+        },
         other => {
             return Err(SaftError::new(format!(
-                "len expects string/list/tuple/object/dataframe, got {}",
+                "{name} expects a date object or timestamp, got {}",
                 other.type_name()
             )));
         }
     };
-    Ok(Value::Int(length))
-}
-
-fn builtin_type(args: Vec<Value>) -> SaftResult<Value> {
-    let arg = take_one_arg(args, "type")?;
-    Ok(Value::String(arg.type_name().to_string()))
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|date| date.naive_utc())
+        .ok_or_else(|| SaftError::new(format!("{name} timestamp {timestamp} is out of range")))
 }
 
-fn builtin_read(args: Vec<Value>) -> SaftResult<Value> {
-    let path = expect_string(take_one_arg(args, "read")?, "read")?;
-    let normalized_path = Path::new(&path);
-    let frame = CsvReader::from_path(normalized_path)
-        .map_err(|err| SaftError::new(format!("read could not open csv '{path}': {err}")))?
-        .has_header(true)
-        .finish()
-        .map_err(|err| SaftError::new(format!("read failed to parse csv '{path}': {err}")))?;
+fn builtin_image(args: Vec<Value>) -> SaftResult<Value> {
+    let path = expect_string(take_one_arg(args, "image")?, "image")?;
+    let extension = Path::new(&path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    let media_type = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => {
+            return Err(SaftError::new(format!(
+                "image expects a .png, .jpg, .jpeg, .gif, or .webp file, got '{path}'"
+            )));
+        }
+    };
+    let bytes = std::fs::read(&path)
+        .map_err(|err| SaftError::new(format!("image could not read '{path}': {err}")))?;
 
-    Ok(Value::DataFrame(DataFrameValue::new(frame)))
+    Ok(Value::Image(ImageValue {
+        path,
+        media_type: media_type.to_string(),
+        data_base64: base64_encode(&bytes).into(),
+    }))
 }
 
 fn builtin_shape(args: Vec<Value>) -> SaftResult<Value> {
@@ -232,19 +3292,94 @@ fn builtin_columns(args: Vec<Value>) -> SaftResult<Value> {
     Ok(Value::List(names))
 }
 
+/// On a lazyframe only the first rows are computed, so previews of large scans
+/// stay cheap.
 fn builtin_head(args: Vec<Value>) -> SaftResult<Value> {
-    let df = expect_dataframe(take_one_arg(args, "head")?, "head")?;
-    let preview = dataframe_rows(df.frame(), DEFAULT_HEAD_ROWS)?;
+    let preview = match take_one_arg(args, "head")? {
+        Value::LazyFrame(lf) => {
+            let frame = lf
+                .plan()
+                .limit(DEFAULT_HEAD_ROWS as IdxSize)
+                .collect()
+                .map_err(|err| SaftError::new(format!("head failed: {err}")))?;
+            dataframe_rows(&frame, DEFAULT_HEAD_ROWS)?
+        }
+        other => dataframe_rows(expect_dataframe(other, "head")?.frame(), DEFAULT_HEAD_ROWS)?,
+    };
+    Ok(Value::List(preview))
+}
+
+fn builtin_tail(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let df = iter
+        .next()
+        .ok_or_else(|| SaftError::new("tail expects a dataframe"))?;
+    let df = expect_dataframe(df, "tail")?;
+    let rows = match iter.next() {
+        Some(n) => expect_non_negative_int(n, "tail", "row count")?,
+        None => DEFAULT_HEAD_ROWS,
+    };
+    let preview = dataframe_rows(&df.frame().tail(Some(rows)), rows)?;
     Ok(Value::List(preview))
 }
 
+/// `sample(df, n, seed?)`: `n` distinct rows (all of them when `n` exceeds the
+/// height), kept in their original order. Without a seed the script RNG is used,
+/// so `--seed` still makes it repeatable. Like `head` and `tail` it is a preview,
+/// so the rows come back as objects rather than a dataframe.
+fn builtin_sample(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let (Some(df), Some(n)) = (iter.next(), iter.next()) else {
+        return Err(SaftError::new("sample expects a dataframe and a row count"));
+    };
+    let df = expect_dataframe(df, "sample")?;
+    let n = expect_non_negative_int(n, "sample", "row count")?;
+    let mut seeded = match iter.next() {
+        Some(seed) => Some(Rng::seeded(
+            expect_non_negative_int(seed, "sample", "seed")? as u64,
+        )),
+        None => None,
+    };
+    let rng = match seeded.as_mut() {
+        Some(rng) => rng,
+        None => host.rng(),
+    };
+
+    let height = df.frame().height();
+    let mut indices = (0..height as IdxSize).collect::<Vec<_>>();
+    let n = n.min(height);
+    for i in 0..n {
+        let j = i + rng.below((height - i) as u128) as usize;
+        indices.swap(i, j);
+    }
+    indices.truncate(n);
+    indices.sort_unstable();
+
+    let sampled = df
+        .frame()
+        .take(&IdxCa::from_vec("idx", indices))
+        .map_err(|err| SaftError::new(format!("sample failed: {err}")))?;
+    Ok(Value::List(dataframe_rows(&sampled, n)?))
+}
+
+fn builtin_to_records(args: Vec<Value>) -> SaftResult<Value> {
+    let df = expect_dataframe(take_one_arg(args, "to_records")?, "to_records")?;
+    Ok(Value::List(dataframe_rows(df.frame(), df.rows())?))
+}
+
 fn builtin_select(args: Vec<Value>) -> SaftResult<Value> {
     let (df_value, cols_value) = take_two_args(args, "select")?;
-    let df = expect_dataframe(df_value, "select")?;
     let cols = expect_string_list(cols_value, "select")?;
     if cols.is_empty() {
         return Err(SaftError::new("select expects at least one column name"));
     }
+    if let Value::LazyFrame(lf) = df_value {
+        let plan = lf
+            .plan()
+            .select(cols.iter().map(|name| col(name)).collect::<Vec<_>>());
+        return Ok(Value::LazyFrame(LazyFrameValue::new(plan)));
+    }
+    let df = expect_dataframe(df_value, "select")?;
 
     let col_refs = cols.iter().map(String::as_str).collect::<Vec<_>>();
     let selected = df
@@ -254,7 +3389,459 @@ fn builtin_select(args: Vec<Value>) -> SaftResult<Value> {
     Ok(Value::DataFrame(DataFrameValue::new(selected)))
 }
 
+/// Aggregations accepted by `group_by`.
+const GROUP_BY_AGGS: &[&str] = &[
+    "mean", "sum", "min", "max", "median", "std", "count", "n_unique", "first", "last",
+];
+
+/// `group_by(df, keys, {column: "agg"})`: one row per distinct key combination, in
+/// first-seen order. Aggregated columns keep their source names and follow the
+/// keys in object (sorted) order.
+fn builtin_group_by(args: Vec<Value>) -> SaftResult<Value> {
+    let (df, keys, aggs) = take_three_args(args, "group_by")?;
+    let keys = expect_string_list(keys, "group_by")?;
+    if keys.is_empty() {
+        return Err(SaftError::new("group_by expects at least one key column"));
+    }
+
+    let mut exprs = Vec::new();
+    for (column, agg) in expect_object(aggs, "group_by")? {
+        let agg = expect_string(agg, "group_by")?;
+        let expr = col(&column);
+        exprs.push(match agg.as_str() {
+            "mean" => expr.mean(),
+            "sum" => expr.sum(),
+            "min" => expr.min(),
+            "max" => expr.max(),
+            "median" => expr.median(),
+            "std" => expr.std(1),
+            "count" => expr.count(),
+            "n_unique" => expr.n_unique(),
+            "first" => expr.first(),
+            "last" => expr.last(),
+            other => {
+                return Err(SaftError::new(format!(
+                    "group_by got unknown aggregation '{other}' for column '{column}' (expected one of {})",
+                    GROUP_BY_AGGS.join(", ")
+                )));
+            }
+        });
+    }
+
+    let (plan, lazy) = match df {
+        Value::LazyFrame(lf) => (lf.plan(), true),
+        other => (
+            expect_dataframe(other, "group_by")?.frame().clone().lazy(),
+            false,
+        ),
+    };
+    let grouped = plan
+        .group_by_stable(keys.iter().map(|key| col(key)).collect::<Vec<_>>())
+        .agg(exprs);
+    if lazy {
+        return Ok(Value::LazyFrame(LazyFrameValue::new(grouped)));
+    }
+    let grouped = grouped
+        .collect()
+        .map_err(|err| SaftError::new(format!("group_by failed: {err}")))?;
+    Ok(Value::DataFrame(DataFrameValue::new(grouped)))
+}
+
+/// `sort_df(df, column | [columns], descending?)`: stable sort; with several
+/// columns later ones break ties.
+fn builtin_sort_df(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let (Some(df), Some(by)) = (iter.next(), iter.next()) else {
+        return Err(SaftError::new("sort_df expects a dataframe and a column"));
+    };
+    let df = expect_dataframe(df, "sort_df")?;
+    let by = match by {
+        Value::String(column) => vec![column],
+        other => expect_string_list(other, "sort_df")?,
+    };
+    if by.is_empty() {
+        return Err(SaftError::new("sort_df expects at least one column name"));
+    }
+    let descending = match iter.next() {
+        None => false,
+        Some(Value::Bool(descending)) => descending,
+        Some(other) => {
+            return Err(SaftError::new(format!(
+                "sort_df expects bool descending, got {}",
+                other.type_name()
+            )));
+        }
+    };
+
+    let sorted = df
+        .frame()
+        .sort(by, descending, true)
+        .map_err(|err| SaftError::new(format!("sort_df failed: {err}")))?;
+    Ok(Value::DataFrame(DataFrameValue::new(sorted)))
+}
+
+/// `with_column(df, name, f | values)`: adds (or replaces) column `name`, either
+/// from `f(row)` called with each row object or from a list with one value per row.
+fn builtin_with_column(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let (df, name, source) = take_three_args(args, "with_column")?;
+    let df = expect_dataframe(df, "with_column")?;
+    let name = expect_string(name, "with_column")?;
+    let values = match source {
+        Value::Function(func) => {
+            let mut values = Vec::with_capacity(df.rows());
+            for row in dataframe_rows(df.frame(), df.rows())? {
+                values.push(host.call(func, vec![row])?);
+            }
+            values
+        }
+        Value::List(values) => {
+            if values.len() != df.rows() {
+                return Err(SaftError::new(format!(
+                    "with_column expects {} values, got {}",
+                    df.rows(),
+                    values.len()
+                )));
+            }
+            values
+        }
+        other => {
+            return Err(SaftError::new(format!(
+                "with_column expects a function or list, got {}",
+                other.type_name()
+            )));
+        }
+    };
+
+    let series = values_to_series(&name, &values, "with_column")?;
+    let mut frame = df.frame().clone();
+    frame
+        .with_column(series)
+        .map_err(|err| SaftError::new(format!("with_column failed: {err}")))?;
+    Ok(Value::DataFrame(DataFrameValue::new(frame)))
+}
+
+fn builtin_drop(args: Vec<Value>) -> SaftResult<Value> {
+    let (df, cols) = take_two_args(args, "drop")?;
+    let df = expect_dataframe(df, "drop")?;
+    let mut frame = df.frame().clone();
+    for column in expect_string_list(cols, "drop")? {
+        frame = frame
+            .drop(&column)
+            .map_err(|err| SaftError::new(format!("drop failed: {err}")))?;
+    }
+    Ok(Value::DataFrame(DataFrameValue::new(frame)))
+}
+
+fn builtin_rename(args: Vec<Value>) -> SaftResult<Value> {
+    let (df, mapping) = take_two_args(args, "rename")?;
+    let df = expect_dataframe(df, "rename")?;
+    let mut frame = df.frame().clone();
+    for (old, new) in expect_object(mapping, "rename")? {
+        let new = expect_string(new, "rename")?;
+        frame
+            .rename(&old, &new)
+            .map_err(|err| SaftError::new(format!("rename failed: {err}")))?;
+    }
+    Ok(Value::DataFrame(DataFrameValue::new(frame)))
+}
+
+fn builtin_distinct(args: Vec<Value>) -> SaftResult<Value> {
+    let df = expect_dataframe(take_one_arg(args, "distinct")?, "distinct")?;
+    distinct_rows(&df, None, "distinct")
+}
+
+/// Keeps the first row for each distinct combination of `cols`.
+fn builtin_distinct_on(args: Vec<Value>) -> SaftResult<Value> {
+    let (df, cols) = take_two_args(args, "distinct_on")?;
+    let df = expect_dataframe(df, "distinct_on")?;
+    let cols = expect_string_list(cols, "distinct_on")?;
+    if cols.is_empty() {
+        return Err(SaftError::new(
+            "distinct_on expects at least one column name",
+        ));
+    }
+    distinct_rows(&df, Some(&cols), "distinct_on")
+}
+
+/// One row per column: `column`, `count` (non-null), `nulls`, and `mean`/`std`/
+/// `min`/`max`, which are null for non-numeric columns.
+fn builtin_describe(args: Vec<Value>) -> SaftResult<Value> {
+    let df = expect_dataframe(take_one_arg(args, "describe")?, "describe")?;
+    let profiles = df.column_profiles();
+    let stat =
+        |pick: fn(&ColumnProfile) -> Option<f64>| profiles.iter().map(pick).collect::<Vec<_>>();
+    let summary = DataFrame::new(vec![
+        Series::new(
+            "column",
+            profiles
+                .iter()
+                .map(|profile| profile.name.as_str())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "count",
+            profiles
+                .iter()
+                .map(|profile| profile.non_null_count as i64)
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "nulls",
+            profiles
+                .iter()
+                .map(|profile| profile.null_count as i64)
+                .collect::<Vec<_>>(),
+        ),
+        Series::new("mean", stat(|profile| profile.mean)),
+        Series::new("std", stat(|profile| profile.std)),
+        Series::new("min", stat(|profile| profile.min)),
+        Series::new("max", stat(|profile| profile.max)),
+    ])
+    .map_err(|err| SaftError::new(format!("describe failed: {err}")))?;
+    Ok(Value::DataFrame(DataFrameValue::new(summary)))
+}
+
+/// Null cells per column, keyed by column name.
+fn builtin_null_counts(args: Vec<Value>) -> SaftResult<Value> {
+    let df = expect_dataframe(take_one_arg(args, "null_counts")?, "null_counts")?;
+    let counts = df
+        .frame()
+        .get_columns()
+        .iter()
+        .map(|column| {
+            (
+                column.name().to_string(),
+                Value::Int(column.null_count() as i64),
+            )
+        })
+        .collect();
+    Ok(Value::Object(counts))
+}
+
+/// Drops rows with a null in any column, or only in `cols` when given.
+fn builtin_drop_nulls(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let df = iter
+        .next()
+        .ok_or_else(|| SaftError::new("drop_nulls expects a dataframe"))?;
+    let df = expect_dataframe(df, "drop_nulls")?;
+    let subset = iter
+        .next()
+        .map(|cols| expect_string_list(cols, "drop_nulls"))
+        .transpose()?;
+    let frame = df
+        .frame()
+        .drop_nulls(subset.as_deref())
+        .map_err(|err| SaftError::new(format!("drop_nulls failed: {err}")))?;
+    Ok(Value::DataFrame(DataFrameValue::new(frame)))
+}
+
+/// `fill_null(df, value, cols?)`: replaces nulls in `cols` (default: every
+/// column) with `value`. Filling an int column with a float widens it to float;
+/// other kind mismatches are errors.
+fn builtin_fill_null(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let (Some(df), Some(fill)) = (iter.next(), iter.next()) else {
+        return Err(SaftError::new("fill_null expects a dataframe and a value"));
+    };
+    let df = expect_dataframe(df, "fill_null")?;
+    let columns = match iter.next() {
+        Some(cols) => expect_string_list(cols, "fill_null")?,
+        None => df
+            .frame()
+            .get_column_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+    };
+
+    let mut frame = df.frame().clone();
+    for name in columns {
+        let column = frame
+            .column(&name)
+            .map_err(|err| SaftError::new(format!("fill_null failed: {err}")))?;
+        if column.null_count() == 0 {
+            continue;
+        }
+        let values = column
+            .rechunk()
+            .iter()
+            .map(|cell| match anyvalue_to_value(cell) {
+                Value::Nil => fill.clone(),
+                value => value,
+            })
+            .collect::<Vec<_>>();
+        let series = values_to_series(&name, &values, "fill_null")?;
+        frame
+            .with_column(series)
+            .map_err(|err| SaftError::new(format!("fill_null failed: {err}")))?;
+    }
+    Ok(Value::DataFrame(DataFrameValue::new(frame)))
+}
+
+/// `concat(frames, how?)`: `"vertical"` (default) stacks rows and needs the same
+/// column names, order, and types; `"horizontal"` places columns side by side
+/// and needs equal heights and distinct names.
+fn builtin_concat(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let frames = iter
+        .next()
+        .ok_or_else(|| SaftError::new("concat expects a list of dataframes"))?;
+    let frames = expect_list(frames, "concat")?
+        .into_iter()
+        .map(|frame| expect_dataframe(frame, "concat"))
+        .collect::<SaftResult<Vec<_>>>()?;
+    let how = match iter.next() {
+        Some(how) => expect_string(how, "concat")?,
+        None => "vertical".to_string(),
+    };
+    let Some((first, rest)) = frames.split_first() else {
+        return Err(SaftError::new("concat expects at least one dataframe"));
+    };
+
+    let mut combined = first.frame().clone();
+    match how.as_str() {
+        "vertical" => {
+            let schema = first.frame().schema();
+            for (offset, frame) in rest.iter().enumerate() {
+                let other = frame.frame().schema();
+                if other != schema {
+                    return Err(SaftError::new(format!(
+                        "concat expects matching schemas; frame 0 has {} but frame {} has {}",
+                        schema_summary(&schema),
+                        offset + 1,
+                        schema_summary(&other)
+                    )));
+                }
+                combined
+                    .vstack_mut(frame.frame())
+                    .map_err(|err| SaftError::new(format!("concat failed: {err}")))?;
+            }
+            combined.as_single_chunk_par();
+        }
+        "horizontal" => {
+            for frame in rest {
+                combined
+                    .hstack_mut(frame.frame().get_columns())
+                    .map_err(|err| SaftError::new(format!("concat failed: {err}")))?;
+            }
+        }
+        other => {
+            return Err(SaftError::new(format!(
+                "concat expects how to be \"vertical\" or \"horizontal\", got \"{other}\""
+            )));
+        }
+    }
+    Ok(Value::DataFrame(DataFrameValue::new(combined)))
+}
+
+/// `[name: type, ...]` for schema mismatch errors.
+fn schema_summary(schema: &Schema) -> String {
+    let fields = schema
+        .iter()
+        .map(|(name, dtype)| format!("{name}: {dtype}"))
+        .collect::<Vec<_>>();
+    format!("[{}]", fields.join(", "))
+}
+
+/// Order-preserving dedup shared by `distinct`/`distinct_on`.
+fn distinct_rows(
+    df: &DataFrameValue,
+    subset: Option<&[String]>,
+    op_name: &str,
+) -> SaftResult<Value> {
+    let unique = df
+        .frame()
+        .unique_stable(subset, UniqueKeepStrategy::First, None)
+        .map_err(|err| SaftError::new(format!("{op_name} failed: {err}")))?;
+    Ok(Value::DataFrame(DataFrameValue::new(unique)))
+}
+
+/// Builds a column from script values: all ints -> int, ints/floats -> float,
+/// strings -> string, bools -> bool; `nil` becomes null. Mixed kinds are an error.
+fn values_to_series(name: &str, values: &[Value], op_name: &str) -> SaftResult<Series> {
+    let kind = values
+        .iter()
+        .filter(|value| !matches!(value, Value::Nil))
+        .try_fold(None, |kind: Option<&str>, value| {
+            let next = match (kind, value) {
+                (None | Some("int"), Value::Int(_)) => "int",
+                (None | Some("int") | Some("float"), Value::Int(_) | Value::Float(_)) => "float",
+                (None | Some("string"), Value::String(_)) => "string",
+                (None | Some("bool"), Value::Bool(_)) => "bool",
+                (Some(kind), value) => {
+                    return Err(SaftError::new(format!(
+                        "{op_name} cannot mix {kind} and {} values in column '{name}'",
+                        value.type_name()
+                    )));
+                }
+                (None, value) => {
+                    return Err(SaftError::new(format!(
+                        "{op_name} cannot store {} values in column '{name}'",
+                        value.type_name()
+                    )));
+                }
+            };
+            Ok(Some(next))
+        })?;
+
+    let series = match kind {
+        Some("int") => Series::new(
+            name,
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::Int(v) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Some("float") => Series::new(
+            name,
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::Int(v) => Some(*v as f64),
+                    Value::Float(v) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Some("bool") => Series::new(
+            name,
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::Bool(v) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        // All-nil columns are stored as nullable strings.
+        _ => Series::new(
+            name,
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::String(v) => Some(v.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+    };
+    Ok(series)
+}
+
 fn builtin_mean(args: Vec<Value>) -> SaftResult<Value> {
+    if args.len() == 1 {
+        let values = expect_number_list(take_one_arg(args, "mean")?, "mean")?;
+        if values.is_empty() {
+            return Err(SaftError::new("mean expects a non-empty list"));
+        }
+        return Ok(Value::Float(
+            values.iter().sum::<f64>() / values.len() as f64,
+        ));
+    }
     let (df_value, column_value) = take_two_args(args, "mean")?;
     let df = expect_dataframe(df_value, "mean")?;
     let column = expect_string(column_value, "mean")?;
@@ -263,6 +3850,25 @@ fn builtin_mean(args: Vec<Value>) -> SaftResult<Value> {
 }
 
 fn builtin_sum(args: Vec<Value>) -> SaftResult<Value> {
+    if args.len() == 1 {
+        let items = expect_numeric_items(take_one_arg(args, "sum")?, "sum")?;
+        if items.iter().all(|item| matches!(item, Value::Int(_))) {
+            let mut total: i64 = 0;
+            for item in &items {
+                if let Value::Int(value) = item {
+                    total = total
+                        .checked_add(*value)
+                        .ok_or_else(|| SaftError::new("sum overflowed int range"))?;
+                }
+            }
+            return Ok(Value::Int(total));
+        }
+        let mut total = 0.0;
+        for item in items {
+            total += expect_number(item, "sum")?;
+        }
+        return Ok(Value::Float(total));
+    }
     let (df_value, column_value) = take_two_args(args, "sum")?;
     let df = expect_dataframe(df_value, "sum")?;
     let column = expect_string(column_value, "sum")?;
@@ -271,6 +3877,9 @@ fn builtin_sum(args: Vec<Value>) -> SaftResult<Value> {
 }
 
 fn builtin_min(args: Vec<Value>) -> SaftResult<Value> {
+    if args.len() == 1 {
+        return list_extreme(take_one_arg(args, "min")?, "min", Ordering::Less);
+    }
     let (df_value, column_value) = take_two_args(args, "min")?;
     let df = expect_dataframe(df_value, "min")?;
     let column = expect_string(column_value, "min")?;
@@ -279,6 +3888,9 @@ fn builtin_min(args: Vec<Value>) -> SaftResult<Value> {
 }
 
 fn builtin_max(args: Vec<Value>) -> SaftResult<Value> {
+    if args.len() == 1 {
+        return list_extreme(take_one_arg(args, "max")?, "max", Ordering::Greater);
+    }
     let (df_value, column_value) = take_two_args(args, "max")?;
     let df = expect_dataframe(df_value, "max")?;
     let column = expect_string(column_value, "max")?;
@@ -286,6 +3898,275 @@ fn builtin_max(args: Vec<Value>) -> SaftResult<Value> {
     Ok(Value::Float(result))
 }
 
+fn builtin_median(args: Vec<Value>) -> SaftResult<Value> {
+    if args.len() == 1 {
+        let values = expect_number_list(take_one_arg(args, "median")?, "median")?;
+        return list_quantile(values, 0.5, "median").map(Value::Float);
+    }
+    let (df_value, column_value) = take_two_args(args, "median")?;
+    let df = expect_dataframe(df_value, "median")?;
+    let column = expect_string(column_value, "median")?;
+    let result = numeric_aggregate(df.frame(), &column, "median", |col| col.median())?;
+    Ok(Value::Float(result))
+}
+
+/// Sample standard deviation (divides by `n - 1`).
+fn builtin_std(args: Vec<Value>) -> SaftResult<Value> {
+    if args.len() == 1 {
+        let values = expect_number_list(take_one_arg(args, "std")?, "std")?;
+        if values.len() < 2 {
+            return Err(SaftError::new("std expects at least two values"));
+        }
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / (values.len() - 1) as f64;
+        return Ok(Value::Float(variance.sqrt()));
+    }
+    let (df_value, column_value) = take_two_args(args, "std")?;
+    let df = expect_dataframe(df_value, "std")?;
+    let column = expect_string(column_value, "std")?;
+    let result = numeric_aggregate(df.frame(), &column, "std", |col| col.std(1))?;
+    Ok(Value::Float(result))
+}
+
+/// `quantile(list, q)` / `quantile(df, column, q)` with linear interpolation
+/// between the closest ranks; `q` is in `[0, 1]`.
+fn builtin_quantile(args: Vec<Value>) -> SaftResult<Value> {
+    if args.len() == 2 {
+        let (values, q) = take_two_args(args, "quantile")?;
+        let values = expect_number_list(values, "quantile")?;
+        let q = expect_quantile(q)?;
+        return list_quantile(values, q, "quantile").map(Value::Float);
+    }
+    let (df_value, column_value, q) = take_three_args(args, "quantile")?;
+    let df = expect_dataframe(df_value, "quantile")?;
+    let column = expect_string(column_value, "quantile")?;
+    let q = expect_quantile(q)?;
+    let result = numeric_aggregate(df.frame(), &column, "quantile", |col| {
+        col.quantile(q, QuantileInterpolOptions::Linear)
+            .ok()
+            .flatten()
+    })?;
+    Ok(Value::Float(result))
+}
+
+/// `count(df)` is the row count; `count(df, column)` counts non-null cells.
+fn builtin_count(args: Vec<Value>) -> SaftResult<Value> {
+    let mut iter = args.into_iter();
+    let df = iter
+        .next()
+        .ok_or_else(|| SaftError::new("count expects a dataframe"))?;
+    let df = expect_dataframe(df, "count")?;
+    let Some(column) = iter.next() else {
+        return Ok(Value::Int(df.rows() as i64));
+    };
+    let column = expect_string(column, "count")?;
+    let series = df
+        .frame()
+        .column(&column)
+        .map_err(|err| SaftError::new(format!("count failed: {err}")))?;
+    Ok(Value::Int((series.len() - series.null_count()) as i64))
+}
+
+/// Distinct values in a column; null counts as one value.
+fn builtin_nunique(args: Vec<Value>) -> SaftResult<Value> {
+    let (df_value, column_value) = take_two_args(args, "nunique")?;
+    let df = expect_dataframe(df_value, "nunique")?;
+    let column = expect_string(column_value, "nunique")?;
+    let count = df
+        .frame()
+        .column(&column)
+        .and_then(|series| series.n_unique())
+        .map_err(|err| SaftError::new(format!("nunique failed: {err}")))?;
+    Ok(Value::Int(count as i64))
+}
+
+/// `value_counts(df, column)`: one row per distinct value (null included) with
+/// its `count`, most frequent first; ties keep first-seen order.
+fn builtin_value_counts(args: Vec<Value>) -> SaftResult<Value> {
+    let (df_value, column_value) = take_two_args(args, "value_counts")?;
+    let df = expect_dataframe(df_value, "value_counts")?;
+    let column = expect_string(column_value, "value_counts")?;
+    if column == "count" {
+        return Err(SaftError::new(
+            "value_counts cannot count a column named 'count'; rename it first",
+        ));
+    }
+    let counts = df
+        .frame()
+        .clone()
+        .lazy()
+        .group_by_stable([col(&column)])
+        .agg([col(&column).len().alias("count")])
+        .collect()
+        .and_then(|counts| counts.sort(["count"], true, true))
+        .map_err(|err| SaftError::new(format!("value_counts failed: {err}")))?;
+    Ok(Value::DataFrame(DataFrameValue::new(counts)))
+}
+
+fn expect_quantile(value: Value) -> SaftResult<f64> {
+    let q = expect_number(value, "quantile")?;
+    if !(0.0..=1.0).contains(&q) {
+        return Err(SaftError::new(format!(
+            "quantile expects q between 0 and 1, got {q}"
+        )));
+    }
+    Ok(q)
+}
+
+/// Linear-interpolated quantile of a list, matching polars' `Linear` option.
+fn list_quantile(mut values: Vec<f64>, q: f64, name: &str) -> SaftResult<f64> {
+    if values.is_empty() {
+        return Err(SaftError::new(format!("{name} expects a non-empty list")));
+    }
+    values.sort_by(f64::total_cmp);
+    let position = q * (values.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    Ok(values[lower] + (values[upper] - values[lower]) * (position - lower as f64))
+}
+
+/// Smallest (`Less`) or largest (`Greater`) list item, keeping its int/float type.
+fn list_extreme(value: Value, name: &str, wanted: Ordering) -> SaftResult<Value> {
+    let items = expect_numeric_items(value, name)?;
+    let mut iter = items.into_iter();
+    let mut best = iter
+        .next()
+        .ok_or_else(|| SaftError::new(format!("{name} expects a non-empty list")))?;
+    for item in iter {
+        if compare_values(&item, &best) == Some(wanted) {
+            best = item;
+        }
+    }
+    Ok(best)
+}
+
+fn builtin_embed(host: &mut dyn BuiltinHost, args: Vec<Value>) -> SaftResult<Value> {
+    let text = expect_string(take_one_arg(args, "embed")?, "embed")?;
+    let vector = host.embed(&text)?;
+    Ok(Value::List(vector.into_iter().map(Value::Float).collect()))
+}
+
+fn builtin_cosine(args: Vec<Value>) -> SaftResult<Value> {
+    let (a_value, b_value) = take_two_args(args, "cosine")?;
+    let a = expect_number_list(a_value, "cosine")?;
+    let b = expect_number_list(b_value, "cosine")?;
+    cosine_similarity(&a, &b, "cosine").map(Value::Float)
+}
+
+fn builtin_vstore(args: Vec<Value>) -> SaftResult<Value> {
+    if !args.is_empty() {
+        return Err(SaftError::new("vstore expects no arguments"));
+    }
+    Ok(Value::VectorStore(VectorStoreValue::new()))
+}
+
+fn builtin_vstore_add(args: Vec<Value>) -> SaftResult<Value> {
+    let (store_value, id_value, vector_value, meta) = take_four_args(args, "vstore_add")?;
+    let store = expect_vector_store(store_value, "vstore_add")?;
+    let id = expect_string(id_value, "vstore_add")?;
+    let vector = expect_number_list(vector_value, "vstore_add")?;
+    if vector.is_empty() {
+        return Err(SaftError::new("vstore_add expects a non-empty vector"));
+    }
+    if let Some(dimensions) = store.dimensions()
+        && dimensions != vector.len()
+    {
+        return Err(SaftError::new(format!(
+            "vstore_add expects vectors of length {dimensions}, got {}",
+            vector.len()
+        )));
+    }
+
+    store.upsert(VectorEntry { id, vector, meta });
+    Ok(Value::Nil)
+}
+
+fn builtin_vstore_query(args: Vec<Value>) -> SaftResult<Value> {
+    let (store_value, vector_value, k_value) = take_three_args(args, "vstore_query")?;
+    let store = expect_vector_store(store_value, "vstore_query")?;
+    let query = expect_number_list(vector_value, "vstore_query")?;
+    let k = match k_value {
+        Value::Int(k) if k >= 0 => k as usize,
+        Value::Int(_) => return Err(SaftError::new("vstore_query expects non-negative k")),
+        other => {
+            return Err(SaftError::new(format!(
+                "vstore_query expects int k, got {}",
+                other.type_name()
+            )));
+        }
+    };
+
+    let mut scored = Vec::new();
+    for entry in store.entries() {
+        let score = cosine_similarity(&query, &entry.vector, "vstore_query")?;
+        scored.push((score, entry));
+    }
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let results = scored
+        .into_iter()
+        .take(k)
+        .map(|(score, entry)| {
+            let mut hit = BTreeMap::new();
+            hit.insert("id".to_string(), Value::String(entry.id));
+            hit.insert("score".to_string(), Value::Float(score));
+            hit.insert("meta".to_string(), entry.meta);
+            Value::Object(hit)
+        })
+        .collect();
+    Ok(Value::List(results))
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = chunk.get(1).copied().unwrap_or(0) as u32;
+        let b2 = chunk.get(2).copied().unwrap_or(0) as u32;
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+
+        out.push(ALPHABET[(triple >> 18) as usize & 0x3f] as char);
+        out.push(ALPHABET[(triple >> 12) as usize & 0x3f] as char);
+        if chunk.len() > 1 {
+            out.push(ALPHABET[(triple >> 6) as usize & 0x3f] as char);
+        } else {
+            out.push('=');
+        }
+        if chunk.len() > 2 {
+            out.push(ALPHABET[triple as usize & 0x3f] as char);
+        } else {
+            out.push('=');
+        }
+    }
+    out
+}
+
+fn cosine_similarity(a: &[f64], b: &[f64], name: &str) -> SaftResult<f64> {
+    if a.len() != b.len() {
+        return Err(SaftError::new(format!(
+            "{name} expects vectors of equal length, got {} and {}",
+            a.len(),
+            b.len()
+        )));
+    }
+
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>();
+    let norm_a = a.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let norm_b = b.iter().map(|y| y * y).sum::<f64>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return Err(SaftError::new(format!(
+            "{name} is undefined for zero-length vectors"
+        )));
+    }
+    Ok(dot / (norm_a * norm_b))
+}
+
 fn numeric_aggregate(
     frame: &polars::prelude::DataFrame,
     column: &str,
@@ -310,7 +4191,20 @@ fn numeric_aggregate(
     })
 }
 
-fn dataframe_rows(frame: &polars::prelude::DataFrame, max_rows: usize) -> SaftResult<Vec<Value>> {
+/// One column's cells in row order; nulls become `nil`. Backs `df["col"]`.
+pub fn dataframe_column(frame: &polars::prelude::DataFrame, name: &str) -> SaftResult<Vec<Value>> {
+    let column = frame
+        .column(name)
+        .map_err(|_| SaftError::new(format!("dataframe has no column '{name}'")))?;
+    Ok(column.rechunk().iter().map(anyvalue_to_value).collect())
+}
+
+/// The first `max_rows` rows as objects keyed by column name; nulls become `nil`.
+/// Also used by the runtime to iterate `for row in df`.
+pub fn dataframe_rows(
+    frame: &polars::prelude::DataFrame,
+    max_rows: usize,
+) -> SaftResult<Vec<Value>> {
     let rows = std::cmp::min(frame.height(), max_rows);
     let mut out = Vec::with_capacity(rows);
 
@@ -318,11 +4212,10 @@ fn dataframe_rows(frame: &polars::prelude::DataFrame, max_rows: usize) -> SaftRe
         let mut row = BTreeMap::new();
         for column in frame.get_columns() {
             let name = column.name().to_string();
-            let cell = anyvalue_to_value(
-                column
-                    .get(row_idx)
-                    .map_err(|err| SaftError::new(format!("head failed to read cell: {err}")))?,
-            );
+            let cell =
+                anyvalue_to_value(column.get(row_idx).map_err(|err| {
+                    SaftError::new(format!("failed to read dataframe cell: {err}"))
+                })?);
             row.insert(name, cell);
         }
         out.push(Value::Object(row));
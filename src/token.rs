@@ -46,6 +46,7 @@ pub enum TokenKind {
     True,
     False,
     Nil,
+    With,
 
     Newline,
     Indent,
@@ -70,3 +71,21 @@ impl Token {
         Self { kind, span }
     }
 }
+
+/// What the lexer leaves out of the token stream but `fmt` puts back.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trivia {
+    pub comments: Vec<Comment>,
+    /// Start offsets of empty (or all-space) lines outside prompt blocks.
+    pub blank_lines: Vec<usize>,
+}
+
+/// A `//` comment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// From `//` to the end of the line, trailing whitespace removed.
+    pub text: String,
+    pub span: Span,
+    /// Alone on its line, as opposed to trailing code.
+    pub own_line: bool,
+}
@@ -0,0 +1,166 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Write;
+use std::rc::Rc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{Value as JsonValue, json};
+
+use crate::error::{SaftError, SaftResult};
+use crate::provider::{PromptProvider, PromptRequest, PromptResponse};
+
+/// Shared JSON lines sink; clones append to the same file with one `seq` counter,
+/// so every named provider of a run can log into a single transcript.
+#[derive(Clone)]
+pub struct TranscriptLog {
+    sink: Rc<RefCell<TranscriptSink>>,
+}
+
+struct TranscriptSink {
+    file: File,
+    path: String,
+    seq: usize,
+}
+
+impl TranscriptLog {
+    /// Starts a fresh transcript, truncating `path` if it exists; `seq` counts
+    /// from 1 again, so appending to an older run's file would repeat numbers.
+    pub fn create(path: &str) -> SaftResult<Self> {
+        let file = File::create(path).map_err(|err| {
+            SaftError::new(format!("could not create transcript file '{path}': {err}"))
+        })?;
+        Ok(Self {
+            sink: Rc::new(RefCell::new(TranscriptSink {
+                file,
+                path: path.to_string(),
+                seq: 0,
+            })),
+        })
+    }
+
+    fn next_seq(&self) -> usize {
+        let mut sink = self.sink.borrow_mut();
+        sink.seq += 1;
+        sink.seq
+    }
+
+    fn write_entry(&self, entry: &JsonValue) -> SaftResult<()> {
+        let mut sink = self.sink.borrow_mut();
+        writeln!(sink.file, "{entry}").map_err(|err| {
+            SaftError::new(format!(
+                "could not write transcript file '{}': {err}",
+                sink.path
+            ))
+        })
+    }
+}
+
+/// Provider wrapper that appends every request/response exchange to a JSON lines
+/// file. Each tool round is its own line, so a prompt that triggers tool calls
+/// shows up as a sequence of requests with growing `tool_results`.
+pub struct TranscriptProvider {
+    inner: Box<dyn PromptProvider>,
+    log: TranscriptLog,
+}
+
+impl TranscriptProvider {
+    pub fn create(path: &str, inner: Box<dyn PromptProvider>) -> SaftResult<Self> {
+        Ok(Self::new(inner, TranscriptLog::create(path)?))
+    }
+
+    pub fn new(inner: Box<dyn PromptProvider>, log: TranscriptLog) -> Self {
+        Self { inner, log }
+    }
+}
+
+impl PromptProvider for TranscriptProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        let seq = self.log.next_seq();
+        let started_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+        let request_json = request_to_json(&request);
+        let model = request
+            .model
+            .clone()
+            .or_else(|| self.inner.model().map(str::to_string));
+
+        let started = Instant::now();
+        let result = self.inner.complete(request);
+        let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        let mut entry = json!({
+            "seq": seq,
+            "started_at_ms": started_at_ms,
+            "duration_ms": duration_ms,
+            "model": model,
+            "request": request_json,
+        });
+        let fields = entry.as_object_mut().expect("entry should be object");
+        match &result {
+            Ok(response) => {
+                fields.insert("response".to_string(), response_to_json(response));
+            }
+            Err(err) => {
+                fields.insert("error".to_string(), JsonValue::String(err.message.clone()));
+            }
+        }
+
+        self.log.write_entry(&entry)?;
+        result
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.inner.model()
+    }
+
+    fn temperature(&self) -> Option<f32> {
+        self.inner.temperature()
+    }
+}
+
+fn request_to_json(request: &PromptRequest) -> JsonValue {
+    json!({
+        "prompt": request.prompt,
+        "images": request
+            .images
+            .iter()
+            .map(|image| json!({
+                "media_type": image.media_type,
+                "base64_len": image.data_base64.len(),
+            }))
+            .collect::<Vec<_>>(),
+        "tools": request
+            .tools
+            .iter()
+            .map(|tool| json!({ "name": tool.name, "params": tool.param_names }))
+            .collect::<Vec<_>>(),
+        "tool_results": request
+            .tool_results
+            .iter()
+            .map(|result| json!({
+                "id": result.id,
+                "name": result.name,
+                "args": result.args,
+                "output": result.output,
+            }))
+            .collect::<Vec<_>>(),
+        "response_format": request
+            .response_format
+            .as_ref()
+            .map(|format| json!({ "name": format.name, "schema": format.schema })),
+    })
+}
+
+fn response_to_json(response: &PromptResponse) -> JsonValue {
+    match response {
+        PromptResponse::FinalText(text) => json!({ "final_text": text }),
+        PromptResponse::ToolCalls(calls) => json!({
+            "tool_calls": calls
+                .iter()
+                .map(|call| json!({ "id": call.id, "name": call.name, "args": call.args }))
+                .collect::<Vec<_>>(),
+        }),
+    }
+}
@@ -1,8 +1,10 @@
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::fmt;
+use std::rc::Rc;
 use std::sync::Arc;
 
-use polars::prelude::DataFrame;
+use polars::prelude::{ChunkAgg, ChunkVar, DataFrame, DataType, LazyFrame};
 
 pub type FunctionId = usize;
 
@@ -29,6 +31,55 @@ impl DataFrameValue {
     pub fn cols(&self) -> usize {
         self.frame.width()
     }
+
+    /// Per-column counts and numeric stats, shared by the prompt context block and
+    /// `describe`. Numeric stats are filled for columns that cast to float with at
+    /// least one non-null value.
+    pub fn column_profiles(&self) -> Vec<ColumnProfile> {
+        self.frame
+            .get_columns()
+            .iter()
+            .map(|column| {
+                let mut profile = ColumnProfile {
+                    name: column.name().to_string(),
+                    non_null_count: column.len() - column.null_count(),
+                    null_count: column.null_count(),
+                    numeric: false,
+                    mean: None,
+                    std: None,
+                    min: None,
+                    max: None,
+                };
+                let Ok(casted) = column.cast(&DataType::Float64) else {
+                    return profile;
+                };
+                let Ok(as_float) = casted.f64() else {
+                    return profile;
+                };
+                if as_float.len() == as_float.null_count() {
+                    return profile;
+                }
+                profile.numeric = true;
+                profile.mean = as_float.mean();
+                profile.std = as_float.std(1);
+                profile.min = as_float.min();
+                profile.max = as_float.max();
+                profile
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub non_null_count: usize,
+    pub null_count: usize,
+    pub numeric: bool,
+    pub mean: Option<f64>,
+    pub std: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
 }
 
 impl PartialEq for DataFrameValue {
@@ -37,6 +88,99 @@ impl PartialEq for DataFrameValue {
     }
 }
 
+/// Deferred polars query from `scan`/`lazy`. `select`/`filter`/`group_by`
+/// extend the plan; nothing is read until `collect` (or `head`) runs it.
+#[derive(Clone)]
+pub struct LazyFrameValue {
+    plan: Arc<LazyFrame>,
+}
+
+impl LazyFrameValue {
+    pub fn new(plan: LazyFrame) -> Self {
+        Self {
+            plan: Arc::new(plan),
+        }
+    }
+
+    /// A copy of the plan to extend or run; cloning only copies plan nodes.
+    pub fn plan(&self) -> LazyFrame {
+        self.plan.as_ref().clone()
+    }
+}
+
+impl fmt::Debug for LazyFrameValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LazyFrameValue")
+    }
+}
+
+impl PartialEq for LazyFrameValue {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.plan, &other.plan)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VectorEntry {
+    pub id: String,
+    pub vector: Vec<f64>,
+    pub meta: Value,
+}
+
+/// Shared handle to an in-memory vector store; clones alias the same entries so
+/// `vstore_add` is visible through every binding of the store.
+#[derive(Debug, Clone, Default)]
+pub struct VectorStoreValue {
+    entries: Rc<RefCell<Vec<VectorEntry>>>,
+}
+
+impl VectorStoreValue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    pub fn dimensions(&self) -> Option<usize> {
+        self.entries
+            .borrow()
+            .first()
+            .map(|entry| entry.vector.len())
+    }
+
+    pub fn upsert(&self, entry: VectorEntry) {
+        let mut entries = self.entries.borrow_mut();
+        match entries.iter_mut().find(|existing| existing.id == entry.id) {
+            Some(existing) => *existing = entry,
+            None => entries.push(entry),
+        }
+    }
+
+    pub fn entries(&self) -> Vec<VectorEntry> {
+        self.entries.borrow().clone()
+    }
+}
+
+impl PartialEq for VectorStoreValue {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.entries, &other.entries)
+    }
+}
+
+/// Image loaded by `image(path)`; the base64 payload is shared between clones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageValue {
+    pub path: String,
+    pub media_type: String,
+    pub data_base64: Rc<str>,
+}
+
 #[derive(Debug, Clone)]
 pub enum Value {
     Int(i64),
@@ -47,6 +191,9 @@ pub enum Value {
     Tuple(Vec<Value>),
     Object(BTreeMap<String, Value>),
     DataFrame(DataFrameValue),
+    LazyFrame(LazyFrameValue),
+    VectorStore(VectorStoreValue),
+    Image(ImageValue),
     Function(FunctionId),
     Nil,
 }
@@ -62,6 +209,9 @@ impl Value {
             Value::Tuple(_) => "tuple",
             Value::Object(_) => "object",
             Value::DataFrame(_) => "dataframe",
+            Value::LazyFrame(_) => "lazyframe",
+            Value::VectorStore(_) => "vstore",
+            Value::Image(_) => "image",
             Value::Function(_) => "function",
             Value::Nil => "nil",
         }
@@ -83,6 +233,9 @@ impl PartialEq for Value {
             (Value::Tuple(a), Value::Tuple(b)) => a == b,
             (Value::Object(a), Value::Object(b)) => a == b,
             (Value::DataFrame(a), Value::DataFrame(b)) => a == b,
+            (Value::LazyFrame(a), Value::LazyFrame(b)) => a == b,
+            (Value::VectorStore(a), Value::VectorStore(b)) => a == b,
+            (Value::Image(a), Value::Image(b)) => a == b,
             (Value::Function(a), Value::Function(b)) => a == b,
             (Value::Nil, Value::Nil) => true,
             _ => false,
@@ -128,6 +281,9 @@ impl fmt::Display for Value {
                 write!(f, "}}")
             }
             Value::DataFrame(df) => write!(f, "<dataframe rows={} cols={}>", df.rows(), df.cols()),
+            Value::LazyFrame(_) => write!(f, "<lazyframe>"),
+            Value::VectorStore(store) => write!(f, "<vstore size={}>", store.len()),
+            Value::Image(image) => write!(f, "<image {} {}>", image.path, image.media_type),
             Value::Function(id) => write!(f, "<function:{id}>"),
             Value::Nil => write!(f, "nil"),
         }
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use zip::ZipArchive;
+
+use crate::error::{SaftError, SaftResult};
+
+/// One worksheet cell. Numbers keep their stored text so callers can tell
+/// `10` from `10.5`; dates are stored by Excel as serial numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Empty,
+    Number(String),
+    Text(String),
+    Bool(bool),
+}
+
+pub enum SheetRef<'a> {
+    Index(usize),
+    Name(&'a str),
+}
+
+/// Reads one worksheet of an `.xlsx` workbook as dense rows (short rows are
+/// padded with `Cell::Empty`). Only cell values are read; styles, formulas,
+/// and merged ranges are ignored.
+pub fn read_sheet(path: &str, sheet: SheetRef<'_>) -> SaftResult<Vec<Vec<Cell>>> {
+    let file = File::open(path)
+        .map_err(|err| SaftError::new(format!("could not open workbook '{path}': {err}")))?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|err| SaftError::new(format!("'{path}' is not an xlsx workbook: {err}")))?;
+
+    let sheets = workbook_sheets(&mut archive, path)?;
+    let (name, target) = match sheet {
+        SheetRef::Index(index) => sheets.get(index).ok_or_else(|| {
+            SaftError::new(format!(
+                "workbook '{path}' has {} sheets, no sheet {index}",
+                sheets.len()
+            ))
+        })?,
+        SheetRef::Name(wanted) => {
+            sheets
+                .iter()
+                .find(|(name, _)| name == wanted)
+                .ok_or_else(|| {
+                    let names = sheets
+                        .iter()
+                        .map(|(name, _)| name.as_str())
+                        .collect::<Vec<_>>();
+                    SaftError::new(format!(
+                        "workbook '{path}' has no sheet '{wanted}' (sheets: {})",
+                        names.join(", ")
+                    ))
+                })?
+        }
+    };
+
+    let shared = match read_entry(&mut archive, "xl/sharedStrings.xml", path) {
+        Ok(xml) => shared_strings(&xml, path)?,
+        Err(_) => Vec::new(),
+    };
+    let xml = read_entry(&mut archive, target, path)?;
+    sheet_rows(&xml, &shared)
+        .map_err(|err| SaftError::new(format!("sheet '{name}' in '{path}': {err}")))
+}
+
+/// Sheet names in workbook order, with the archive path of each sheet's XML.
+fn workbook_sheets(
+    archive: &mut ZipArchive<File>,
+    path: &str,
+) -> SaftResult<Vec<(String, String)>> {
+    let rels_xml = read_entry(archive, "xl/_rels/workbook.xml.rels", path)?;
+    let mut targets = HashMap::new();
+    let mut reader = Reader::from_str(&rels_xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(tag) | Event::Empty(tag))
+                if tag.local_name().as_ref() == b"Relationship" =>
+            {
+                if let (Some(id), Some(target)) = (attr(&tag, b"Id"), attr(&tag, b"Target")) {
+                    let target = match target.strip_prefix('/') {
+                        Some(absolute) => absolute.to_string(),
+                        None => format!("xl/{target}"),
+                    };
+                    targets.insert(id, target);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(err) => return Err(xml_error(path, "workbook relationships", err)),
+        }
+    }
+
+    let workbook_xml = read_entry(archive, "xl/workbook.xml", path)?;
+    let mut sheets = Vec::new();
+    let mut reader = Reader::from_str(&workbook_xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(tag) | Event::Empty(tag)) if tag.local_name().as_ref() == b"sheet" => {
+                let target = attr(&tag, b"r:id").and_then(|id| targets.get(&id).cloned());
+                if let (Some(name), Some(target)) = (attr(&tag, b"name"), target) {
+                    sheets.push((name, target));
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(err) => return Err(xml_error(path, "workbook", err)),
+        }
+    }
+    Ok(sheets)
+}
+
+/// The shared string table; rich-text runs inside one entry are concatenated.
+fn shared_strings(xml: &str, path: &str) -> SaftResult<Vec<String>> {
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let mut in_text = false;
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(tag)) => match tag.local_name().as_ref() {
+                b"si" => current.clear(),
+                b"t" => in_text = true,
+                _ => {}
+            },
+            Ok(Event::Text(text)) if in_text => {
+                let text = text
+                    .unescape()
+                    .map_err(|err| xml_error(path, "shared strings", err))?;
+                current.push_str(&text);
+            }
+            Ok(Event::End(tag)) => match tag.local_name().as_ref() {
+                b"si" => strings.push(std::mem::take(&mut current)),
+                b"t" => in_text = false,
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(err) => return Err(xml_error(path, "shared strings", err)),
+        }
+    }
+    Ok(strings)
+}
+
+fn sheet_rows(xml: &str, shared: &[String]) -> Result<Vec<Vec<Cell>>, String> {
+    let mut rows: Vec<Vec<Cell>> = Vec::new();
+    let mut row_index = 0usize;
+    let mut column_index = 0usize;
+    let mut cell_type = String::new();
+    let mut text = String::new();
+    let mut in_value = false;
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(tag)) | Ok(Event::Empty(tag))
+                if tag.local_name().as_ref() == b"row" =>
+            {
+                row_index = match attr(&tag, b"r").and_then(|r| r.parse::<usize>().ok()) {
+                    Some(number) if number > 0 => number - 1,
+                    _ => rows.len(),
+                };
+                column_index = 0;
+            }
+            Ok(Event::Start(tag)) => match tag.local_name().as_ref() {
+                b"c" => {
+                    if let Some(column) = attr(&tag, b"r").and_then(|r| column_from_ref(&r)) {
+                        column_index = column;
+                    }
+                    cell_type = attr(&tag, b"t").unwrap_or_default();
+                    text.clear();
+                }
+                b"v" | b"t" => in_value = true,
+                _ => {}
+            },
+            Ok(Event::Text(chunk)) if in_value => {
+                text.push_str(&chunk.unescape().map_err(|err| err.to_string())?);
+            }
+            Ok(Event::End(tag)) => match tag.local_name().as_ref() {
+                b"v" | b"t" => in_value = false,
+                b"c" => {
+                    let cell = match cell_type.as_str() {
+                        _ if text.is_empty() => Cell::Empty,
+                        "s" => {
+                            let index = text
+                                .trim()
+                                .parse::<usize>()
+                                .map_err(|_| format!("invalid shared string index '{text}'"))?;
+                            let value = shared
+                                .get(index)
+                                .ok_or_else(|| format!("shared string {index} is missing"))?;
+                            Cell::Text(value.clone())
+                        }
+                        "b" => Cell::Bool(text.trim() == "1"),
+                        "str" | "inlineStr" => Cell::Text(text.clone()),
+                        "e" => Cell::Empty,
+                        _ => Cell::Number(text.trim().to_string()),
+                    };
+                    if rows.len() <= row_index {
+                        rows.resize_with(row_index + 1, Vec::new);
+                    }
+                    let row = &mut rows[row_index];
+                    if row.len() <= column_index {
+                        row.resize(column_index + 1, Cell::Empty);
+                    }
+                    row[column_index] = cell;
+                    column_index += 1;
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    for row in &mut rows {
+        row.resize(width, Cell::Empty);
+    }
+    Ok(rows)
+}
+
+/// Zero-based column of a cell reference such as `AB12`.
+fn column_from_ref(reference: &str) -> Option<usize> {
+    let mut column = 0usize;
+    let mut letters = 0;
+    for ch in reference.chars().take_while(char::is_ascii_alphabetic) {
+        column = column * 26 + (ch.to_ascii_uppercase() as usize - 'A' as usize + 1);
+        letters += 1;
+    }
+    (letters > 0).then(|| column - 1)
+}
+
+fn attr(tag: &BytesStart<'_>, name: &[u8]) -> Option<String> {
+    tag.try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|value| value.unescape_value().ok().map(|value| value.into_owned()))
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str, path: &str) -> SaftResult<String> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|err| SaftError::new(format!("workbook '{path}' is missing {name}: {err}")))?;
+    let mut xml = String::new();
+    entry
+        .read_to_string(&mut xml)
+        .map_err(|err| SaftError::new(format!("could not read {name} from '{path}': {err}")))?;
+    Ok(xml)
+}
+
+fn xml_error(path: &str, part: &str, err: impl std::fmt::Display) -> SaftError {
+    SaftError::new(format!("could not parse {part} in '{path}': {err}"))
+}
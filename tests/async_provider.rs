@@ -0,0 +1,67 @@
+#![cfg(feature = "tokio")]
+
+use orangensaft::async_provider::{AsyncPromptProvider, ProviderFuture, SyncProviderAdapter};
+use orangensaft::provider::{PromptRequest, PromptResponse, SequenceProvider};
+use orangensaft::run_source_with_async_provider;
+
+#[test]
+fn runtime_awaits_async_provider() {
+    let source = r#"
+answer = $
+    what is the answer?
+$
+assert answer == "42"
+"#;
+
+    let result = run_source_with_async_provider(source, Box::new(YieldingProvider));
+    assert!(
+        result.is_ok(),
+        "expected async provider to answer, got {result:?}"
+    );
+}
+
+#[test]
+fn sync_provider_adapter_runs_existing_providers() {
+    let source = r#"
+answer = $
+    say hi
+$
+assert answer == "hi"
+"#;
+
+    let sync = SequenceProvider::from_texts(vec!["hi".to_string()]);
+    let adapter = SyncProviderAdapter::new(Box::new(sync));
+    let result = run_source_with_async_provider(source, Box::new(adapter));
+    assert!(
+        result.is_ok(),
+        "expected adapted provider to answer, got {result:?}"
+    );
+}
+
+#[test]
+fn async_provider_inside_a_tokio_runtime_is_an_error() {
+    let executor = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("failed to build tokio runtime");
+    let result = executor.block_on(async {
+        run_source_with_async_provider("x = 1\n", Box::new(YieldingProvider))
+    });
+    let err = result.expect_err("nested runtime should be rejected");
+    assert!(
+        err.message.contains("inside a tokio runtime"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+struct YieldingProvider;
+
+impl AsyncPromptProvider for YieldingProvider {
+    fn complete(&mut self, request: PromptRequest) -> ProviderFuture<'_> {
+        Box::pin(async move {
+            tokio::task::yield_now().await;
+            assert!(request.prompt.contains("what is the answer?"));
+            Ok(PromptResponse::FinalText("42".to_string()))
+        })
+    }
+}
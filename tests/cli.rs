@@ -0,0 +1,694 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the CLI with `args`, feeding `stdin` to the process.
+fn run_cli(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_orangensaft"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run orangensaft binary");
+    child
+        .stdin
+        .take()
+        .expect("stdin should be piped")
+        .write_all(stdin.as_bytes())
+        .expect("failed to write stdin");
+    child.wait_with_output().expect("failed to wait for orangensaft")
+}
+
+#[test]
+fn scripts_can_be_read_from_stdin() {
+    let output = run_cli(&["run", "-", "--provider", "none", "--", "x"], "print(\"hi\", args())\n");
+    assert!(
+        output.status.success(),
+        "expected run - to succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hi [\"x\"]\n");
+
+    let output = run_cli(&["-", "--provider", "none"], "print(1 + 2)\n");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+
+    let output = run_cli(&["check", "-"], "x = 1\n");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "OK: <stdin>\n");
+
+    let output = run_cli(&["fmt", "-"], "x   =   1\n");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "x = 1\n");
+}
+
+#[test]
+fn stdin_errors_are_reported_against_stdin() {
+    let output = run_cli(&["check", "-"], "x = y\n");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("<stdin>"), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn arguments_after_double_dash_reach_the_script() {
+    let dir = std::env::temp_dir().join(format!("orangensaft_cli_args_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    let script = dir.join("report.saft");
+    std::fs::write(&script, "print(args())\n").expect("failed to write script");
+    let script = script.to_string_lossy().into_owned();
+
+    let output = run_cli(&[&script, "--provider", "none", "--", "data.csv", "--seed", "2024"], "");
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(
+        output.status.success(),
+        "expected shorthand run to succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "[\"data.csv\", \"--seed\", \"2024\"]\n"
+    );
+}
+
+#[test]
+fn error_format_json_emits_machine_readable_diagnostics() {
+    let output = run_cli(&["check", "-", "--error-format", "json"], "x = 1\ny = missing\n");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let diagnostic: serde_json::Value =
+        serde_json::from_str(stderr.trim()).expect("stderr should be one JSON object");
+    assert_eq!(diagnostic["severity"], "error");
+    assert_eq!(diagnostic["file"], "<stdin>");
+    assert_eq!(diagnostic["span"]["line"], 2);
+    assert_eq!(diagnostic["span"]["col"], 5);
+    assert!(
+        diagnostic["message"]
+            .as_str()
+            .is_some_and(|message| message.contains("missing")),
+        "unexpected diagnostic: {diagnostic}"
+    );
+
+    let output = run_cli(
+        &["run", "-", "--provider", "none", "--error-format", "json"],
+        "assert 1 == 2\n",
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let diagnostic: serde_json::Value =
+        serde_json::from_str(stderr.trim()).expect("stderr should be one JSON object");
+    assert_eq!(diagnostic["span"]["line"], 1);
+}
+
+#[test]
+fn diagnostics_are_colored_only_when_requested() {
+    let output = run_cli(&["check", "-"], "y = missing\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("error: "), "unexpected stderr: {stderr}");
+    assert!(!stderr.contains('\x1b'), "piped stderr should be plain: {stderr:?}");
+
+    let err = orangensaft::error::SaftError::with_span(
+        "boom",
+        orangensaft::error::Span::new(4, 11, 1, 5),
+    );
+    let source = "y = missing\n";
+    let colored = err.render_styled("a.saft", source, true);
+    assert!(colored.starts_with("\x1b[1;31merror\x1b[0m: "), "{colored:?}");
+    assert!(colored.contains("\x1b[34ma.saft:1:5\x1b[0m"), "{colored:?}");
+    assert!(colored.contains("\x1b[1;31m^^^^^^^\x1b[0m"), "{colored:?}");
+    assert_eq!(err.render_styled("a.saft", source, false), err.render("a.saft", source));
+}
+
+#[test]
+fn fmt_checks_and_writes_whole_directories() {
+    let dir = std::env::temp_dir().join(format!("orangensaft_cli_fmt_{}", std::process::id()));
+    let nested = dir.join("nested");
+    std::fs::create_dir_all(&nested).expect("failed to create temp dirs");
+    std::fs::write(dir.join("a.saft"), "x = 1\n").expect("failed to write a.saft");
+    std::fs::write(dir.join("b.saft"), "y   =   2\n").expect("failed to write b.saft");
+    std::fs::write(nested.join("c.saft"), "z =3\n").expect("failed to write c.saft");
+    std::fs::write(nested.join("notes.txt"), "not   saft\n").expect("failed to write notes");
+    let root = dir.to_string_lossy().into_owned();
+
+    let check = run_cli(&["fmt", &root, "--check"], "");
+    let stdout = String::from_utf8_lossy(&check.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&check.stderr).into_owned();
+
+    let write = run_cli(&["fmt", "--write", &root], "");
+    let rewritten = std::fs::read_to_string(nested.join("c.saft")).expect("c.saft should exist");
+    let recheck = run_cli(&["fmt", &root, "--check"], "");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(!check.status.success());
+    assert!(stdout.contains("OK: ") && stdout.contains("a.saft"), "stdout: {stdout}");
+    assert!(stderr.contains("b.saft") && stderr.contains("c.saft"), "stderr: {stderr}");
+    assert!(stderr.contains("fmt failed for 2 of 3 files"), "stderr: {stderr}");
+    assert!(write.status.success());
+    assert_eq!(rewritten, "z = 3\n");
+    assert!(recheck.status.success());
+}
+
+#[test]
+fn bundled_examples_are_formatted() {
+    let examples = concat!(env!("CARGO_MANIFEST_DIR"), "/examples");
+    let output = run_cli(&["fmt", examples, "--check"], "");
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn fmt_stdin_prints_formatted_source_and_honors_check() {
+    let output = run_cli(&["fmt", "--stdin"], "x   =   [1,2]\n");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "x = [1, 2]\n");
+
+    let output = run_cli(&["fmt", "--stdin", "--check"], "x   =   1\n");
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "not formatted: <stdin>\n");
+
+    let output = run_cli(&["fmt", "--stdin", "--check"], "x = 1\n");
+    assert!(output.status.success());
+
+    let output = run_cli(&["fmt", "--stdin"], "x = (\n");
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn check_validates_many_files_with_a_combined_exit_code() {
+    let dir = std::env::temp_dir().join(format!("orangensaft_cli_check_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib")).expect("failed to create temp dirs");
+    std::fs::write(dir.join("good.saft"), "x = 1\n").expect("failed to write good.saft");
+    std::fs::write(dir.join("lib").join("bad.saft"), "y = missing\n").expect("failed to write bad.saft");
+    let root = dir.to_string_lossy().into_owned();
+    let good = dir.join("good.saft").to_string_lossy().into_owned();
+
+    let all = run_cli(&["check", &root], "");
+    let only_good = run_cli(&["check", &good, &good], "");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(!all.status.success());
+    let stdout = String::from_utf8_lossy(&all.stdout);
+    let stderr = String::from_utf8_lossy(&all.stderr);
+    assert!(stdout.contains("good.saft"), "stdout: {stdout}");
+    assert!(stderr.contains("bad.saft"), "stderr: {stderr}");
+    assert!(stderr.contains("check failed for 1 of 2 files"), "stderr: {stderr}");
+
+    assert!(only_good.status.success());
+    assert_eq!(String::from_utf8_lossy(&only_good.stdout).lines().count(), 2);
+}
+
+#[test]
+fn exit_codes_distinguish_failure_classes() {
+    let code = |args: &[&str], stdin: &str| run_cli(args, stdin).status.code();
+
+    assert_eq!(code(&["run", "-", "--provider", "none"], "x = 1\n"), Some(0));
+    assert_eq!(code(&["run", "-", "--bogus"], ""), Some(2));
+    assert_eq!(code(&["check", "-"], "x = (\n"), Some(3));
+    assert_eq!(code(&["run", "-", "--provider", "none"], "y = missing\n"), Some(3));
+    assert_eq!(code(&["run", "-", "--provider", "none"], "x = 1 / 0\n"), Some(4));
+    assert_eq!(
+        code(&["run", "-", "--provider", "none"], "x: string = $hello$\n"),
+        Some(5)
+    );
+    assert_eq!(code(&["run", "-", "--provider", "none"], "assert 1 == 2\n"), Some(6));
+    assert_eq!(code(&["fmt", "--stdin", "--check"], "x   =   1\n"), Some(1));
+    assert_eq!(
+        code(
+            &["run", "-", "--provider", "none", "--timeout", "1"],
+            "for i in range(5000000):\n    x = i\nfor i in range(5000000):\n    x = i\n"
+        ),
+        Some(7)
+    );
+}
+
+#[test]
+fn verbose_logs_prompt_rounds_and_tool_calls() {
+    let script = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/06_function_map.saft");
+    let output = run_cli(&["run", script, "-v"], "");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("round 1/8 via default provider") && stderr.contains("-> 3 tool call(s)"),
+        "stderr: {stderr}"
+    );
+    assert!(
+        stderr.contains(r#"tool call 1/32: my_func({"verb":"build"})"#),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("round 2/8"), "stderr: {stderr}");
+
+    let quiet = run_cli(&["run", script], "");
+    assert!(quiet.stderr.is_empty());
+
+    let logged = Command::new(env!("CARGO_BIN_EXE_orangensaft"))
+        .args(["run", script])
+        .env("ORANGENSAFT_LOG", "1")
+        .output()
+        .expect("failed to run orangensaft binary");
+    assert!(String::from_utf8_lossy(&logged.stderr).contains("round 1/8"));
+}
+
+#[test]
+fn tool_approvals_leave_later_stdin_lines_for_input() {
+    let dir = std::env::temp_dir().join(format!("orangensaft_cli_approve_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    let script = dir.join("approve.saft");
+    let source = include_str!("../examples/06_function_map.saft");
+    std::fs::write(&script, format!("{source}print(\"got: \" + str(input()))\n"))
+        .expect("failed to write script");
+    let script = script.to_string_lossy().into_owned();
+
+    let output = run_cli(&["run", &script, "--approve-tools"], "y\ny\ny\nhello\n");
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stdout).ends_with("got: hello\n"),
+        "stdout: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn init_scaffolds_a_runnable_project_without_overwriting() {
+    let dir = std::env::temp_dir().join(format!("orangensaft_cli_init_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let root = dir.to_string_lossy().into_owned();
+
+    let created = run_cli(&["init", &root], "");
+    let entry = dir.join("examples").join("main.saft");
+    let ran = run_cli(&["run", entry.to_string_lossy().as_ref()], "");
+    let smoke = dir.join("tests").join("smoke.saft");
+    let smoke_ran = run_cli(&["run", smoke.to_string_lossy().as_ref(), "--provider", "none"], "");
+    let config = std::fs::read_to_string(dir.join("orangensaft.toml")).unwrap_or_default();
+    let gitignore = std::fs::read_to_string(dir.join(".gitignore")).unwrap_or_default();
+    let again = run_cli(&["init", &root], "");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(
+        created.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&created.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&created.stdout).lines().count(), 4);
+    assert_eq!(String::from_utf8_lossy(&ran.stdout), "total: 5\n");
+    assert!(smoke_ran.status.success());
+    assert!(config.contains("provider = \"mock\""), "config: {config}");
+    assert!(gitignore.contains(".orangensaft/"), "gitignore: {gitignore}");
+    assert!(!again.status.success());
+    assert!(String::from_utf8_lossy(&again.stderr).contains("init would overwrite existing files"));
+}
+
+#[test]
+fn doc_lists_builtins_and_describes_one() {
+    let output = run_cli(&["doc", "split"], "");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "split(string, sep: string) -> [string]\n  Splits a string on every occurrence of `sep`.\n  arguments: 2\n"
+    );
+
+    let output = run_cli(&["doc"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("upper(string) -> string\n"), "stdout: {stdout}");
+    assert!(stdout.contains("\n\nsql(query: string, tables: object) -> dataframe\n"));
+    assert!(stdout.contains("arguments: at least 1"), "stdout: {stdout}");
+
+    let output = run_cli(&["doc", "nope"], "");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown builtin 'nope'"));
+}
+
+#[test]
+fn project_config_sets_run_defaults_below_env_and_flags() {
+    let dir = std::env::temp_dir().join(format!("orangensaft_cli_config_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("scripts")).expect("failed to create temp dir");
+    let config = dir.join("orangensaft.toml");
+    std::fs::write(
+        &config,
+        "# project defaults\n[run]\nprovider = \"none\"  # offline by default\nmax_tool_rounds = 3\n",
+    )
+    .expect("failed to write config");
+    let script = dir.join("scripts/greet.saft");
+    std::fs::write(&script, "x = 2\ny = 3\ntotal: int = $what is {x} + {y}?$\nprint(total)\n")
+        .expect("failed to write script");
+    let script = script.to_string_lossy().into_owned();
+
+    let from_config = run_cli(&["run", &script], "");
+    assert_eq!(from_config.status.code(), Some(5));
+
+    let from_flag = run_cli(&["run", &script, "--provider", "mock", "-v"], "");
+    assert!(
+        from_flag.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&from_flag.stderr)
+    );
+    assert!(String::from_utf8_lossy(&from_flag.stderr).contains("round 1/3"));
+
+    let from_env = Command::new(env!("CARGO_BIN_EXE_orangensaft"))
+        .args(["run", &script])
+        .env("ORANGENSAFT_PROVIDER", "mock")
+        .output()
+        .expect("failed to run orangensaft binary");
+    assert!(from_env.status.success());
+
+    let failures = [
+        ("[run]\nmax_tool_rounds = \"many\"\n", "max_tool_rounds in"),
+        ("[run]\nallow_net = true\n", "pass --allow-net on the command line"),
+        ("[run]\nprovidr = \"mock\"\n", "unknown key 'providr' in [run]"),
+        ("[cache]\ndir = \".cache\"\n", "unknown section [cache]"),
+        ("[run]\nprovider = mock\n", "orangensaft.toml:2: invalid value 'mock'"),
+    ];
+    for (source, expected) in failures {
+        std::fs::write(&config, source).expect("failed to write config");
+        let output = run_cli(&["run", &script], "");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(2), "stderr: {stderr}");
+        assert!(stderr.contains(expected), "expected {expected:?} in: {stderr}");
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn help_version_and_typo_suggestions() {
+    let help = run_cli(&["run", "--help"], "");
+    assert!(help.status.success());
+    let stdout = String::from_utf8_lossy(&help.stdout);
+    assert!(stdout.contains("orangensaft run <file.saft>"), "stdout: {stdout}");
+    assert!(stdout.contains("--max-tool-rounds N"), "stdout: {stdout}");
+    assert!(!stdout.contains("--write"), "stdout: {stdout}");
+
+    let top = run_cli(&["--help"], "");
+    assert!(top.status.success());
+    assert!(String::from_utf8_lossy(&top.stdout).contains("orangensaft fmt --stdin"));
+
+    let version = run_cli(&["--version"], "");
+    assert_eq!(
+        String::from_utf8_lossy(&version.stdout),
+        format!("orangensaft {}\n", env!("CARGO_PKG_VERSION"))
+    );
+
+    let typo = run_cli(&["run", "-", "--modle", "x"], "");
+    assert_eq!(typo.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&typo.stderr);
+    assert!(stderr.contains("did you mean '--model'?"), "stderr: {stderr}");
+
+    let command = run_cli(&["chek", "report.saft"], "");
+    assert_eq!(command.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&command.stderr);
+    assert!(stderr.contains("did you mean 'check'?"), "stderr: {stderr}");
+}
+
+#[test]
+fn bench_reports_timings_and_prompt_rounds() {
+    let script = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/06_function_map.saft");
+    let output = run_cli(&["bench", script, "--iterations", "3"], "");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stderr.contains("(3 iterations)"), "stderr: {stderr}");
+    for label in ["min", "mean", "max"] {
+        assert!(stderr.contains(&format!("\n  {label} ")), "stderr: {stderr}");
+    }
+    assert!(stderr.contains("prompt rounds per run: 2"), "stderr: {stderr}");
+
+    let live = run_cli(&["bench", script, "--provider", "openrouter"], "");
+    assert_eq!(live.status.code(), Some(2));
+
+    let dir = std::env::temp_dir().join(format!("orangensaft_cli_bench_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    let failing = dir.join("failing.saft");
+    std::fs::write(&failing, "print(args())\nassert len(args()) == 0\n")
+        .expect("failed to write script");
+    let failing = failing.to_string_lossy().into_owned();
+    let output = run_cli(&["bench", &failing, "--", "x"], "");
+    assert_eq!(output.status.code(), Some(6));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[\"x\"]\n");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cache_subcommand_lists_and_clears_run_cache() {
+    let dir = std::env::temp_dir().join(format!("orangensaft_cli_cache_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache_dir = dir.to_string_lossy().into_owned();
+    let script = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/06_function_map.saft");
+    let cli = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_orangensaft"))
+            .args(args)
+            .env("ORANGENSAFT_CACHE_DIR", &cache_dir)
+            .output()
+            .expect("failed to run orangensaft binary")
+    };
+
+    assert!(cli(&["run", script, "--cache"]).status.success());
+    assert!(cli(&["run", script, "--cache"]).status.success());
+
+    let listing = cli(&["cache", "ls"]);
+    let stdout = String::from_utf8_lossy(&listing.stdout);
+    assert_eq!(stdout.lines().count(), 2, "stdout: {stdout}");
+    assert!(stdout.lines().all(|line| line.contains("  mock  ")), "stdout: {stdout}");
+
+    let stats = cli(&["cache", "stats"]);
+    assert!(String::from_utf8_lossy(&stats.stdout).contains(": 2 entries,"));
+
+    assert_eq!(cli(&["cache", "prune"]).status.code(), Some(2));
+    let cleared = cli(&["cache", "clear"]);
+    assert_eq!(
+        String::from_utf8_lossy(&cleared.stdout),
+        format!("removed 2 entries from {cache_dir}\n")
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn vars_are_bound_as_typed_globals() {
+    let script = "print(city, limit + 1, ratio * 2, verbose, tags[1])\n";
+    let output = run_cli(
+        &[
+            "run",
+            "-",
+            "--provider",
+            "none",
+            "--var",
+            "city=berlin",
+            "--var",
+            "limit:int=10",
+            "--var",
+            "ratio:float=0.25",
+            "--var",
+            "verbose:bool=true",
+            "--var",
+            "tags:json=[\"a\", \"b\"]",
+        ],
+        script,
+    );
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "berlin 11 0.5 true b\n");
+
+    let missing = run_cli(&["run", "-", "--var", "city=berlin"], script);
+    assert_eq!(missing.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("undefined name 'limit'"));
+
+    let check = run_cli(
+        &["check", "-", "--var", "city", "--var", "limit", "--var", "ratio"],
+        "print(city, limit, ratio)\n",
+    );
+    assert!(check.status.success());
+}
+
+#[test]
+fn providers_test_reports_status_and_fails_on_default_provider() {
+    let mock = run_cli(&["providers", "test", "--provider", "mock"], "");
+    assert!(mock.status.success());
+    let stdout = String::from_utf8_lossy(&mock.stdout);
+    assert!(stdout.starts_with("PROVIDER"), "stdout: {stdout}");
+    assert!(stdout.contains("mock*"), "stdout: {stdout}");
+
+    let fixture = run_cli(
+        &["providers", "test", "--provider", "fixture:missing_fixture.json"],
+        "",
+    );
+    assert_eq!(fixture.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&fixture.stdout).contains("FAIL"));
+    assert!(
+        String::from_utf8_lossy(&fixture.stderr)
+            .contains("default provider 'fixture:missing_fixture.json' failed 1 check(s)")
+    );
+
+    let ollama = Command::new(env!("CARGO_BIN_EXE_orangensaft"))
+        .args(["providers", "test", "--provider", "ollama"])
+        .env("OLLAMA_HOST", "http://127.0.0.1:9")
+        .output()
+        .expect("failed to run orangensaft binary");
+    assert_eq!(ollama.status.code(), Some(5));
+    let stdout = String::from_utf8_lossy(&ollama.stdout);
+    assert!(stdout.contains("endpoint  FAIL"), "stdout: {stdout}");
+    assert!(stdout.contains("model     skipped"), "stdout: {stdout}");
+
+    assert_eq!(run_cli(&["providers"], "").status.code(), Some(2));
+}
+
+#[test]
+fn compiled_artifacts_run_without_the_source() {
+    let dir = std::env::temp_dir().join(format!("orangensaft_cli_compile_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let script = dir.join("greet.saft");
+    std::fs::write(&script, "print(\"hi \" + name)\nassert name != \"bob\"\n").expect("write script");
+    let script = script.to_string_lossy().into_owned();
+    let artifact = dir.join("greet.saftc").to_string_lossy().into_owned();
+
+    assert_eq!(run_cli(&["compile", &script], "").status.code(), Some(3));
+    let compiled = run_cli(&["compile", &script, "--var", "name"], "");
+    assert!(String::from_utf8_lossy(&compiled.stdout).starts_with("compiled: "));
+    let again = run_cli(&["compile", &script, "--var", "name"], "");
+    assert_eq!(
+        String::from_utf8_lossy(&again.stdout),
+        format!("up to date: {artifact}\n")
+    );
+
+    std::fs::remove_file(&script).expect("remove source");
+    let ran = run_cli(&["run", &artifact, "--var", "name=ada"], "");
+    assert!(ran.status.success());
+    assert_eq!(String::from_utf8_lossy(&ran.stdout), "hi ada\n");
+
+    let failed = run_cli(&["run", &artifact, "--var", "name=bob"], "");
+    assert_eq!(failed.status.code(), Some(6));
+    assert!(String::from_utf8_lossy(&failed.stderr).contains("assert name != \"bob\""));
+
+    let unbound = run_cli(&["run", &artifact], "");
+    assert_eq!(unbound.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&unbound.stderr).contains("compiled with --var name"));
+
+    let stale = std::fs::read_to_string(&artifact)
+        .expect("read artifact")
+        .replace(env!("CARGO_PKG_VERSION"), "0.0.0-old");
+    std::fs::write(&artifact, stale).expect("write artifact");
+    let rejected = run_cli(&["run", &artifact, "--var", "name=ada"], "");
+    assert_eq!(rejected.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&rejected.stderr).contains("recompile it"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn fmt_and_autofmt_take_indent_width_from_project_config_and_flags() {
+    let dir = std::env::temp_dir().join(format!("orangensaft_cli_fmt_config_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    std::fs::write(dir.join("orangensaft.toml"), "[fmt]\nindent_width = 2\n")
+        .expect("failed to write config");
+    std::fs::write(dir.join("loop.saft"), "for x in [1, 2]:\n    print(x)\n")
+        .expect("failed to write script");
+    let fmt = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_orangensaft"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .expect("failed to run orangensaft binary")
+    };
+
+    let from_config = fmt(&["fmt", "loop.saft"]);
+    assert_eq!(
+        String::from_utf8_lossy(&from_config.stdout),
+        "for x in [1, 2]:\n  print(x)\n"
+    );
+    let from_flag = fmt(&["fmt", "loop.saft", "--indent-width", "3"]);
+    assert_eq!(
+        String::from_utf8_lossy(&from_flag.stdout),
+        "for x in [1, 2]:\n   print(x)\n"
+    );
+
+    // `--autofmt` formats with the same `[fmt]` options, so diagnostics point
+    // at the two-space layout.
+    std::fs::write(dir.join("broken.saft"), "for x in [1, 2]:\n    print(missing)\n")
+        .expect("failed to write script");
+    for command in ["check", "run"] {
+        let autofmt = fmt(&[command, "broken.saft", "--autofmt"]);
+        assert_eq!(autofmt.status.code(), Some(3), "{command}");
+        assert!(
+            String::from_utf8_lossy(&autofmt.stderr).contains("2 |   print(missing)"),
+            "{command}: {}",
+            String::from_utf8_lossy(&autofmt.stderr)
+        );
+    }
+
+    std::fs::write(dir.join("orangensaft.toml"), "[fmt]\nindent = 2\n")
+        .expect("failed to write config");
+    let unknown = fmt(&["fmt", "loop.saft"]);
+    assert_eq!(unknown.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&unknown.stderr).contains("unknown key 'indent' in [fmt]"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn openrouter_without_api_key_reports_the_missing_key() {
+    let script = "x = $\n    say hi\n$ with {provider: \"openrouter\"}\n";
+    let output = run_cli(
+        &[
+            "run",
+            "-",
+            "--provider",
+            "mock",
+            "--api-key-env",
+            "ORANGENSAFT_TEST_UNSET_KEY",
+        ],
+        script,
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(5), "{stderr}");
+    assert!(
+        stderr.contains("missing API key in env var 'ORANGENSAFT_TEST_UNSET_KEY'"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("unknown provider"), "{stderr}");
+}
+
+#[test]
+fn config_is_discovered_next_to_the_script() {
+    let root = std::env::temp_dir().join(format!(
+        "orangensaft_cli_config_dir_{}",
+        std::process::id()
+    ));
+    let project = root.join("sub");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&project).expect("failed to create temp dir");
+    std::fs::write(project.join("orangensaft.toml"), "[fmt]\nindent_width = 2\n")
+        .expect("failed to write config");
+    std::fs::write(project.join("broken.saft"), "for x in [1, 2]:\n    print(missing)\n")
+        .expect("failed to write script");
+    let cli = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_orangensaft"))
+            .args(args)
+            .current_dir(&root)
+            .output()
+            .expect("failed to run orangensaft binary")
+    };
+
+    // Run from the parent directory: `sub/orangensaft.toml` still applies.
+    let formatted = cli(&["fmt", "sub/broken.saft"]);
+    assert_eq!(
+        String::from_utf8_lossy(&formatted.stdout),
+        "for x in [1, 2]:\n  print(missing)\n"
+    );
+    for command in ["check", "run"] {
+        let autofmt = cli(&[command, "sub/broken.saft", "--autofmt"]);
+        assert!(
+            String::from_utf8_lossy(&autofmt.stderr).contains("2 |   print(missing)"),
+            "{command}: {}",
+            String::from_utf8_lossy(&autofmt.stderr)
+        );
+    }
+    let _ = std::fs::remove_dir_all(&root);
+}
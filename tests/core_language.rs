@@ -1,4 +1,9 @@
-use orangensaft::{check_source, run_source};
+use std::time::{Duration, Instant};
+
+use orangensaft::error::{ErrorKind, SaftResult};
+use orangensaft::provider::{NoopProvider, PromptProvider, PromptRequest, PromptResponse};
+use orangensaft::runtime::RuntimeOptions;
+use orangensaft::{check_source, run_source, run_source_with_provider_and_options};
 
 #[test]
 fn runs_basic_assignments_example() {
@@ -47,3 +52,101 @@ assert report.meta.title == "ok"
         "expected multiline object schema assignment to run, got {result:?}"
     );
 }
+
+#[test]
+fn wrapped_calls_and_literals_accept_trailing_commas() {
+    let source = r#"
+f pair(a, b):
+    ret [
+        a,
+        b,
+    ]
+
+row = pair(
+    {name: "ada", langs: ["en", "de",],},
+    2,
+)
+assert row[0].langs == ["en", "de"]
+assert row[1] == 2
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected wrapped calls and literals to run, got {result:?}"
+    );
+}
+
+#[test]
+fn wrapped_object_schemas_accept_trailing_commas() {
+    let source = r#"
+f label(row: {
+    name: string,
+    langs: [string],
+}) -> {
+    text: string,
+}:
+    ret {text: row.name}
+
+out: {text: string,} = label({name: "ada", langs: []})
+assert out.text == "ada"
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected wrapped object schemas to run, got {result:?}"
+    );
+}
+
+#[test]
+fn timeout_stops_runaway_loops_without_a_span() {
+    let source = r#"
+f spin(x):
+    ret x + 1
+
+total = 0
+for i in range(5000000):
+    total = spin(total)
+"#;
+    let options = RuntimeOptions {
+        timeout: Some(Duration::from_millis(50)),
+        ..RuntimeOptions::default()
+    };
+    let err = run_source_with_provider_and_options(source, Box::new(NoopProvider), options)
+        .expect_err("the loop should outlive the timeout");
+    assert_eq!(err.kind, ErrorKind::Timeout);
+    assert_eq!(err.span, None);
+    assert_eq!(err.message, "script exceeded its timeout of 50ms");
+}
+
+#[test]
+fn timeout_interrupts_sleep_and_bounds_provider_requests() {
+    let options = RuntimeOptions {
+        timeout: Some(Duration::from_millis(50)),
+        ..RuntimeOptions::default()
+    };
+    let started = Instant::now();
+    let err = run_source_with_provider_and_options("sleep(5)\n", Box::new(NoopProvider), options)
+        .expect_err("sleep should be cut short by the timeout");
+    assert_eq!(err.kind, ErrorKind::Timeout);
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    // The provider sees the remaining budget, and a last statement that ends
+    // past the deadline is still reported.
+    let err =
+        run_source_with_provider_and_options("x = $ hello $\n", Box::new(SlowProvider), options)
+            .expect_err("the slow request should outlive the timeout");
+    assert_eq!(err.kind, ErrorKind::Timeout);
+}
+
+struct SlowProvider;
+
+impl PromptProvider for SlowProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        let timeout = request.timeout.expect("the run has a timeout");
+        assert!(timeout <= Duration::from_millis(50), "got {timeout:?}");
+        std::thread::sleep(Duration::from_millis(100));
+        Ok(PromptResponse::FinalText("hi".to_string()))
+    }
+}
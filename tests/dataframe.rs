@@ -5,7 +5,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use orangensaft::error::SaftResult;
 use orangensaft::provider::{PromptProvider, PromptRequest, PromptResponse};
-use orangensaft::run_source_with_provider;
+use orangensaft::{run_source, run_source_with_provider};
 
 #[test]
 fn polars_dataframe_builtins_work() {
@@ -46,7 +46,7 @@ assert shape(small).1 == 2
         path = saft_string(csv_path.as_path()),
     );
 
-    let result = run_source_with_provider(source.as_str(), Box::new(InspectingProvider::default()));
+    let result = run_source_with_provider(source.as_str(), Box::new(InspectingProvider));
     let _ = fs::remove_file(&csv_path);
 
     assert!(
@@ -74,7 +74,7 @@ assert answer == "points"
         path = saft_string(csv_path.as_path()),
     );
 
-    let result = run_source_with_provider(source.as_str(), Box::new(InspectingProvider::default()));
+    let result = run_source_with_provider(source.as_str(), Box::new(InspectingProvider));
     let _ = fs::remove_file(&csv_path);
 
     assert!(
@@ -83,6 +83,646 @@ assert answer == "points"
     );
 }
 
+#[test]
+fn filter_keeps_matching_rows() {
+    let result = run_with_csv(
+        "filter",
+        "city,temp,score\nalpha,10,0.2\nbeta,20,0.8\ngamma,30,0.6\ndelta,,0.9\n",
+        r#"
+warm = filter(df, "temp", ">=", 20)
+assert shape(warm).0 == 2
+assert head(warm)[0]["city"] == "beta"
+
+assert shape(filter(df, "score", ">", 0.5)).0 == 3
+assert shape(filter(df, "temp", "==", 10.0)).0 == 1
+assert shape(filter(df, "temp", "==", nil)).0 == 1
+assert shape(filter(df, "temp", "!=", 10)).0 == 3
+assert shape(filter(df, "city", "contains", "ta")).0 == 2
+assert shape(filter(df, "city", "in", ["alpha", "gamma"])).0 == 2
+assert mean(filter(df, "score", "<", 0.7), "temp") == 20.0
+
+assert filter([1, 2, 3], is_int) == [1, 2, 3]
+"#,
+    );
+    assert!(result.is_ok(), "expected dataframe filter to work, got {result:?}");
+}
+
+#[test]
+fn filter_rejects_unknown_operator_and_column() {
+    let csv = "city,temp\nalpha,10\n";
+    let err = run_with_csv("filter_op", csv, "x = filter(df, \"temp\", \"~\", 1)\n")
+        .expect_err("unknown operator should fail");
+    assert!(
+        err.message.contains("filter got unknown operator '~'"),
+        "unexpected error: {}",
+        err.message
+    );
+    let err = run_with_csv("filter_col", csv, "x = filter(df, \"nope\", \"==\", 1)\n")
+        .expect_err("missing column should fail");
+    assert!(err.message.contains("filter failed"), "unexpected error: {}", err.message);
+
+    // Same polars comparison as on a lazyframe: a kind mismatch fails instead of matching nothing.
+    let err = run_with_csv("filter_kind", csv, "x = filter(df, \"temp\", \">\", \"x\")\n")
+        .expect_err("comparing ints with a string should fail");
+    assert!(
+        err.message.contains("filter failed") && err.message.contains("cannot compare"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn group_by_aggregates_per_key() {
+    let result = run_with_csv(
+        "group_by",
+        "city,temp,score\nberlin,10,0.2\nparis,20,0.8\nberlin,14,0.6\nparis,22,0.4\nrome,30,0.9\n",
+        r#"
+summary = group_by(df, ["city"], {temp: "mean", score: "max"})
+assert shape(summary) == (3, 3)
+assert columns(summary) == ["city", "score", "temp"]
+rows = head(summary)
+assert rows[0] == {city: "berlin", temp: 12.0, score: 0.6}
+assert rows[1]["city"] == "paris"
+assert rows[2]["temp"] == 30.0
+
+counts = group_by(df, ["city"], {temp: "count"})
+assert head(counts)[0]["temp"] == 2
+"#,
+    );
+    assert!(result.is_ok(), "expected group_by to work, got {result:?}");
+
+    let err = run_with_csv("group_by_agg", "city,temp\na,1\n", "x = group_by(df, [\"city\"], {temp: \"avg\"})\n")
+        .expect_err("unknown aggregation should fail");
+    assert!(
+        err.message.contains("group_by got unknown aggregation 'avg' for column 'temp'"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn sort_df_orders_rows() {
+    let result = run_with_csv(
+        "sort_df",
+        "city,temp\nparis,20\nberlin,14\nrome,30\nberlin,10\n",
+        r#"
+rows = head(sort_df(df, "temp"))
+assert [rows[0]["temp"], rows[1]["temp"], rows[3]["temp"]] == [10, 14, 30]
+
+rows = head(sort_df(df, "temp", true))
+assert rows[0]["city"] == "rome"
+
+rows = head(sort_df(df, ["city", "temp"], true))
+assert [rows[0]["city"], rows[2]["temp"], rows[3]["temp"]] == ["rome", 14, 10]
+"#,
+    );
+    assert!(result.is_ok(), "expected sort_df to work, got {result:?}");
+}
+
+#[test]
+fn with_column_derives_values_per_row() {
+    let result = run_with_csv(
+        "with_column",
+        "item,price,qty\napple,0.5,4\npear,1.25,2\nfig,2,\n",
+        r#"
+f total(row):
+    if row.qty == nil:
+        ret nil
+    ret row.price * row.qty
+
+f label(row):
+    ret upper(row.item)
+
+out = with_column(df, "total", total)
+out = with_column(out, "label", label)
+out = with_column(out, "rank", [3, 1, 2])
+assert columns(out) == ["item", "price", "qty", "total", "label", "rank"]
+rows = head(out)
+assert rows[0]["total"] == 2.0
+assert rows[1]["label"] == "PEAR"
+assert rows[2]["total"] == nil
+assert sum(out, "rank") == 6.0
+
+replaced = with_column(df, "qty", [1, 1, 1])
+assert shape(replaced) == (3, 3)
+assert sum(replaced, "qty") == 3.0
+"#,
+    );
+    assert!(result.is_ok(), "expected with_column to work, got {result:?}");
+
+    let err = run_with_csv("with_column_len", "a\n1\n2\n", "x = with_column(df, \"b\", [1])\n")
+        .expect_err("length mismatch should fail");
+    assert!(
+        err.message.contains("with_column expects 2 values, got 1"),
+        "unexpected error: {}",
+        err.message
+    );
+    let err = run_with_csv("with_column_mix", "a\n1\n2\n", "x = with_column(df, \"b\", [1, \"x\"])\n")
+        .expect_err("mixed kinds should fail");
+    assert!(
+        err.message.contains("with_column cannot mix int and string values in column 'b'"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn drop_and_rename_clean_up_columns() {
+    let result = run_with_csv(
+        "drop_rename",
+        "id,city,temp_c,internal\n1,berlin,10,x\n2,paris,20,y\n",
+        r#"
+clean = rename(drop(df, ["id", "internal"]), {temp_c: "temperature"})
+assert columns(clean) == ["city", "temperature"]
+assert head(clean)[1]["temperature"] == 20
+assert columns(df) == ["id", "city", "temp_c", "internal"]
+"#,
+    );
+    assert!(result.is_ok(), "expected drop/rename to work, got {result:?}");
+
+    let err = run_with_csv("drop_missing", "a\n1\n", "x = drop(df, [\"b\"])\n")
+        .expect_err("dropping a missing column should fail");
+    assert!(err.message.contains("drop failed"), "unexpected error: {}", err.message);
+    let err = run_with_csv("rename_missing", "a\n1\n", "x = rename(df, {b: \"c\"})\n")
+        .expect_err("renaming a missing column should fail");
+    assert!(err.message.contains("rename failed"), "unexpected error: {}", err.message);
+}
+
+#[test]
+fn distinct_removes_duplicate_rows() {
+    let result = run_with_csv(
+        "distinct",
+        "city,temp\nberlin,10\nparis,20\nberlin,10\nberlin,12\n",
+        r#"
+assert shape(distinct(df)) == (3, 2)
+firsts = distinct_on(df, ["city"])
+assert shape(firsts) == (2, 2)
+assert head(firsts)[0] == {city: "berlin", temp: 10}
+assert head(firsts)[1]["city"] == "paris"
+"#,
+    );
+    assert!(result.is_ok(), "expected distinct to work, got {result:?}");
+}
+
+#[test]
+fn describe_profiles_every_column() {
+    let result = run_with_csv(
+        "describe",
+        "city,temp,score\nberlin,10,0.2\nparis,,0.8\nrome,30,0.5\n",
+        r#"
+summary = describe(df)
+assert columns(summary) == ["column", "count", "nulls", "mean", "std", "min", "max"]
+rows = head(summary)
+assert rows[0] == {column: "city", count: 3, nulls: 0, mean: nil, std: nil, min: nil, max: nil}
+assert rows[1]["count"] == 2
+assert rows[1]["nulls"] == 1
+assert rows[1]["mean"] == 20.0
+assert rows[1]["max"] == 30.0
+assert round(rows[2]["std"], 2) == 0.3
+"#,
+    );
+    assert!(result.is_ok(), "expected describe to work, got {result:?}");
+}
+
+#[test]
+fn write_csv_round_trips_and_honours_options() {
+    let out_path = temp_csv_path("write_csv_out");
+    let out = saft_string(out_path.as_path());
+    let result = run_with_csv(
+        "write_csv",
+        "city,temp\nberlin,10\nparis,20\n",
+        &format!(
+            r#"
+write_csv(df, "{out}")
+assert head(read("{out}")) == head(df)
+write_csv(df, "{out}", {{header: false, sep: ";"}})
+assert read_text("{out}") == "berlin;10\nparis;20\n"
+"#
+        ),
+    );
+    let _ = fs::remove_file(&out_path);
+    assert!(result.is_ok(), "expected write_csv to work, got {result:?}");
+}
+
+#[test]
+fn parquet_round_trips_column_types() {
+    let out_path = temp_csv_path("parquet_out").with_extension("parquet");
+    let out = saft_string(out_path.as_path());
+    let result = run_with_csv(
+        "parquet",
+        "city,temp,score\nberlin,10,0.5\nparis,,0.8\n",
+        &format!(
+            r#"
+write_parquet(df, "{out}")
+back = read_parquet("{out}")
+assert shape(back) == (2, 3)
+assert head(back) == head(df)
+assert head(back)[1]["temp"] == nil
+"#
+        ),
+    );
+    let _ = fs::remove_file(&out_path);
+    assert!(result.is_ok(), "expected parquet round trip, got {result:?}");
+}
+
+#[test]
+fn read_excel_loads_sheets_by_name_or_index() {
+    let path = temp_csv_path("excel").with_extension("xlsx");
+    write_xlsx(
+        &path,
+        &[
+            (
+                "Scores",
+                r#"<row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c><c r="C1" t="inlineStr"><is><t>ok</t></is></c></row>
+<row r="2"><c r="A2" t="s"><v>2</v></c><c r="B2"><v>10</v></c><c r="C2" t="b"><v>1</v></c></row>
+<row r="3"><c r="A3" t="s"><v>3</v></c><c r="C3" t="b"><v>0</v></c></row>
+<row r="4"><c r="A4" t="inlineStr"><is><t>rome &amp; co</t></is></c><c r="B4"><v>12.5</v></c></row>"#,
+            ),
+            (
+                "Mixed",
+                r#"<row r="1"><c r="B1" t="s"><v>0</v></c></row>
+<row r="2"><c r="A2"><v>1</v></c><c r="B2" t="s"><v>2</v></c></row>"#,
+            ),
+        ],
+        &["city", "temp", "berlin", "paris"],
+    );
+    let file = saft_string(path.as_path());
+    let source = format!(
+        r#"
+df = read_excel("{file}")
+assert shape(df) == (3, 3)
+assert columns(df) == ["city", "temp", "ok"]
+assert head(df)[0] == {{city: "berlin", ok: true, temp: 10.0}}
+assert head(df)[1] == {{city: "paris", ok: false, temp: nil}}
+assert head(df)[2] == {{city: "rome & co", ok: nil, temp: 12.5}}
+assert head(read_excel("{file}", 0)) == head(df)
+
+mixed = read_excel("{file}", "Mixed")
+assert columns(mixed) == ["column_1", "city"]
+assert head(mixed) == [{{city: "berlin", column_1: 1}}]
+"#
+    );
+    let result = run_source(&source);
+    let missing = run_source(&format!("df = read_excel(\"{file}\", \"Nope\")\n"));
+    let _ = fs::remove_file(&path);
+    assert!(result.is_ok(), "expected read_excel to work, got {result:?}");
+    let err = missing.expect_err("unknown sheet should fail");
+    assert!(
+        err.message.contains("has no sheet 'Nope' (sheets: Scores, Mixed)"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+/// Writes a minimal `.xlsx` workbook with the given sheets and shared strings.
+fn write_xlsx(path: &Path, sheets: &[(&str, &str)], shared: &[&str]) {
+    use std::io::Write;
+    let file = fs::File::create(path).expect("failed to create xlsx fixture");
+    let mut zip = zip::ZipWriter::new(file);
+    let mut add = |name: &str, body: String| {
+        zip.start_file(name, zip::write::SimpleFileOptions::default())
+            .expect("failed to add xlsx part");
+        zip.write_all(body.as_bytes()).expect("failed to write xlsx part");
+    };
+
+    let mut workbook = String::new();
+    let mut rels = String::new();
+    for (index, (name, rows)) in sheets.iter().enumerate() {
+        let id = index + 1;
+        workbook.push_str(&format!(r#"<sheet name="{name}" sheetId="{id}" r:id="rId{id}"/>"#));
+        rels.push_str(&format!(
+            r#"<Relationship Id="rId{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{id}.xml"/>"#
+        ));
+        add(
+            &format!("xl/worksheets/sheet{id}.xml"),
+            format!(r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>{rows}</sheetData></worksheet>"#),
+        );
+    }
+    add(
+        "xl/workbook.xml",
+        format!(r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>{workbook}</sheets></workbook>"#),
+    );
+    add(
+        "xl/_rels/workbook.xml.rels",
+        format!(r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{rels}</Relationships>"#),
+    );
+    let strings = shared
+        .iter()
+        .map(|text| format!("<si><t>{text}</t></si>"))
+        .collect::<String>();
+    add(
+        "xl/sharedStrings.xml",
+        format!(r#"<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">{strings}</sst>"#),
+    );
+    zip.finish().expect("failed to finish xlsx fixture");
+}
+
+#[test]
+fn sql_queries_named_dataframes() {
+    let result = run_with_csv(
+        "sql",
+        "city,temp\nberlin,10\nparis,20\nberlin,14\n",
+        r#"
+out = sql("select city, avg(temp) as temp from weather group by city order by city", {weather: df})
+assert head(out) == [{city: "berlin", temp: 12.0}, {city: "paris", temp: 20.0}]
+
+hot = sql("select city from w where temp > 12", {w: lazy(df)})
+assert shape(hot) == (2, 1)
+"#,
+    );
+    assert!(result.is_ok(), "expected sql() to work, got {result:?}");
+
+    let err = run_with_csv("sql_err", "a\n1\n", "out = sql(\"select * from missing\", {df: df})\n")
+        .expect_err("unknown tables should fail");
+    assert!(
+        err.message.contains("sql query failed"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn tail_and_sample_preview_rows() {
+    let result = run_with_csv(
+        "tail_sample",
+        "id\n1\n2\n3\n4\n5\n6\n7\n",
+        r#"
+assert len(tail(df)) == 5
+assert tail(df, 2) == [{id: 6}, {id: 7}]
+assert tail(df, 0) == []
+
+f row_id(row):
+    ret row.id
+
+picked = sample(df, 3, 42)
+assert len(picked) == 3
+assert picked == sample(df, 3, 42)
+ids = map(picked, row_id)
+assert ids == sort(ids)
+assert len(unique(ids)) == 3
+assert map(sample(df, 100, 1), row_id) == [1, 2, 3, 4, 5, 6, 7]
+assert sample(df, 0, 1) == []
+"#,
+    );
+    assert!(result.is_ok(), "expected tail/sample to work, got {result:?}");
+}
+
+#[test]
+fn null_builtins_count_drop_and_fill() {
+    let result = run_with_csv(
+        "nulls",
+        "city,temp,score\nberlin,10,0.5\nparis,,0.8\n,30,\n",
+        r#"
+assert null_counts(df) == {city: 1, score: 1, temp: 1}
+assert shape(drop_nulls(df)) == (1, 3)
+assert shape(drop_nulls(df, ["temp"])) == (2, 3)
+
+filled = fill_null(df, 0, ["temp", "score"])
+assert null_counts(filled) == {city: 1, score: 0, temp: 0}
+assert head(filled)[1]["temp"] == 0
+assert head(filled)[2]["score"] == 0.0
+assert head(fill_null(df, "?", ["city"]))[2]["city"] == "?"
+assert head(fill_null(df, 2.5, ["temp"]))[0]["temp"] == 10.0
+"#,
+    );
+    assert!(result.is_ok(), "expected null builtins to work, got {result:?}");
+
+    let err = run_with_csv("fill_null_kind", "a,b\n1,\n,x\n", "x = fill_null(df, 0)\n")
+        .expect_err("filling a string column with an int should fail");
+    assert!(
+        err.message
+            .contains("fill_null cannot mix int and string values in column 'b'"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn to_records_and_for_loops_yield_row_objects() {
+    let result = run_with_csv(
+        "records",
+        "city,temp\nberlin,10\nparis,\n",
+        r#"
+assert to_records(df) == [{city: "berlin", temp: 10}, {city: "paris", temp: nil}]
+
+seen = ""
+for row in df:
+    seen = seen + row.city + ";"
+assert seen == "berlin;paris;"
+"#,
+    );
+    assert!(
+        result.is_ok(),
+        "expected dataframe rows to iterate, got {result:?}"
+    );
+}
+
+#[test]
+fn dataframe_index_returns_column_lists() {
+    let result = run_with_csv(
+        "index",
+        "city,temp\nberlin,10\nparis,\nrome,30\n",
+        r#"
+assert df["city"] == ["berlin", "paris", "rome"]
+assert df["temp"][2] == 30
+assert df["temp"][1] == nil
+"#,
+    );
+    assert!(result.is_ok(), "expected df[col] to work, got {result:?}");
+
+    let err = run_with_csv("index_missing", "a\n1\n", "x = df[\"b\"]\n")
+        .expect_err("unknown column should fail");
+    assert!(
+        err.message.contains("dataframe has no column 'b'"),
+        "unexpected error: {}",
+        err.message
+    );
+    assert!(err.span.is_some(), "expected a span on {err:?}");
+}
+
+#[test]
+fn concat_stacks_frames_and_checks_schemas() {
+    let result = run_with_csv(
+        "concat",
+        "city,temp\nberlin,10\nparis,20\n",
+        r#"
+all = concat([df])
+for i in range(2):
+    all = concat([all, df])
+assert shape(all) == (6, 2)
+assert all["city"][4] == "berlin"
+
+wide = concat([select(df, ["city"]), rename(select(df, ["temp"]), {temp: "t"})], "horizontal")
+assert columns(wide) == ["city", "t"]
+"#,
+    );
+    assert!(result.is_ok(), "expected concat to work, got {result:?}");
+
+    let err = run_with_csv(
+        "concat_schema",
+        "a,b\n1,x\n",
+        "x = concat([df, rename(df, {b: \"c\"})])\n",
+    )
+    .expect_err("mismatched columns should fail");
+    assert!(
+        err.message.contains(
+            "concat expects matching schemas; frame 0 has [a: i64, b: str] but frame 1 has [a: i64, c: str]"
+        ),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn extended_aggregations_cover_frames_and_lists() {
+    let result = run_with_csv(
+        "aggregations",
+        "city,temp\nberlin,10\nparis,\nrome,30\nberlin,20\nparis,40\n",
+        r#"
+assert median(df, "temp") == 25.0
+assert round(std(df, "temp"), 4) == 12.9099
+assert quantile(df, "temp", 0.25) == 17.5
+assert quantile(df, "temp", 1) == 40.0
+assert count(df) == 5
+assert count(df, "temp") == 4
+assert nunique(df, "city") == 3
+assert nunique(df, "temp") == 5
+
+assert median([3, 1, 2]) == 2.0
+assert median([4, 1, 3, 2]) == 2.5
+assert std([2, 4, 4, 4, 5, 5, 7, 9]) == sqrt(32 / 7)
+assert quantile([10, 20, 30, 40], 0.25) == 17.5
+"#,
+    );
+    assert!(result.is_ok(), "expected aggregations to work, got {result:?}");
+
+    let err = run_with_csv("quantile_range", "a\n1\n", "x = quantile(df, \"a\", 1.5)\n")
+        .expect_err("q outside [0, 1] should fail");
+    assert!(
+        err.message
+            .contains("quantile expects q between 0 and 1, got 1.5"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn value_counts_orders_by_frequency() {
+    let result = run_with_csv(
+        "value_counts",
+        "city\nparis\nberlin\nberlin\n\nrome\nberlin\nrome\n",
+        r#"
+counts = value_counts(df, "city")
+assert columns(counts) == ["city", "count"]
+assert to_records(counts) == [{city: "berlin", count: 3}, {city: "rome", count: 2}, {city: "paris", count: 1}, {city: nil, count: 1}]
+"#,
+    );
+    assert!(result.is_ok(), "expected value_counts to work, got {result:?}");
+}
+
+#[test]
+fn lazy_pipelines_build_plans_until_collect() {
+    let csv_path = temp_csv_path("scan");
+    fs::write(
+        &csv_path,
+        "city,temp,note\nberlin,10,rain\nparis,,sun\nberlin,30,rainy\nrome,40,sun\n",
+    )
+    .expect("failed to write csv test fixture");
+    let source = format!(
+        r#"
+lf = scan("{path}")
+assert type(lf) == "lazyframe"
+assert is_lazyframe(lf)
+assert len(head(lf)) == 4
+
+plan = select(filter(lf, "note", "contains", "rain"), ["city", "temp"])
+assert is_lazyframe(plan)
+out = collect(plan)
+assert to_records(out) == [{{city: "berlin", temp: 10}}, {{city: "berlin", temp: 30}}]
+
+warm = collect(group_by(filter(lf, "temp", ">=", 20), ["city"], {{temp: "mean"}}))
+assert to_records(warm) == [{{city: "berlin", temp: 30.0}}, {{city: "rome", temp: 40.0}}]
+
+assert shape(collect(filter(lf, "temp", "==", nil))) == (1, 3)
+assert shape(collect(filter(lf, "temp", "!=", 10))) == (3, 3)
+assert shape(collect(filter(lazy(read("{path}")), "city", "in", ["rome", "paris"]))) == (2, 3)
+"#,
+        path = saft_string(csv_path.as_path())
+    );
+    let result = run_source(&source);
+    let _ = fs::remove_file(&csv_path);
+    assert!(result.is_ok(), "expected lazy pipeline to work, got {result:?}");
+}
+
+#[test]
+fn read_options_handle_messy_csv_files() {
+    let csv_path = temp_csv_path("read_options");
+    fs::write(
+        &csv_path,
+        "exported by tool\nid;zip;temp\n1;01234;10\n2;99999;12\n",
+    )
+    .expect("failed to write csv test fixture");
+    let source = format!(
+        r#"
+df = read("{path}", {{sep: ";", skip_rows: 1, dtypes: {{zip: "string", temp: "float"}}}})
+assert columns(df) == ["id", "zip", "temp"]
+assert head(df)[0] == {{id: 1, zip: "01234", temp: 10.0}}
+
+raw = read("{path}", {{sep: ";", skip_rows: 2, header: false}})
+assert columns(raw) == ["column_1", "column_2", "column_3"]
+assert shape(raw) == (2, 3)
+"#,
+        path = saft_string(csv_path.as_path())
+    );
+    let result = run_source(&source);
+    let _ = fs::remove_file(&csv_path);
+    assert!(result.is_ok(), "expected read options to work, got {result:?}");
+
+    let err = run_source("x = read(\"a.csv\", {delimiter: \";\"})\n")
+        .expect_err("unknown option should fail");
+    assert!(
+        err.message.contains("read got unknown option 'delimiter'"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn dataframe_schema_annotations_check_columns() {
+    let result = run_with_csv(
+        "schema_ok",
+        "city,temp,score\nberlin,10,0.5\nparis,,0.8\n",
+        r#"
+checked: dataframe{city: string, temp: int | float, score: float} = df
+any_frame: dataframe = df
+"#,
+    );
+    assert!(result.is_ok(), "expected schema to accept df, got {result:?}");
+
+    let err = run_with_csv(
+        "schema_bad",
+        "city,temp\nberlin,10\n",
+        "checked: dataframe{city: string, temp: float, rain: bool} = df\n",
+    )
+    .expect_err("mismatched columns should fail");
+    assert!(
+        err.message.contains(
+            "schema validation failed for 'checked': value: dataframe columns do not match schema (column 'temp' expected float, got int; missing column 'rain')"
+        ),
+        "unexpected error: {}",
+        err.message
+    );
+
+    let err = run_source("x: dataframe{tags: [string]} = nil\n")
+        .expect_err("non-scalar column schema should fail to parse");
+    assert!(
+        err.message
+            .contains("dataframe column 'tags' must be any, int, float, bool, or string"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
 #[derive(Default)]
 struct InspectingProvider;
 
@@ -134,3 +774,16 @@ fn saft_string(path: &Path) -> String {
         .replace('\\', "\\\\")
         .replace('"', "\\\"")
 }
+
+/// Writes `csv` to a temp file, binds it as `df`, then runs `body`.
+fn run_with_csv(prefix: &str, csv: &str, body: &str) -> SaftResult<()> {
+    let csv_path = temp_csv_path(prefix);
+    fs::write(&csv_path, csv).expect("failed to write csv test fixture");
+    let source = format!(
+        "df = read(\"{path}\")\n{body}",
+        path = saft_string(csv_path.as_path())
+    );
+    let result = run_source(&source);
+    let _ = fs::remove_file(&csv_path);
+    result
+}
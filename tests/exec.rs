@@ -0,0 +1,64 @@
+use orangensaft::error::SaftResult;
+use orangensaft::provider::NoopProvider;
+use orangensaft::runtime::RuntimeOptions;
+use orangensaft::{run_source, run_source_with_provider_and_options};
+
+fn run_with_exec(source: &str) -> SaftResult<()> {
+    let options = RuntimeOptions {
+        allow_exec: true,
+        ..RuntimeOptions::default()
+    };
+    run_source_with_provider_and_options(source, Box::new(NoopProvider), options)
+}
+
+#[test]
+fn exec_captures_status_and_output() {
+    let source = r#"
+ok = exec("echo", ["hello", "world"])
+assert ok.status == 0
+assert ok.stdout == "hello world\n"
+assert ok.stderr == ""
+
+failed = exec("sh", ["-c", "echo oops >&2; exit 3"])
+assert failed.status == 3
+assert failed.stderr == "oops\n"
+"#;
+
+    let result = run_with_exec(source);
+    assert!(result.is_ok(), "expected exec() to work, got {result:?}");
+}
+
+#[test]
+fn exec_does_not_go_through_a_shell() {
+    let result = run_with_exec(
+        "out = exec(\"echo\", [\"$HOME; ls\"])\nassert out.stdout == \"$HOME; ls\\n\"\n",
+    );
+    assert!(
+        result.is_ok(),
+        "expected args to be passed verbatim, got {result:?}"
+    );
+}
+
+#[test]
+fn exec_reports_missing_program() {
+    let err = run_with_exec("x = exec(\"definitely-not-a-real-program\")\n")
+        .expect_err("missing program should fail");
+    assert!(
+        err.message
+            .contains("exec could not run 'definitely-not-a-real-program'"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn exec_requires_allow_exec() {
+    let err =
+        run_source("x = exec(\"echo\", [\"hi\"])\n").expect_err("exec should be denied by default");
+    assert!(
+        err.message
+            .contains("exec is disabled; run with --allow-exec to allow it"),
+        "unexpected error: {}",
+        err.message
+    );
+}
@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use orangensaft::run_source;
+
+fn temp_dir(prefix: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock should be after unix epoch")
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!(
+        "orangensaft_{prefix}_{}_{}",
+        std::process::id(),
+        nanos
+    ));
+    fs::create_dir_all(&dir).expect("failed to create temp dir");
+    dir
+}
+
+#[test]
+fn write_text_then_read_text_round_trips() {
+    let dir = temp_dir("text_io");
+    let path = dir.join("summary.txt");
+    let source = format!(
+        r#"
+path = "{path}"
+write_text(path, "first draft")
+write_text(path, "line one\nline two")
+assert read_text(path) == "line one\nline two"
+"#,
+        path = path.display()
+    );
+
+    let result = run_source(&source);
+    let on_disk = fs::read_to_string(&path).ok();
+    let _ = fs::remove_dir_all(&dir);
+    assert!(
+        result.is_ok(),
+        "expected read_text()/write_text() to work, got {result:?}"
+    );
+    assert_eq!(on_disk.as_deref(), Some("line one\nline two"));
+}
+
+#[test]
+fn append_file_and_read_lines_work_incrementally() {
+    let dir = temp_dir("lines_io");
+    let path = dir.join("results.log");
+    let source = format!(
+        r#"
+path = "{path}"
+for item in ["alpha", "beta"]:
+    append_file(path, item + "\n")
+append_file(path, "gamma\r\n")
+assert read_lines(path) == ["alpha", "beta", "gamma"]
+"#,
+        path = path.display()
+    );
+
+    let result = run_source(&source);
+    let _ = fs::remove_dir_all(&dir);
+    assert!(
+        result.is_ok(),
+        "expected append_file()/read_lines() to work, got {result:?}"
+    );
+}
+
+#[test]
+fn metadata_builtins_inspect_a_folder() {
+    let dir = temp_dir("metadata");
+    fs::write(dir.join("b.txt"), "hello").expect("failed to write b.txt");
+    fs::write(dir.join("a.txt"), "").expect("failed to write a.txt");
+    fs::create_dir(dir.join("nested")).expect("failed to create nested dir");
+    let source = format!(
+        r#"
+dir = "{dir}"
+assert exists(dir)
+assert exists(dir + "/b.txt")
+assert not exists(dir + "/missing.txt")
+assert list_dir(dir) == ["a.txt", "b.txt", "nested"]
+assert file_size(dir + "/b.txt") == 5
+assert file_size(dir + "/a.txt") == 0
+"#,
+        dir = dir.display()
+    );
+
+    let result = run_source(&source);
+    let dir_err = run_source(&format!("x = file_size(\"{}\")\n", dir.display()))
+        .expect_err("directory size should fail");
+    let _ = fs::remove_dir_all(&dir);
+    assert!(
+        result.is_ok(),
+        "expected exists/file_size/list_dir to work, got {result:?}"
+    );
+    assert!(
+        dir_err.message.contains("file_size expects a file"),
+        "unexpected error: {}",
+        dir_err.message
+    );
+}
+
+#[test]
+fn read_text_reports_missing_file() {
+    let err = run_source("x = read_text(\"/definitely/not/here.txt\")\n")
+        .expect_err("missing file should fail");
+    assert!(
+        err.message
+            .contains("read_text could not read '/definitely/not/here.txt'"),
+        "unexpected error: {}",
+        err.message
+    );
+}
@@ -0,0 +1,65 @@
+use orangensaft::fixture::FixtureProvider;
+use orangensaft::run_source_with_provider;
+
+#[test]
+fn fixture_provider_scripts_tool_rounds_from_file() {
+    let provider = FixtureProvider::from_file("examples/fixtures/06_function_map.json")
+        .expect("fixture should load");
+    let source = include_str!("../examples/06_function_map.saft");
+    let result = run_source_with_provider(source, Box::new(provider));
+    assert!(
+        result.is_ok(),
+        "expected fixture-driven example to run, got {result:?}"
+    );
+}
+
+#[test]
+fn fixture_provider_matches_substring_and_regex_rules() {
+    let fixture = r#"
+{"rules": [
+  {"contains": "capital of France", "text": "Paris"},
+  {"regex": "count \\w+ things", "text": {"count": 3}}
+]}
+"#;
+    let source = r#"
+city = $
+    what is the capital of France?
+$
+assert city == "Paris"
+
+x: {count: int} = $
+    count the things
+$
+assert x.count == 3
+"#;
+
+    let provider = FixtureProvider::from_json_str(fixture).expect("fixture should parse");
+    let result = run_source_with_provider(source, Box::new(provider));
+    assert!(
+        result.is_ok(),
+        "expected fixture rules to match, got {result:?}"
+    );
+}
+
+#[test]
+fn fixture_provider_reports_unmatched_prompt() {
+    let provider = FixtureProvider::from_json_str(r#"{"rules": [{"contains": "a", "text": "b"}]}"#)
+        .expect("fixture should parse");
+    let err = run_source_with_provider("x = $ zzz $\n", Box::new(provider))
+        .expect_err("unmatched prompt should fail");
+    assert!(
+        err.message.contains("fixture has no rule matching prompt"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn fixture_provider_rejects_rule_without_matcher() {
+    let result = FixtureProvider::from_json_str(r#"{"rules": [{"text": "b"}]}"#);
+    let err = result.err().expect("rule without matcher should fail");
+    assert!(
+        err.message
+            .contains("rule 0 needs exactly one of 'contains' or 'regex'")
+    );
+}
@@ -0,0 +1,227 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+
+use orangensaft::provider::{
+    NoopProvider, OllamaProvider, PromptProvider, PromptRequest, PromptResponse,
+};
+use orangensaft::runtime::RuntimeOptions;
+use orangensaft::{run_source, run_source_with_provider_and_options};
+
+/// Serves a single request with the given status/body and returns the raw
+/// request (head plus body) it received.
+fn serve_once(status: &str, body: &'static str) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind test server");
+    let url = format!("http://{}", listener.local_addr().expect("local addr"));
+    let status = status.to_string();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("failed to accept connection");
+        let mut reader = BufReader::new(stream);
+        let mut request = String::new();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).expect("failed to read request");
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse().expect("content length");
+            }
+            request.push_str(&line);
+            if line == "\r\n" || line.is_empty() {
+                break;
+            }
+        }
+        let mut request_body = vec![0; content_length];
+        reader
+            .read_exact(&mut request_body)
+            .expect("failed to read request body");
+        request.push_str(&String::from_utf8_lossy(&request_body));
+
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        reader
+            .get_mut()
+            .write_all(response.as_bytes())
+            .expect("failed to write response");
+        request
+    });
+    (url, handle)
+}
+
+fn run_with_net(source: &str) -> orangensaft::error::SaftResult<()> {
+    let options = RuntimeOptions {
+        allow_net: true,
+        ..RuntimeOptions::default()
+    };
+    run_source_with_provider_and_options(source, Box::new(NoopProvider), options)
+}
+
+#[test]
+fn http_get_returns_status_and_body() {
+    let (url, server) = serve_once("200 OK", r#"{"players": 3}"#);
+    let source = format!(
+        r#"
+resp = http_get("{url}/stats", {{Accept: "application/json"}})
+assert resp.status == 200
+assert json_parse(resp.body) == {{players: 3}}
+"#
+    );
+
+    let result = run_with_net(&source);
+    assert!(
+        result.is_ok(),
+        "expected http_get() to work, got {result:?}"
+    );
+    let request = server.join().expect("server thread panicked");
+    assert!(
+        request.starts_with("GET /stats "),
+        "unexpected request: {request}"
+    );
+    assert!(
+        request.contains("Accept: application/json"),
+        "missing header: {request}"
+    );
+}
+
+#[test]
+fn http_post_sends_non_string_bodies_as_json() {
+    let (url, server) = serve_once("404 Not Found", "missing");
+    let source = format!(
+        r#"
+resp = http_post("{url}/hook", {{score: 91}}, ["X-Request-Id: run-7"])
+assert resp.status == 404
+assert resp.body == "missing"
+"#
+    );
+
+    let result = run_with_net(&source);
+    assert!(
+        result.is_ok(),
+        "expected http_post() to work, got {result:?}"
+    );
+    let request = server.join().expect("server thread panicked");
+    assert!(
+        request.starts_with("POST /hook "),
+        "unexpected request: {request}"
+    );
+    assert!(
+        request.contains("Content-Type: application/json"),
+        "missing content type: {request}"
+    );
+    assert!(
+        request.contains("X-Request-Id: run-7"),
+        "missing header: {request}"
+    );
+    assert!(
+        request.ends_with(r#"{"score":91}"#),
+        "unexpected body: {request}"
+    );
+}
+
+#[test]
+fn read_fetches_csv_urls() {
+    let (url, server) = serve_once("200 OK", "city,temp\nberlin,10\nparis,20\n");
+    let source = format!(
+        r#"
+df = read("{url}/weather.csv")
+assert shape(df) == (2, 2)
+assert head(df)[1] == {{city: "paris", temp: 20}}
+"#
+    );
+
+    let result = run_with_net(&source);
+    assert!(result.is_ok(), "expected read() of a URL to work, got {result:?}");
+    let request = server.join().expect("server thread panicked");
+    assert!(
+        request.starts_with("GET /weather.csv "),
+        "unexpected request: {request}"
+    );
+}
+
+#[test]
+fn read_reports_http_errors_for_csv_urls() {
+    let (url, server) = serve_once("404 Not Found", "missing");
+    let err = run_with_net(&format!("df = read(\"{url}/gone.csv\")\n"))
+        .expect_err("a 404 should fail");
+    server.join().expect("server thread panicked");
+    assert!(
+        err.message.contains("gone.csv': HTTP 404"),
+        "unexpected error: {}",
+        err.message
+    );
+
+    let err = run_source("df = read(\"https://example.com/data.csv\")\n")
+        .expect_err("reading a URL should be denied by default");
+    assert!(
+        err.message
+            .contains("read from a URL is disabled; run with --allow-net to allow it"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn http_builtins_only_accept_http_urls() {
+    for url in ["file:///etc/hostname", "-o/tmp/saft-http-test", "ftp://127.0.0.1/"] {
+        let err = run_with_net(&format!("resp = http_get(\"{url}\")\n"))
+            .expect_err("non-http URLs should be rejected");
+        assert!(
+            err.message
+                .contains("only http:// and https:// URLs are supported"),
+            "unexpected error for {url}: {}",
+            err.message
+        );
+    }
+}
+
+#[test]
+fn http_builtins_require_allow_net() {
+    let err = run_source("resp = http_get(\"http://127.0.0.1:9/\")\n")
+        .expect_err("network access should be denied by default");
+    assert!(
+        err.message
+            .contains("http_get is disabled; run with --allow-net to allow it"),
+        "unexpected error: {}",
+        err.message
+    );
+
+    let err = run_source("df = read(\"https://example.com/data.csv\")\n")
+        .expect_err("reading a URL should be denied by default");
+    assert!(
+        err.message
+            .contains("read from a URL is disabled; run with --allow-net to allow it"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn provider_payloads_larger_than_arg_max_are_sent() {
+    let (url, server) = serve_once(
+        "200 OK",
+        r#"{"message": {"role": "assistant", "content": "got it"}}"#,
+    );
+    // Well past both the per-argument limit and a typical ARG_MAX.
+    let prompt = "x".repeat(4 * 1024 * 1024);
+    let mut provider = OllamaProvider::new(Some(url), Some("llama3".to_string()));
+    let response = provider.complete(PromptRequest {
+        prompt: prompt.clone(),
+        images: Vec::new(),
+        tools: Vec::new(),
+        tool_results: Vec::new(),
+        response_format: None,
+        model: None,
+        timeout: None,
+    });
+
+    // Checked before joining: if curl never connects, the server never returns.
+    assert!(
+        matches!(&response, Ok(PromptResponse::FinalText(text)) if text == "got it"),
+        "expected the large request to go through, got {response:?}"
+    );
+    let request = server.join().expect("server thread panicked");
+    assert!(request.contains(&prompt));
+}
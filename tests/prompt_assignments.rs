@@ -1,5 +1,9 @@
-use orangensaft::provider::SequenceProvider;
-use orangensaft::{run_source, run_source_with_provider};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use orangensaft::error::SaftResult;
+use orangensaft::provider::{PromptProvider, PromptRequest, PromptResponse, SequenceProvider};
+use orangensaft::runtime::Runtime;
+use orangensaft::{run_source, run_source_with_provider, run_source_with_runtime};
 
 #[test]
 fn runs_prompt_addition_example() {
@@ -78,3 +82,133 @@ assert x == 7
         "expected typed prompt repair to recover, got {result:?}"
     );
 }
+
+#[test]
+fn typed_prompt_assignment_requests_structured_output() {
+    let source = r#"
+plain = $
+    say hi
+$
+x: {count: int} = $
+    count the things
+$
+assert x.count == 3
+"#;
+
+    let result = run_source_with_provider(source, Box::new(StructuredOutputProvider));
+    assert!(
+        result.is_ok(),
+        "expected typed prompt to carry response format, got {result:?}"
+    );
+}
+
+struct StructuredOutputProvider;
+
+impl PromptProvider for StructuredOutputProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        if request.prompt.contains("say hi") {
+            assert!(
+                request.response_format.is_none(),
+                "untyped prompt should not request structured output"
+            );
+            return Ok(PromptResponse::FinalText("hi".to_string()));
+        }
+
+        let format = request
+            .response_format
+            .expect("typed prompt should request structured output");
+        assert_eq!(format.name, "x");
+        assert_eq!(format.schema["type"], "object");
+        assert_eq!(format.schema["properties"]["count"]["type"], "integer");
+        Ok(PromptResponse::FinalText("{\"count\": 3}".to_string()))
+    }
+}
+
+#[test]
+fn prompt_image_interpolation_attaches_image() {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock should be after unix epoch")
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!(
+        "orangensaft_image_{}_{}.png",
+        std::process::id(),
+        nanos
+    ));
+    std::fs::write(&path, b"saft!").expect("should write temp image");
+
+    let source = format!(
+        r#"
+chart = image("{}")
+answer = $
+    what does {{chart}} show?
+$
+assert answer == "a chart"
+"#,
+        path.display()
+    );
+
+    let result = run_source_with_provider(&source, Box::new(ImageProvider));
+    let _ = std::fs::remove_file(&path);
+    assert!(
+        result.is_ok(),
+        "expected image to be attached to prompt, got {result:?}"
+    );
+}
+
+struct ImageProvider;
+
+impl PromptProvider for ImageProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        assert!(request.prompt.contains("what does [image 1] show?"));
+        assert_eq!(request.images.len(), 1);
+        assert_eq!(request.images[0].media_type, "image/png");
+        assert_eq!(request.images[0].data_base64, "c2FmdCE=");
+        Ok(PromptResponse::FinalText("a chart".to_string()))
+    }
+}
+
+#[test]
+fn prompt_with_options_routes_to_named_provider() {
+    let source = r#"
+hosted = $
+    hosted question
+$
+local = $
+    private question
+$ with {provider: "local", model: "llama3"}
+assert hosted == "hosted"
+assert local == "local:llama3"
+"#;
+
+    let mut runtime = Runtime::with_provider(Box::new(SequenceProvider::from_texts(vec![
+        "hosted".to_string(),
+    ])));
+    runtime.register_provider("local", Box::new(EchoModelProvider));
+    let result = run_source_with_runtime(source, &mut runtime);
+    assert!(
+        result.is_ok(),
+        "expected prompt options to select provider, got {result:?}"
+    );
+}
+
+#[test]
+fn prompt_with_unknown_provider_fails() {
+    let source = "x = $ hi $ with {provider: \"missing\"}\n";
+    let err = run_source(source).expect_err("unknown provider should fail");
+    assert!(
+        err.message.contains("unknown provider 'missing'"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+struct EchoModelProvider;
+
+impl PromptProvider for EchoModelProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        assert!(request.prompt.contains("private question"));
+        let model = request.model.unwrap_or_default();
+        Ok(PromptResponse::FinalText(format!("local:{model}")))
+    }
+}
@@ -0,0 +1,91 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use orangensaft::cache::{CachingProvider, PromptCache};
+use orangensaft::error::SaftResult;
+use orangensaft::provider::{HeuristicMockProvider, PromptProvider, PromptRequest, PromptResponse};
+use orangensaft::run_source_with_provider;
+
+/// Counts the requests that get past the cache.
+struct CountingProvider {
+    inner: HeuristicMockProvider,
+    calls: Rc<Cell<usize>>,
+    temperature: Option<f32>,
+}
+
+impl PromptProvider for CountingProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        self.calls.set(self.calls.get() + 1);
+        self.inner.complete(request)
+    }
+
+    fn temperature(&self) -> Option<f32> {
+        self.temperature
+    }
+}
+
+fn cached_run(source: &str, cache: &PromptCache, provider: &str) -> usize {
+    cached_run_at(source, cache, provider, None)
+}
+
+fn cached_run_at(
+    source: &str,
+    cache: &PromptCache,
+    provider: &str,
+    temperature: Option<f32>,
+) -> usize {
+    let calls = Rc::new(Cell::new(0));
+    let inner = CountingProvider {
+        inner: HeuristicMockProvider::new(),
+        calls: calls.clone(),
+        temperature,
+    };
+    let provider = CachingProvider::new(Box::new(inner), cache.clone(), provider);
+    let result = run_source_with_provider(source, Box::new(provider));
+    assert!(result.is_ok(), "expected script to run, got {result:?}");
+    calls.get()
+}
+
+#[test]
+fn repeated_prompts_are_served_from_the_cache() {
+    let dir = std::env::temp_dir().join(format!("orangensaft_prompt_cache_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = PromptCache::new(&dir);
+    let source = include_str!("../examples/06_function_map.saft");
+
+    let first = cached_run(source, &cache, "mock");
+    assert!(first >= 2, "expected tool round plus final answer, got {first}");
+    assert_eq!(cache.entries().expect("cache should list").len(), first);
+    assert_eq!(cached_run(source, &cache, "mock"), 0);
+
+    // Entries are namespaced by provider, and a changed prompt is a miss.
+    assert_eq!(cached_run(source, &cache, "ollama"), first);
+    let edited = source.replace("each verb", "every verb");
+    assert!(cached_run(&edited, &cache, "mock") > 0);
+
+    let entries = cache.entries().expect("cache should list");
+    assert!(entries.iter().any(|entry| entry.provider == "ollama"));
+    assert!(entries.iter().all(|entry| entry.created_at_ms > 0));
+    assert_eq!(cache.clear(Some(60_000)).expect("clear should work"), 0);
+    assert_eq!(cache.clear(None).expect("clear should work"), entries.len());
+    assert!(cache.entries().expect("cache should list").is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn responses_are_cached_per_temperature() {
+    let dir = std::env::temp_dir().join(format!(
+        "orangensaft_prompt_cache_temp_{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = PromptCache::new(&dir);
+    let source = include_str!("../examples/06_function_map.saft");
+
+    let first = cached_run_at(source, &cache, "mock", Some(0.0));
+    assert!(first > 0);
+    assert_eq!(cached_run_at(source, &cache, "mock", Some(0.0)), 0);
+    assert_eq!(cached_run_at(source, &cache, "mock", Some(1.0)), first);
+    assert_eq!(cached_run_at(source, &cache, "mock", Some(1.0)), 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
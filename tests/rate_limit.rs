@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use orangensaft::error::{ErrorKind, SaftResult};
+use orangensaft::provider::{PromptProvider, PromptRequest, PromptResponse, SequenceProvider};
+use orangensaft::rate_limit::{RateLimit, RateLimitedProvider, RateLimiter};
+use orangensaft::runtime::RuntimeOptions;
+use orangensaft::{run_source_with_provider, run_source_with_provider_and_options};
+
+#[test]
+fn rate_limited_provider_waits_for_window_budget() {
+    let source = r#"
+a = $ one $
+b = $ two $
+c = $ three $
+assert c == "3"
+"#;
+
+    let inner = SequenceProvider::from_texts(vec!["1".into(), "2".into(), "3".into()]);
+    let limit = RateLimit {
+        max_requests: Some(2),
+        window: Duration::from_millis(150),
+        max_concurrent: None,
+    };
+    let started = Instant::now();
+    let result = run_source_with_provider(
+        source,
+        Box::new(RateLimitedProvider::new(Box::new(inner), limit)),
+    );
+    assert!(
+        result.is_ok(),
+        "expected limited run to finish, got {result:?}"
+    );
+    assert!(
+        started.elapsed() >= Duration::from_millis(150),
+        "third request should wait for the window to roll over"
+    );
+}
+
+#[test]
+fn rate_limit_waits_stop_at_the_script_timeout() {
+    let inner = SequenceProvider::from_texts(vec!["1".into(), "2".into()]);
+    let provider = RateLimitedProvider::new(Box::new(inner), RateLimit::per_minute(1));
+    let options = RuntimeOptions {
+        timeout: Some(Duration::from_millis(100)),
+        ..RuntimeOptions::default()
+    };
+    let started = Instant::now();
+    let err = run_source_with_provider_and_options(
+        "a = $ one $\nb = $ two $\n",
+        Box::new(provider),
+        options,
+    )
+    .expect_err("the second request should not wait out the minute");
+    assert_eq!(err.kind, ErrorKind::Timeout);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn shared_rate_limiter_caps_concurrent_requests() {
+    let limiter = RateLimiter::new(RateLimit {
+        max_concurrent: Some(1),
+        ..RateLimit::default()
+    });
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let workers = (0..3)
+        .map(|_| {
+            let limiter = limiter.clone();
+            let in_flight = Arc::clone(&in_flight);
+            let peak = Arc::clone(&peak);
+            thread::spawn(move || {
+                let inner = SlowProvider { in_flight, peak };
+                let provider = RateLimitedProvider::with_limiter(Box::new(inner), limiter);
+                run_source_with_provider("x = $ hello $\n", Box::new(provider))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for worker in workers {
+        let result = worker.join().expect("worker should not panic");
+        assert!(
+            result.is_ok(),
+            "expected worker run to succeed, got {result:?}"
+        );
+    }
+    assert_eq!(peak.load(Ordering::SeqCst), 1);
+}
+
+struct SlowProvider {
+    in_flight: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+}
+
+impl PromptProvider for SlowProvider {
+    fn complete(&mut self, _request: PromptRequest) -> SaftResult<PromptResponse> {
+        let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(now, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(20));
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        Ok(PromptResponse::FinalText("hi".to_string()))
+    }
+}
@@ -0,0 +1,71 @@
+use orangensaft::provider::SequenceProvider;
+use orangensaft::response_filter::{FilterVerdict, PatternFilter, ResponseFilter};
+use orangensaft::run_source_with_runtime;
+use orangensaft::runtime::Runtime;
+
+fn runtime_with_responses(responses: &[&str]) -> Runtime {
+    let texts = responses.iter().map(|text| text.to_string()).collect();
+    Runtime::with_provider(Box::new(SequenceProvider::from_texts(texts)))
+}
+
+#[test]
+fn pattern_filter_redacts_matching_text() {
+    let source = r#"
+answer = $
+    what is the account number?
+$
+assert answer == "account [REDACTED] is active"
+"#;
+
+    let mut runtime = runtime_with_responses(&["account 1234-5678 is active"]);
+    let filter = PatternFilter::new()
+        .redact(r"\d{4}-\d{4}", "[REDACTED]")
+        .expect("pattern should compile");
+    runtime.add_response_filter(Box::new(filter));
+    let result = run_source_with_runtime(source, &mut runtime);
+    assert!(result.is_ok(), "expected redacted response, got {result:?}");
+}
+
+#[test]
+fn pattern_filter_rejects_blocked_keyword() {
+    let mut runtime = runtime_with_responses(&["here is the Password you asked for"]);
+    let filter = PatternFilter::new()
+        .reject_keyword("password")
+        .expect("keyword should compile");
+    runtime.add_response_filter(Box::new(filter));
+
+    let err = run_source_with_runtime("x = $ tell me a secret $\n", &mut runtime)
+        .expect_err("blocked keyword should fail");
+    assert!(
+        err.message.contains("prompt response rejected by filter"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn filters_annotate_and_apply_to_typed_prompts() {
+    let source = r#"
+x: {count: int} = $
+    count the things
+$
+assert x.count == 3
+"#;
+
+    let mut runtime = runtime_with_responses(&["{\"count\": 3}"]);
+    runtime.add_response_filter(Box::new(CountingFilter));
+    let result = run_source_with_runtime(source, &mut runtime);
+    assert!(
+        result.is_ok(),
+        "expected typed prompt to pass, got {result:?}"
+    );
+    assert_eq!(runtime.response_annotations(), ["saw 12 chars"]);
+}
+
+struct CountingFilter;
+
+impl ResponseFilter for CountingFilter {
+    fn inspect(&mut self, text: &str) -> FilterVerdict {
+        FilterVerdict::Annotate(format!("saw {} chars", text.len()))
+    }
+}
@@ -1,9 +1,14 @@
 use std::fs;
 use std::path::PathBuf;
-use std::process::Command;
+use std::io::Write;
+use std::process::{Command, Stdio};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use orangensaft::run_source;
+use orangensaft::embedding::EmbeddingProvider;
+use orangensaft::error::SaftResult;
+use orangensaft::runtime::{Runtime, RuntimeOptions};
+use orangensaft::provider::NoopProvider;
+use orangensaft::{run_source, run_source_with_provider_and_options, run_source_with_runtime};
 
 #[test]
 fn runs_stdlib_basics_example() {
@@ -26,6 +31,208 @@ assert out == "SHIP"
     assert!(result.is_ok(), "expected upper() to work, got {result:?}");
 }
 
+#[test]
+fn lower_builtin_normalizes_text() {
+    let source = r#"
+assert lower("ShIp") == "ship"
+assert lower(upper("mixed Case")) == "mixed case"
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected lower() to work, got {result:?}");
+}
+
+#[test]
+fn trim_builtins_strip_whitespace() {
+    let source = r#"
+answer = "\n  yes \t"
+assert trim(answer) == "yes"
+assert lstrip(answer) == "yes \t"
+assert rstrip(answer) == "\n  yes"
+assert trim("") == ""
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected trim builtins to work, got {result:?}"
+    );
+}
+
+#[test]
+fn split_and_join_round_trip() {
+    let source = r#"
+parts = split("a,b,,c", ",")
+assert parts == ["a", "b", "", "c"]
+assert join(parts, ";") == "a;b;;c"
+assert split("abc", "") == ["a", "b", "c"]
+assert join([], ", ") == ""
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected split()/join() to work, got {result:?}"
+    );
+}
+
+#[test]
+fn replace_builtin_substitutes_all_occurrences() {
+    let source = r#"
+assert replace("a-b-c", "-", "+") == "a+b+c"
+assert replace("Answer: yes", "Answer: ", "") == "yes"
+assert replace("none here", "x", "y") == "none here"
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected replace() to work, got {result:?}");
+}
+
+#[test]
+fn substring_and_membership_predicates() {
+    let source = r#"
+reply = "Answer: yes, definitely"
+assert contains(reply, "yes")
+assert not contains(reply, "no")
+assert contains(["build", "test"], "test")
+assert not contains([1, 2, 3], 4)
+assert contains((1, "a"), "a")
+assert starts_with(reply, "Answer:")
+assert ends_with(reply, "definitely")
+assert not ends_with(reply, "Answer")
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected contains/starts_with/ends_with to work, got {result:?}"
+    );
+}
+
+#[test]
+fn str_and_format_render_values() {
+    let source = r#"
+assert str(42) == "42"
+assert str(true) == "true"
+assert str("raw") == "raw"
+assert str([1, "a"]) == "[1, \"a\"]"
+assert format("{} of {} done", 3, 5) == "3 of 5 done"
+assert format("{{literal}} {}", "x") == "{literal} x"
+assert format("no placeholders") == "no placeholders"
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected str()/format() to work, got {result:?}"
+    );
+}
+
+#[test]
+fn parse_builtins_return_numbers_or_nil() {
+    let source = r#"
+assert parse_int(" 42\n") == 42
+assert parse_int("-7") == -7
+assert parse_int("3.5") == nil
+assert parse_int("forty-two") == nil
+assert parse_float("3.5") == 3.5
+assert parse_float("42") == 42.0
+assert parse_float("nan") == nil
+assert parse_float("") == nil
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected parse_int()/parse_float() to work, got {result:?}"
+    );
+}
+
+#[test]
+fn format_rejects_placeholder_count_mismatch() {
+    let err = run_source("x = format(\"{} {}\", 1)\n").expect_err("missing value should fail");
+    assert!(
+        err.message.contains("more '{}' placeholders"),
+        "unexpected error: {}",
+        err.message
+    );
+
+    let err = run_source("x = format(\"{}\", 1, 2)\n").expect_err("extra value should fail");
+    assert!(
+        err.message.contains("got 2 value(s)"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn numeric_rounding_builtins() {
+    let source = r#"
+assert abs(-3) == 3
+assert abs(-2.5) == 2.5
+assert round(2.5) == 3
+assert round(-2.5) == -3
+assert round(7) == 7
+assert round(3.14159, 2) == 3.14
+assert floor(2.7) == 2
+assert floor(-2.1) == -3
+assert ceil(2.1) == 3
+assert ceil(4) == 4
+assert type(round(1.2)) == "int"
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected abs/round/floor/ceil to work, got {result:?}"
+    );
+}
+
+#[test]
+fn math_builtins_compute_locally() {
+    let source = r#"
+assert sqrt(16) == 4.0
+assert pow(2, 10) == 1024
+assert pow(2, -1) == 0.5
+assert pow(9, 0.5) == 3.0
+assert round(exp(1), 5) == 2.71828
+assert log(exp(2)) == 2.0
+assert log(1000, 10) == 3.0
+assert sin(0) == 0.0
+assert cos(0) == 1.0
+assert round(tan(pi() / 4), 6) == 1.0
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected math builtins to work, got {result:?}");
+}
+
+#[test]
+fn math_builtins_reject_domain_errors() {
+    for (source, expected) in [
+        ("x = sqrt(-1)\n", "sqrt expects a non-negative number"),
+        ("x = log(0)\n", "log expects a positive number"),
+        ("x = pow(10, 40)\n", "pow overflowed int range"),
+    ] {
+        let err = run_source(source).expect_err("domain error should fail");
+        assert!(
+            err.message.contains(expected),
+            "unexpected error for {source:?}: {}",
+            err.message
+        );
+    }
+}
+
+#[test]
+fn join_rejects_non_string_items() {
+    let err = run_source("x = join([1, 2], \",\")\n").expect_err("join should reject ints");
+    assert!(
+        err.message.contains("join expects [string]"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
 #[test]
 fn len_builtin_supports_core_collection_types() {
     let source = r#"
@@ -92,6 +299,589 @@ fn print_builtin_writes_to_stdout_via_cli() {
     );
 }
 
+#[test]
+fn print_and_eprint_join_arguments_with_spaces() {
+    let binary = env!("CARGO_BIN_EXE_orangensaft");
+    let script_path = temp_script_path("print_variadic");
+    fs::write(
+        &script_path,
+        "print(\"score:\", 91, [1, 2], nil)\nprint()\neprint(\"warning:\", 3, \"retries\")\n",
+    )
+    .expect("failed to write temp script");
+
+    let output = Command::new(binary)
+        .args(["run", script_path.to_string_lossy().as_ref(), "--provider", "none"])
+        .output()
+        .expect("failed to run orangensaft binary");
+    let _ = fs::remove_file(&script_path);
+
+    assert!(
+        output.status.success(),
+        "expected CLI run to succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "score: 91 [1, 2] nil\n\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "warning: 3 retries\n");
+}
+
+#[test]
+fn pprint_breaks_long_values_and_tabulates_dataframes() {
+    let binary = env!("CARGO_BIN_EXE_orangensaft");
+    let dir = std::env::temp_dir().join(format!("orangensaft_pprint_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("failed to create temp dir");
+    let csv_path = dir.join("scores.csv");
+    fs::write(&csv_path, "name,score\nalice,91\nbob,7\ncarol,85\n").expect("failed to write csv");
+    let script_path = dir.join("pprint.saft");
+    fs::write(
+        &script_path,
+        format!(
+            r#"
+pprint([1, 2, 3])
+pprint({{name: "alice", notes: "a note that is long enough to push the object past eighty columns"}})
+pprint(read("{}"))
+"#,
+            csv_path.display()
+        ),
+    )
+    .expect("failed to write temp script");
+
+    let output = Command::new(binary)
+        .args(["run", script_path.to_string_lossy().as_ref(), "--provider", "none"])
+        .output()
+        .expect("failed to run orangensaft binary");
+    let _ = fs::remove_dir_all(&dir);
+
+    assert!(
+        output.status.success(),
+        "expected pprint run to succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        concat!(
+            "[1, 2, 3]\n",
+            "{\n",
+            "  name: \"alice\",\n",
+            "  notes: \"a note that is long enough to push the object past eighty columns\"\n",
+            "}\n",
+            "name   score\n",
+            "-----  -----\n",
+            "alice     91\n",
+            "bob        7\n",
+            "carol     85\n",
+            "(3 rows x 2 cols)\n",
+        )
+    );
+}
+
+#[test]
+fn random_builtins_respect_bounds() {
+    let source = r#"
+x = random()
+assert x >= 0.0
+assert x < 1.0
+for i in [1, 2, 3, 4, 5, 6, 7, 8]:
+    roll = rand_int(1, 6)
+    assert roll >= 1
+    assert roll <= 6
+assert rand_int(3, 3) == 3
+deck = shuffle([1, 2, 3, 4, 5])
+assert len(deck) == 5
+assert contains(deck, 5)
+"#;
+
+    let options = RuntimeOptions {
+        seed: Some(7),
+        ..RuntimeOptions::default()
+    };
+    let result = run_source_with_provider_and_options(source, Box::new(NoopProvider), options);
+    assert!(
+        result.is_ok(),
+        "expected random builtins to work, got {result:?}"
+    );
+}
+
+#[test]
+fn sort_and_sort_by_order_lists() {
+    let source = r#"
+assert sort([3, 1.5, 2]) == [1.5, 2, 3]
+assert sort(["pear", "apple", "fig"]) == ["apple", "fig", "pear"]
+assert sort([(2, "b"), (1, "z"), (2, "a")]) == [(1, "z"), (2, "a"), (2, "b")]
+
+f by_score(player):
+    ret -player.score
+
+players = [{name: "ana", score: 7}, {name: "bo", score: 9}, {name: "cy", score: 7}]
+ranked = sort_by(players, by_score)
+assert ranked[0].name == "bo"
+assert ranked[1].name == "ana"
+assert ranked[2].name == "cy"
+assert sort_by(["ccc", "a", "bb"], len) == ["a", "bb", "ccc"]
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected sort()/sort_by() to work, got {result:?}"
+    );
+}
+
+#[test]
+fn map_filter_reduce_call_function_values() {
+    let source = r#"
+f double(x):
+    ret x * 2
+
+f is_big(x):
+    ret x > 4
+
+f add(acc, x):
+    ret acc + x
+
+nums = [1, 2, 3, 4]
+assert map(nums, double) == [2, 4, 6, 8]
+assert filter(map(nums, double), is_big) == [6, 8]
+assert reduce(nums, add, 0) == 10
+assert reduce([], add, 5) == 5
+assert map(["a", "b"], upper) == ["A", "B"]
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected map/filter/reduce to work, got {result:?}"
+    );
+}
+
+#[test]
+fn zip_and_enumerate_build_tuples() {
+    let source = r#"
+names = ["ana", "bo", "cy"]
+scores = [7, 9]
+assert zip(names, scores) == [("ana", 7), ("bo", 9)]
+assert enumerate(["x", "y"]) == [(0, "x"), (1, "y")]
+
+total = 0
+for i, name in enumerate(names):
+    total = total + i
+assert total == 3
+
+for name, score in zip(names, scores):
+    assert score > 5
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected zip()/enumerate() to work, got {result:?}"
+    );
+}
+
+#[test]
+fn range_builtin_counts_like_python() {
+    let source = r#"
+assert range(3) == [0, 1, 2]
+assert range(2, 5) == [2, 3, 4]
+assert range(0, 10, 3) == [0, 3, 6, 9]
+assert range(5, 0, -2) == [5, 3, 1]
+assert range(0) == []
+assert range(3, 1) == []
+assert range(0 - 9223372036854775807, 9223372036854775807, 4611686018427387904) == [0 - 9223372036854775807, 0 - 4611686018427387903, 1, 4611686018427387905]
+
+total = 0
+for i in range(101):
+    total = total + i
+assert total == 5050
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected range() to work, got {result:?}");
+
+    let err = run_source("x = range(0, 5, 0)\n").expect_err("zero step should fail");
+    assert!(
+        err.message.contains("range step must not be zero"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn aggregates_accept_plain_number_lists() {
+    let source = r#"
+assert sum([1, 2, 3]) == 6
+assert type(sum([1, 2, 3])) == "int"
+assert sum([1, 2.5]) == 3.5
+assert sum([]) == 0
+assert min([3, 1.5, 2]) == 1.5
+assert max([3, 1.5, 2]) == 3
+assert type(max([3, 1.5, 2])) == "int"
+assert mean([1, 2, 3, 4]) == 2.5
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected list aggregates to work, got {result:?}"
+    );
+
+    let err = run_source("x = mean([])\n").expect_err("empty mean should fail");
+    assert!(
+        err.message.contains("mean expects a non-empty list"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn unique_keeps_first_seen_order() {
+    let source = r#"
+assert unique(["acme", "globex", "acme", "initech", "globex"]) == ["acme", "globex", "initech"]
+assert unique([{id: 1}, {id: 1}, {id: 2}]) == [{id: 1}, {id: 2}]
+assert unique([]) == []
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected unique() to work, got {result:?}");
+}
+
+#[test]
+fn reverse_and_last_builtins() {
+    let source = r#"
+assert reverse([1, 2, 3]) == [3, 2, 1]
+assert reverse((1, "a")) == ("a", 1)
+assert reverse("abc") == "cba"
+assert last(["build", "test", "ship"]) == "ship"
+assert last(reverse(range(5))) == 0
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected reverse()/last() to work, got {result:?}"
+    );
+
+    let err = run_source("x = last([])\n").expect_err("last of empty list should fail");
+    assert!(
+        err.message.contains("last expects a non-empty list"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn object_entry_builtins() {
+    let source = r#"
+config = {retries: 3, model: "small", verbose: false}
+assert keys(config) == ["model", "retries", "verbose"]
+assert values(config) == ["small", 3, false]
+assert items({b: 2, a: 1}) == [("a", 1), ("b", 2)]
+
+seen = ""
+for key, value in items(config):
+    seen = seen + key + "=" + str(value) + ";"
+assert seen == "model=small;retries=3;verbose=false;"
+assert keys({}) == []
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected keys/values/items to work, got {result:?}"
+    );
+}
+
+#[test]
+fn get_and_has_handle_missing_fields() {
+    let source = r#"
+reply = {title: "Q3 report", tags: ["finance"]}
+assert get(reply, "title", "untitled") == "Q3 report"
+assert get(reply, "summary", "n/a") == "n/a"
+assert get(reply, "summary") == nil
+assert has(reply, "tags")
+assert not has(reply, "summary")
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected get()/has() to work, got {result:?}");
+}
+
+#[test]
+fn merge_layers_second_object_over_first() {
+    let source = r#"
+defaults = {retries: 3, model: "small", opts: {a: 1}}
+from_model = {model: "large", opts: {b: 2}}
+config = merge(defaults, from_model)
+assert config == {retries: 3, model: "large", opts: {b: 2}}
+assert defaults.model == "small"
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected merge() to work, got {result:?}");
+}
+
+#[test]
+fn json_builtins_round_trip_values() {
+    let source = r#"
+raw = "{\"name\": \"ana\", \"scores\": [7, 9.5], \"extra\": null}"
+parsed = json_parse(raw)
+assert parsed.name == "ana"
+assert parsed.scores == [7, 9.5]
+assert parsed.extra == nil
+assert json_stringify({b: [1, (2, "x")], a: true}) == "{\"a\":true,\"b\":[1,[2,\"x\"]]}"
+assert json_parse(json_stringify(parsed, true)) == parsed
+"#;
+
+    let result = run_source(source);
+    assert!(
+        result.is_ok(),
+        "expected json_parse()/json_stringify() to work, got {result:?}"
+    );
+
+    let err = run_source("x = json_parse(\"{oops\")\n").expect_err("invalid JSON should fail");
+    assert!(
+        err.message.contains("json_parse failed"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn sort_rejects_mixed_types() {
+    let err = run_source("x = sort([1, \"a\"])\n").expect_err("mixed sort should fail");
+    assert!(
+        err.message.contains("sort cannot compare"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn args_builtin_returns_arguments_after_double_dash() {
+    let binary = env!("CARGO_BIN_EXE_orangensaft");
+    let script_path = temp_script_path("script_args");
+    fs::write(
+        &script_path,
+        "print(args())\nassert args() == [\"data.csv\", \"--limit\", \"5\"]\n",
+    )
+    .expect("failed to write temp script");
+
+    let output = Command::new(binary)
+        .args([
+            "run",
+            script_path.to_string_lossy().as_ref(),
+            "--provider",
+            "none",
+            "--",
+            "data.csv",
+            "--limit",
+            "5",
+        ])
+        .output()
+        .expect("failed to run orangensaft binary");
+    let _ = fs::remove_file(&script_path);
+    assert!(
+        output.status.success(),
+        "expected script args run to succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn args_builtin_is_empty_without_script_args() {
+    let result = run_source("assert args() == []\n");
+    assert!(result.is_ok(), "expected args() to be empty, got {result:?}");
+}
+
+#[test]
+fn input_builtin_reads_lines_until_end_of_stdin() {
+    let binary = env!("CARGO_BIN_EXE_orangensaft");
+    let script_path = temp_script_path("input_loop");
+    fs::write(
+        &script_path,
+        r#"
+count = 0
+for turn in range(3):
+    line = input("> ")
+    if line != nil:
+        print("echo: " + line)
+        count = count + 1
+print(count)
+"#,
+    )
+    .expect("failed to write temp script");
+
+    let mut child = Command::new(binary)
+        .args([
+            "run",
+            script_path.to_string_lossy().as_ref(),
+            "--provider",
+            "none",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run orangensaft binary");
+    child
+        .stdin
+        .take()
+        .expect("stdin should be piped")
+        .write_all(b"hello\r\nworld\n")
+        .expect("failed to write stdin");
+    let output = child.wait_with_output().expect("failed to wait for binary");
+    let _ = fs::remove_file(&script_path);
+    assert!(
+        output.status.success(),
+        "expected input loop to succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "> echo: hello\n> echo: world\n> 2\n"
+    );
+}
+
+#[test]
+fn seed_option_makes_random_output_reproducible() {
+    let binary = env!("CARGO_BIN_EXE_orangensaft");
+    let script_path = temp_script_path("seeded_random");
+    fs::write(
+        &script_path,
+        "print([random(), rand_int(0, 1000000), shuffle([1, 2, 3, 4, 5, 6]), uuid()])\n",
+    )
+    .expect("failed to write temp script");
+
+    let run_with_seed = |seed: &str| {
+        let output = Command::new(binary)
+            .args([
+                "run",
+                script_path.to_string_lossy().as_ref(),
+                "--provider",
+                "none",
+                "--seed",
+                seed,
+            ])
+            .output()
+            .expect("failed to run orangensaft binary");
+        assert!(
+            output.status.success(),
+            "expected seeded run to succeed, stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let first = run_with_seed("42");
+    let second = run_with_seed("42");
+    let other = run_with_seed("43");
+    let _ = fs::remove_file(&script_path);
+
+    assert_eq!(first, second, "same seed should reproduce output");
+    assert_ne!(first, other, "different seeds should differ");
+}
+
+#[test]
+fn embed_builtin_returns_float_vector() {
+    let source = r#"
+a = embed("the quick brown fox")
+b = embed("the quick brown fox")
+assert type(a) == "list"
+assert len(a) == 64
+assert type(a[0]) == "float"
+assert a == b
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected embed() to work, got {result:?}");
+}
+
+#[test]
+fn embed_builtin_uses_configured_embedding_provider() {
+    let source = r#"
+v = embed("abc")
+assert v == [3.0, 0.5]
+"#;
+
+    let mut runtime = Runtime::new();
+    runtime.set_embedding_provider(Box::new(LengthEmbeddingProvider));
+    let result = run_source_with_runtime(source, &mut runtime);
+    assert!(
+        result.is_ok(),
+        "expected embed() to use configured provider, got {result:?}"
+    );
+}
+
+#[test]
+fn cosine_builtin_scores_vectors() {
+    let source = r#"
+assert cosine([1, 0], [1, 0]) == 1.0
+assert cosine([1, 0], [0, 2]) == 0.0
+assert cosine([1.0, 1.0], [-1.0, -1.0]) < -0.99
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected cosine() to work, got {result:?}");
+}
+
+#[test]
+fn cosine_builtin_rejects_mismatched_dimensions() {
+    let err = run_source("x = cosine([1, 2], [1, 2, 3])").expect_err("mismatch should fail");
+    assert!(
+        err.message.contains("cosine expects vectors of equal length"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn vstore_builtins_support_retrieval() {
+    let source = r#"
+store = vstore()
+vstore_add(store, "fruit", embed("apples and oranges are fruit"), {source: "a.txt"})
+vstore_add(store, "space", embed("rockets fly to outer space"), {source: "b.txt"})
+vstore_add(store, "fruit", embed("oranges are citrus fruit"), {source: "c.txt"})
+assert len(store) == 2
+
+hits = vstore_query(store, embed("which fruit is citrus"), 1)
+assert len(hits) == 1
+assert hits[0].id == "fruit"
+assert hits[0].meta.source == "c.txt"
+assert type(hits[0].score) == "float"
+
+all = vstore_query(store, embed("space rockets"), 5)
+assert len(all) == 2
+assert all[0].id == "space"
+assert all[0].score >= all[1].score
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected vstore builtins to work, got {result:?}");
+}
+
+#[test]
+fn vstore_add_rejects_dimension_mismatch() {
+    let source = r#"
+store = vstore()
+vstore_add(store, "a", [1.0, 0.0], nil)
+vstore_add(store, "b", [1.0, 0.0, 0.0], nil)
+"#;
+
+    let err = run_source(source).expect_err("dimension mismatch should fail");
+    assert!(
+        err.message.contains("vstore_add expects vectors of length 2, got 3"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+struct LengthEmbeddingProvider;
+
+impl EmbeddingProvider for LengthEmbeddingProvider {
+    fn embed(&mut self, text: &str) -> SaftResult<Vec<f64>> {
+        Ok(vec![text.len() as f64, 0.5])
+    }
+}
+
 fn temp_script_path(prefix: &str) -> PathBuf {
     let nanos = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -99,3 +889,236 @@ fn temp_script_path(prefix: &str) -> PathBuf {
         .as_nanos();
     std::env::temp_dir().join(format!("orangensaft_{prefix}_{}_{}.saft", std::process::id(), nanos))
 }
+
+#[test]
+fn date_builtins_parse_format_and_compare() {
+    let source = r#"
+d = parse_date("2024-03-09T14:05:30Z")
+assert d.year == 2024
+assert d.month == 3
+assert d.day == 9
+assert d.hour == 14
+assert d.weekday == "Sat"
+assert d.timestamp == 1709993130
+assert format_date(d) == "2024-03-09T14:05:30Z"
+assert format_date(d, "%d/%m/%Y") == "09/03/2024"
+assert format_date(d.timestamp + 86400, "%Y-%m-%d") == "2024-03-10"
+assert parse_date("2024-03-09").hour == 0
+assert parse_date("09.03.2024", "%d.%m.%Y").timestamp == 1709942400
+assert parse_date("2024-03-09T16:05:30+02:00").timestamp == d.timestamp
+assert parse_date("not a date") == nil
+assert now().timestamp > d.timestamp
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected date builtins to work, got {result:?}");
+}
+
+#[test]
+fn format_date_rejects_invalid_format() {
+    let err = run_source("x = format_date(0, \"%Q\")\n").expect_err("bad format should fail");
+    assert!(
+        err.message.contains("format_date got an invalid format"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn sleep_builtin_waits_and_rejects_negative_durations() {
+    let started = std::time::Instant::now();
+    let result = run_source("assert sleep(0.05) == nil\nsleep(0)\n");
+    assert!(result.is_ok(), "expected sleep() to work, got {result:?}");
+    assert!(started.elapsed() >= std::time::Duration::from_millis(50));
+
+    let err = run_source("sleep(-1)\n").expect_err("negative sleep should fail");
+    assert!(
+        err.message.contains("sleep expects a non-negative number"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn uuid_builtin_returns_distinct_v4_identifiers() {
+    let source = r#"
+a = uuid()
+b = uuid()
+assert a != b
+assert len(a) == 36
+parts = split(a, "-")
+assert [len(parts[0]), len(parts[1]), len(parts[2]), len(parts[3]), len(parts[4])] == [8, 4, 4, 4, 12]
+assert starts_with(parts[2], "4")
+assert contains(["8", "9", "a", "b"], split(parts[3], "")[0])
+assert lower(a) == a
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected uuid() to work, got {result:?}");
+}
+
+#[test]
+fn hash_builtins_match_known_digests() {
+    let long_text = "a".repeat(1000);
+    let source = format!(
+        r#"
+assert sha256("") == "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+assert sha256("abc") == "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+assert md5("") == "d41d8cd98f00b204e9800998ecf8427e"
+assert md5("The quick brown fox jumps over the lazy dog") == "9e107d9d372bb6826bd81d3542a419d6"
+assert sha256("{long_text}") == "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+assert md5("{long_text}") == "cabe45dcc9ae5b66ba86600cca6b8ba8"
+"#
+    );
+
+    let result = run_source(&source);
+    assert!(result.is_ok(), "expected hash builtins to work, got {result:?}");
+}
+
+#[test]
+fn regex_builtins_extract_and_rewrite_text() {
+    let source = r#"
+reply = "Scores: alice=91, bob=78, carol=85"
+m = re_match("(\\w+)=(\\d+)", reply)
+assert m == ["alice=91", "alice", "91"]
+assert re_match("^\\d+$", reply) == nil
+assert re_find_all("\\d+", reply) == ["91", "78", "85"]
+assert re_find_all("(\\w+)=\\d+", reply) == ["alice", "bob", "carol"]
+assert re_find_all("(\\w+)=(\\d+)", reply)[1] == ("bob", "78")
+assert re_replace("(\\w+)=(\\d+)", reply, "$2 ($1)") == "Scores: 91 (alice), 78 (bob), 85 (carol)"
+assert re_replace("(?i)SCORES", reply, "Points") == "Points: alice=91, bob=78, carol=85"
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected regex builtins to work, got {result:?}");
+}
+
+#[test]
+fn regex_builtins_report_invalid_patterns() {
+    let err = run_source("x = re_find_all(\"(unclosed\", \"text\")\n")
+        .expect_err("invalid pattern should fail");
+    assert!(
+        err.message.contains("invalid regex '(unclosed'"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn error_builtin_raises_message_at_call_site() {
+    let source = r#"
+f check_score(score):
+    if score > 100:
+        error("score out of range: " + str(score))
+    ret score
+
+ok = check_score(91)
+bad = check_score(140)
+"#;
+
+    let err = run_source(source).expect_err("error() should fail the script");
+    assert_eq!(err.message, "score out of range: 140");
+    let span = err.span.expect("error() should carry the call site span");
+    assert_eq!((span.line, span.col), (4, 9));
+}
+
+#[test]
+fn builtin_errors_point_at_their_call() {
+    let err = run_source("x = 1\ny = upper(3)\n").expect_err("upper(3) should fail");
+    assert_eq!(err.message, "upper expects string, got int");
+    assert_eq!(err.span.map(|span| span.line), Some(2));
+}
+
+#[test]
+fn type_predicates_match_type_names() {
+    let source = r#"
+f id(x):
+    ret x
+
+assert is_int(1) and not is_int(1.0)
+assert is_float(1.5) and not is_float(1)
+assert is_number(1) and is_number(1.5) and not is_number("1")
+assert is_bool(false) and not is_bool(nil)
+assert is_string("a") and not is_string(["a"])
+assert is_list([]) and not is_list((1, 2))
+assert is_tuple((1, 2)) and not is_tuple([1, 2])
+assert is_object({a: 1}) and not is_object([])
+assert is_function(id) and is_function(upper) and not is_function("id")
+assert is_nil(nil) and not is_nil(0)
+assert is_vstore(vstore()) and not is_dataframe(vstore())
+assert not is_image(nil)
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected type predicates to work, got {result:?}");
+}
+
+#[test]
+fn copy_snapshots_vector_stores() {
+    let source = r#"
+store = vstore()
+vstore_add(store, "a", [1.0, 0.0], {tag: "first"})
+alias = store
+snapshot = copy(store)
+nested = copy({stores: [store], n: 1})
+vstore_add(store, "b", [0.0, 1.0], nil)
+
+assert len(alias) == 2
+assert len(snapshot) == 1
+assert len(nested.stores[0]) == 1
+assert vstore_query(snapshot, [1.0, 0.0], 1)[0].meta == {tag: "first"}
+assert copy([1, (2, 3), {a: [4]}]) == [1, (2, 3), {a: [4]}]
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected copy() to work, got {result:?}");
+}
+
+#[test]
+fn substr_and_char_at_count_unicode_characters() {
+    let source = r#"
+word = "Grüße aus Köln"
+assert substr(word, 0, 5) == "Grüße"
+assert substr(word, 10) == "Köln"
+assert substr(word, 10, 100) == "Köln"
+assert substr(word, 50, 2) == ""
+assert char_at(word, 2) == "ü"
+assert char_at("🍊saft", 0) == "🍊"
+assert char_at("🍊saft", 1) == "s"
+"#;
+
+    let result = run_source(source);
+    assert!(result.is_ok(), "expected substr()/char_at() to work, got {result:?}");
+
+    let err = run_source("x = char_at(\"abc\", 3)\n").expect_err("out of bounds should fail");
+    assert!(
+        err.message
+            .contains("char_at index 3 out of bounds for string of length 3"),
+        "unexpected error: {}",
+        err.message
+    );
+    let err = run_source("x = substr(\"abc\", -1, 2)\n").expect_err("negative start should fail");
+    assert!(
+        err.message.contains("substr expects non-negative start, got -1"),
+        "unexpected error: {}",
+        err.message
+    );
+}
+
+#[test]
+fn every_builtin_documents_its_signature() {
+    for spec in orangensaft::stdlib::BUILTINS {
+        assert!(
+            spec.signature.starts_with(&format!("{}(", spec.name)),
+            "signature of {} should start with its name: {}",
+            spec.name,
+            spec.signature
+        );
+        assert!(
+            spec.doc.ends_with('.') && !spec.doc.contains('\n'),
+            "doc of {} should be one sentence: {}",
+            spec.name,
+            spec.doc
+        );
+    }
+}
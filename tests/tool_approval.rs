@@ -0,0 +1,120 @@
+use std::cell::RefCell;
+use std::io::{Cursor, Write};
+use std::rc::Rc;
+
+use orangensaft::approval::{PromptingToolApprover, ToolApprover};
+use orangensaft::error::SaftResult;
+use orangensaft::fixture::FixtureProvider;
+use orangensaft::provider::ToolCall;
+use orangensaft::run_source_with_runtime;
+use orangensaft::runtime::Runtime;
+
+const FIXTURE: &str = r#"
+{"rules": [
+  {"contains": "delete the build dir", "steps": [
+    {"tool_calls": [{"name": "remove", "args": {"path": "build"}}]},
+    {"text": "done"}
+  ]}
+]}
+"#;
+
+struct RecordingApprover {
+    allow: bool,
+    seen: Rc<RefCell<Vec<String>>>,
+}
+
+impl ToolApprover for RecordingApprover {
+    fn approve(&mut self, call: &ToolCall) -> SaftResult<bool> {
+        self.seen
+            .borrow_mut()
+            .push(format!("{}({})", call.name, call.args));
+        Ok(self.allow)
+    }
+}
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn fixture_runtime() -> Runtime {
+    let provider = FixtureProvider::from_json_str(FIXTURE).expect("fixture should parse");
+    Runtime::with_provider(Box::new(provider))
+}
+
+#[test]
+fn denied_tool_call_is_not_executed() {
+    let source = r#"
+f remove(path):
+    assert false
+    ret path
+
+answer = $
+    Use {remove} to delete the build dir.
+$
+assert answer == "done"
+"#;
+
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let mut runtime = fixture_runtime();
+    runtime.set_tool_approver(Box::new(RecordingApprover {
+        allow: false,
+        seen: seen.clone(),
+    }));
+
+    let result = run_source_with_runtime(source, &mut runtime);
+    assert!(
+        result.is_ok(),
+        "denied call should be skipped, got {result:?}"
+    );
+    assert_eq!(*seen.borrow(), vec![r#"remove({"path":"build"})"#]);
+}
+
+#[test]
+fn prompting_approver_runs_call_after_yes() {
+    let source = r#"
+f remove(path):
+    ret "removed " + path
+
+answer = $
+    Use {remove} to delete the build dir.
+$
+assert answer == "done"
+"#;
+
+    let output = SharedBuffer::default();
+    let mut runtime = fixture_runtime();
+    runtime.set_tool_approver(Box::new(PromptingToolApprover::new(
+        Box::new(Cursor::new("yes\n")),
+        Box::new(output.clone()),
+    )));
+
+    let result = run_source_with_runtime(source, &mut runtime);
+    assert!(result.is_ok(), "approved call should run, got {result:?}");
+    let printed = String::from_utf8(output.0.borrow().clone()).expect("utf8 output");
+    assert!(
+        printed.contains(r#"tool call requested: remove({"path":"build"})"#),
+        "unexpected approval prompt: {printed}"
+    );
+}
+
+#[test]
+fn prompting_approver_denies_on_end_of_input() {
+    let call = ToolCall {
+        id: "call_1".to_string(),
+        name: "remove".to_string(),
+        args: serde_json::json!({"path": "build"}),
+    };
+    let mut approver =
+        PromptingToolApprover::new(Box::new(Cursor::new("")), Box::new(std::io::sink()));
+    assert!(!approver.approve(&call).expect("approval should not error"));
+}
@@ -1,4 +1,12 @@
-use orangensaft::run_source;
+use std::time::{Duration, Instant};
+
+use orangensaft::error::SaftResult;
+use orangensaft::provider::{
+    HeuristicMockProvider, PromptProvider, PromptRequest, PromptResponse, ToolCall,
+};
+use orangensaft::runtime::{Runtime, RuntimeOptions};
+use orangensaft::{check_source, run_source, run_source_with_provider_and_options};
+use serde_json::json;
 
 #[test]
 fn runs_function_map_tool_calling_example() {
@@ -49,3 +57,118 @@ fn runs_single_pair_tool_call_example() {
         "expected single pair tool-call example to run, got {result:?}"
     );
 }
+
+/// Calls the first exposed tool once, then echoes its JSON output as the answer.
+struct EchoToolOutputProvider;
+
+impl PromptProvider for EchoToolOutputProvider {
+    fn complete(&mut self, request: PromptRequest) -> SaftResult<PromptResponse> {
+        match request.tool_results.first() {
+            Some(result) => Ok(PromptResponse::FinalText(result.output.to_string())),
+            None => Ok(PromptResponse::ToolCalls(vec![ToolCall {
+                id: "call_1".to_string(),
+                name: request.tools[0].name.clone(),
+                args: json!({"n": 3}),
+            }])),
+        }
+    }
+}
+
+fn run_with_tool_timeout(source: &str, timeout: Duration) -> SaftResult<()> {
+    let options = RuntimeOptions {
+        tool_timeout: Some(timeout),
+        ..RuntimeOptions::default()
+    };
+    run_source_with_provider_and_options(source, Box::new(EchoToolOutputProvider), options)
+}
+
+#[test]
+fn tool_call_exceeding_timeout_returns_structured_result() {
+    let source = r#"
+f slow(n):
+    ret n * 2
+
+outcome: {error: string, timeout_ms: int} = $
+    Call {slow} with 3.
+$
+assert outcome.error == "tool call timed out"
+assert outcome.timeout_ms == 0
+"#;
+
+    let result = run_with_tool_timeout(source, Duration::ZERO);
+    assert!(
+        result.is_ok(),
+        "expected timeout result to reach the model, got {result:?}"
+    );
+}
+
+#[test]
+fn sleep_inside_a_tool_wakes_at_the_tool_timeout() {
+    let source = r#"
+f slow(n):
+    sleep(60)
+    ret n * 2
+
+outcome: {error: string, timeout_ms: int} = $
+    Call {slow} with 3.
+$
+assert outcome.error == "tool call timed out"
+assert outcome.timeout_ms == 100
+"#;
+
+    let started = Instant::now();
+    let result = run_with_tool_timeout(source, Duration::from_millis(100));
+    assert!(
+        result.is_ok(),
+        "expected the sleeping tool to time out, got {result:?}"
+    );
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn tool_call_within_timeout_returns_output() {
+    let source = r#"
+f slow(n):
+    ret n * 2
+
+outcome: int = $
+    Call {slow} with 3.
+$
+assert outcome == 6
+"#;
+
+    let result = run_with_tool_timeout(source, Duration::from_secs(30));
+    assert!(
+        result.is_ok(),
+        "expected tool call to finish in time, got {result:?}"
+    );
+}
+
+#[test]
+fn profile_counts_statements_functions_and_prompt_rounds() {
+    let source = include_str!("../examples/06_function_map.saft");
+    let program = check_source(source).expect("example should check");
+    let options = RuntimeOptions {
+        profile: true,
+        ..RuntimeOptions::default()
+    };
+    let mut runtime =
+        Runtime::with_provider_and_options(Box::new(HeuristicMockProvider::new()), options);
+    runtime.run_program(&program).expect("example should run");
+
+    let profile = runtime.profile();
+    assert_eq!(profile.prompt_rounds.len(), 1);
+    assert_eq!(profile.prompt_rounds.values().next().map(|entry| entry.count), Some(2));
+    assert_eq!(profile.functions.get("my_func").map(|entry| entry.count), Some(3));
+    assert!(profile.total >= profile.provider_time());
+    let report = profile.report();
+    assert!(report.contains("waiting on providers"), "{report}");
+    assert!(report.contains("my_func()"), "{report}");
+
+    let mut quiet = Runtime::with_provider_and_options(
+        Box::new(HeuristicMockProvider::new()),
+        RuntimeOptions::default(),
+    );
+    quiet.run_program(&program).expect("example should run");
+    assert!(quiet.profile().statements.is_empty());
+}
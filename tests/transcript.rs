@@ -0,0 +1,56 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use orangensaft::provider::HeuristicMockProvider;
+use orangensaft::run_source_with_provider;
+use orangensaft::transcript::TranscriptProvider;
+use serde_json::Value as JsonValue;
+
+#[test]
+fn transcript_records_every_provider_round() {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock should be after unix epoch")
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!(
+        "orangensaft_transcript_{}_{}.jsonl",
+        std::process::id(),
+        nanos
+    ));
+    let path_str = path.to_str().expect("temp path should be utf-8");
+    // A transcript from an earlier run is replaced, not appended to.
+    std::fs::write(&path, "stale\n").expect("failed to seed old transcript");
+
+    let provider = TranscriptProvider::create(path_str, Box::new(HeuristicMockProvider::new()))
+        .expect("transcript file should be created");
+    let source = include_str!("../examples/06_function_map.saft");
+    let result = run_source_with_provider(source, Box::new(provider));
+    assert!(result.is_ok(), "expected example to run, got {result:?}");
+
+    let transcript = std::fs::read_to_string(&path).expect("transcript should be readable");
+    let _ = std::fs::remove_file(&path);
+    let entries = transcript
+        .lines()
+        .map(|line| serde_json::from_str::<JsonValue>(line).expect("line should be JSON"))
+        .collect::<Vec<_>>();
+
+    assert!(
+        entries.len() >= 2,
+        "expected tool round plus final answer, got {entries:?}"
+    );
+    let first = &entries[0];
+    assert_eq!(first["seq"], 1);
+    assert!(first["duration_ms"].is_number());
+    assert!(first["request"]["prompt"].is_string());
+    assert!(!first["request"]["tools"].as_array().unwrap().is_empty());
+    assert!(first["request"]["response_format"].is_object());
+    assert!(first["response"]["tool_calls"].is_array());
+
+    let last = entries.last().unwrap();
+    assert!(last["response"]["final_text"].is_string());
+    assert!(
+        !last["request"]["tool_results"]
+            .as_array()
+            .unwrap()
+            .is_empty()
+    );
+}